@@ -57,6 +57,44 @@ impl<'a> iterative_repair::Problem for Chess<'a> {
     }
 }
 
+///
+/// Allows to use min-conflicts - queens can be moved to any column of the board.
+///
+impl<'a> iterative_repair::Repairable for Chess<'a> {
+    type Value = i32;
+    type Assignment = Vec<(i32, i32)>;
+
+    fn values(&self, _: &Self::Conflict) -> Vec<Self::Value> {
+        (0..WIDTH).collect()
+    }
+
+    fn assign(&mut self, conflict: &Self::Conflict, value: &Self::Value) {
+        self.board.get_mut(&conflict.0).unwrap().x = *value;
+    }
+
+    fn assignment(&self) -> Self::Assignment {
+        (0..self.board.len() as i32)
+            .map(|i| (self.board[&i].x, self.board[&i].y))
+            .collect()
+    }
+
+    fn restore(&mut self, assignment: &Self::Assignment) {
+        for (i, pos) in assignment.iter().enumerate() {
+            let queen = self.board.get_mut(&(i as i32)).unwrap();
+            queen.x = pos.0;
+            queen.y = pos.1;
+        }
+    }
+
+    fn randomize(&mut self, seed: u64) {
+        // one queen per row - shifted around by the seed.
+        for (i, queen) in self.board.values_mut().enumerate() {
+            queen.x = ((seed as usize + i) % WIDTH as usize) as i32;
+            queen.y = i as i32;
+        }
+    }
+}
+
 fn main() -> Result<(), num::ParseIntError> {
     let mut board: collections::HashMap<i32, Queen> = collections::HashMap::new();
     board.insert(0, Queen { x: 0, y: 0 });
@@ -68,6 +106,22 @@ fn main() -> Result<(), num::ParseIntError> {
     let res = iterative_repair::solve(&mut ps, 10);
     println!("Solution found: {}.\nIterations: {}.", res.0, res.1);
 
+    // greedy repairs might oscillate - use min-conflicts with a tabu list & restarts instead.
+    if !res.0 {
+        let config = iterative_repair::Config {
+            strategy: iterative_repair::Strategy::MinConflicts,
+            steps: 10,
+            tabu: 2,
+            restarts: 5,
+            ..Default::default()
+        };
+        let res = iterative_repair::solve_with_config(&mut ps, &config);
+        println!(
+            "Solution found: {}.\nSteps: {} (restarts: {}).",
+            res.found, res.steps, res.restarts
+        );
+    }
+
     for (i, queen) in board {
         println!("Queen {} is now @ ({}, {}).", i, queen.x, queen.y)
     }
@@ -85,11 +85,10 @@ impl planner::SharedStates for Picker {
             data[1].parse::<i32>().unwrap(),
             data[2].parse::<i32>().unwrap(),
         );
-        let mut para: Vec<f64> = vec![];
-        if data.len() > 3 {
-            para.insert(0, data[3].parse::<f64>().unwrap());
-            para.insert(1, data[4].parse::<f64>().unwrap());
-        }
+        let para: Vec<f64> = data[3..]
+            .iter()
+            .map(|x| x.parse::<f64>().unwrap())
+            .collect();
 
         (msg_type, state, para)
     }
@@ -141,16 +140,31 @@ fn main() {
     // Solve this mystery.
     let start = (0, 0);
     let goal = (2, 2);
-    let res = mad_astar::solve(&agent, &ps, start, goal);
+    let config = mad_astar::Config {
+        ring: Some((ps.pick_id as usize, 2)),
+        timeout: Some(30000),
+        ..Default::default()
+    };
+    let res = mad_astar::solve_joint(&agent, &ps, start, goal, &config);
     agent.send_msg(&ep, &agent::Msg::Kill());
     ths.0.join().unwrap();
     ths.1.join().unwrap();
 
-    println!("My part of moving from {:?} to {:?} is:", start, goal);
-    for step in res {
-        print!("{:?} -> ", step);
+    match res {
+        Ok(steps) => {
+            println!("Joint plan for moving from {:?} to {:?} is:", start, goal);
+            for step in steps {
+                let who = if step.agent == ps.pick_id as usize {
+                    "me"
+                } else {
+                    "partner"
+                };
+                println!("{:?} -> {:?} ({})", step.from, step.to, who);
+            }
+            println!("done...");
+        }
+        Err(err) => println!("Could not move from {:?} to {:?}: {}.", start, goal, err),
     }
-    println!("\ndone...");
 }
 
 #[cfg(not(feature = "multi_agent"))]
@@ -0,0 +1,48 @@
+use rusty_planner::iterative_repair;
+use rusty_planner::scheduling;
+
+///
+/// Planes need to land on one of two runways - each plane has a time window in which it can land
+/// and blocks a runway for a while. Heavy planes need more time, and plane 3 can only land after
+/// plane 0 has cleared the runway.
+///
+fn main() {
+    let mut schedule = scheduling::Schedule::new(30);
+    let runways = schedule.add_resource(2);
+
+    let planes = [
+        // (blocking time, earliest landing, latest time clearing the runway)
+        (4, 0, 10),
+        (2, 0, 8),
+        (2, 1, 12),
+        (3, 2, 15),
+        (4, 3, 20),
+        (2, 5, 20),
+    ];
+    for (duration, release, deadline) in planes {
+        schedule.add_task(scheduling::Task {
+            duration,
+            release,
+            deadline,
+            demands: vec![(runways, 1)],
+        });
+    }
+    schedule.add_precedence(0, 3);
+
+    let config = iterative_repair::Config {
+        strategy: iterative_repair::Strategy::MinConflicts,
+        tabu: 2,
+        restarts: 5,
+        ..Default::default()
+    };
+    let res = iterative_repair::solve_with_config(&mut schedule, &config);
+    println!(
+        "Schedule found: {} after {} steps - all planes landed by {}.",
+        res.found,
+        res.steps,
+        schedule.makespan()
+    );
+    for (i, start) in schedule.starts.iter().enumerate() {
+        println!("Plane {} lands @ {}.", i, start);
+    }
+}
@@ -0,0 +1,25 @@
+use rusty_planner::dlx;
+
+const N: usize = 8;
+
+///
+/// The n-queens problem as exact cover - each rank & file needs exactly one queen (primary
+/// columns), each diagonal can hold at most one queen (secondary columns).
+///
+fn main() {
+    let mut matrix = dlx::Matrix::new(2 * N, 2 * (2 * N - 1));
+    let mut squares = Vec::new();
+    for rank in 0..N {
+        for file in 0..N {
+            let diagonal = 2 * N + rank + file;
+            let anti_diagonal = 2 * N + (2 * N - 1) + (N - 1 + rank - file);
+            matrix.add_row(&[rank, N + file, diagonal, anti_diagonal]);
+            squares.push((rank, file));
+        }
+    }
+
+    println!("Number of solutions: {}.", dlx::count(&matrix, None));
+    for row in dlx::solve(&matrix, Some(1)).concat() {
+        println!("Queen @ ({}, {}).", squares[row].0, squares[row].1);
+    }
+}
@@ -13,6 +13,7 @@ fn key(data: &util::StateData, h: f64, eps: f64) -> (f64, f64) {
 }
 
 /// Update the state's information.
+#[allow(clippy::too_many_arguments)]
 fn update_state<PS: planner::ProblemSpace>(
     ps: &PS,
     s: PS::State,
@@ -51,6 +52,7 @@ fn update_state<PS: planner::ProblemSpace>(
 }
 
 /// Compute or improve the path between start & goal.
+#[allow(clippy::too_many_arguments)]
 fn compute_path<PS: planner::ProblemSpace>(
     ps: &PS,
     start: PS::State,
@@ -337,7 +339,7 @@ mod tests {
                 contains = true;
             }
         }
-        assert_eq!(contains, true); // should have been added to list...
+        assert!(contains); // should have been added to list...
 
         closed.push(s);
         any_dyn_astar::update_state(
@@ -351,7 +353,7 @@ mod tests {
             &mut incons,
             1.0,
         );
-        assert_eq!(incons.contains(&s), true);
+        assert!(incons.contains(&s));
     }
 
     #[test]
@@ -0,0 +1,243 @@
+use std::cell;
+use std::sync;
+use std::sync::mpsc;
+use std::time;
+
+use crate::util;
+
+use rusty_agent::agent;
+
+///
+/// Configuration of the in-memory message bus - allows to inject faults.
+///
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Range (in ms) of the random delay before a message gets delivered.
+    pub latency: (u64, u64),
+    /// Probability of a message getting lost - the counts of the termination detection never
+    /// balance then, so searches need a timeout.
+    pub drop_rate: f64,
+    /// Seed for the random latency & drops.
+    pub seed: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            latency: (0, 0),
+            drop_rate: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Message & the time it becomes visible to the receiver.
+type Envelope = (time::Instant, String);
+
+///
+/// Endpoint of an agent on the in-memory message bus - implements `agent::Agent` so it can be used
+/// by all multi-agent algorithms without any external infrastructure.
+///
+pub struct Endpoint {
+    id: usize,
+    senders: Vec<mpsc::Sender<Envelope>>,
+    receiver: mpsc::Receiver<Envelope>,
+    /// Messages received which are not yet due.
+    pending: cell::RefCell<Vec<Envelope>>,
+    config: Config,
+    rng: sync::Arc<sync::Mutex<util::Rng>>,
+}
+
+///
+/// Create a bus connecting the given number of agents - returns an endpoint per agent.
+///
+pub fn connect(n_agents: usize, config: Config) -> Vec<Endpoint> {
+    assert!(
+        config.latency.0 <= config.latency.1,
+        "Latency range is invalid."
+    );
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..n_agents).map(|_| mpsc::channel()).unzip();
+    let rng = sync::Arc::new(sync::Mutex::new(util::Rng::new(config.seed)));
+    receivers
+        .into_iter()
+        .enumerate()
+        .map(|(id, receiver)| Endpoint {
+            id,
+            senders: senders.clone(),
+            receiver,
+            pending: cell::RefCell::new(Vec::new()),
+            config: config.clone(),
+            rng: sync::Arc::clone(&rng),
+        })
+        .collect()
+}
+
+impl Endpoint {
+    /// Id of this agent.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Number of agents on the bus.
+    pub fn n_agents(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Send a message to a single agent.
+    pub fn send(&self, to: usize, msg: &str) {
+        let delay = {
+            let mut rng = self.rng.lock().unwrap();
+            if self.config.drop_rate > 0.0 && rng.next_f64() < self.config.drop_rate {
+                return;
+            }
+            let (min, max) = self.config.latency;
+            min + rng.gen_range((max - min + 1) as usize) as u64
+        };
+        let due = time::Instant::now() + time::Duration::from_millis(delay);
+        // receivers which left the bus are ignored.
+        let _ = self.senders[to].send((due, msg.to_string()));
+    }
+}
+
+impl agent::Agent for Endpoint {
+    fn retrieve(&self) -> Vec<String> {
+        let mut pending = self.pending.borrow_mut();
+        pending.extend(self.receiver.try_iter());
+        let now = time::Instant::now();
+        let mut due: Vec<Envelope> = Vec::new();
+        let mut i = 0;
+        while i < pending.len() {
+            if pending[i].0 <= now {
+                due.push(pending.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        due.sort_by_key(|msg| msg.0);
+        due.into_iter().map(|msg| msg.1).collect()
+    }
+
+    fn broadcast(&self, msg: &str) {
+        for to in 0..self.n_agents() {
+            if to != self.id {
+                self.send(to, msg);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time;
+
+    use crate::bus;
+
+    use rusty_agent::agent::Agent;
+
+    // Test for success.
+
+    #[test]
+    fn test_connect_for_success() {
+        bus::connect(3, bus::Config::default());
+    }
+
+    #[test]
+    fn test_send_for_success() {
+        let endpoints = bus::connect(2, bus::Config::default());
+        endpoints[0].send(1, "foo");
+        endpoints[0].broadcast("bar");
+        endpoints[1].retrieve();
+    }
+
+    // Test for failure.
+
+    #[test]
+    #[should_panic]
+    fn test_connect_for_failure() {
+        let config = bus::Config {
+            latency: (10, 5),
+            ..Default::default()
+        };
+        bus::connect(2, config);
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_connect_for_sanity() {
+        let endpoints = bus::connect(3, bus::Config::default());
+        assert_eq!(endpoints.len(), 3);
+        for (i, endpoint) in endpoints.iter().enumerate() {
+            assert_eq!(endpoint.id(), i);
+            assert_eq!(endpoint.n_agents(), 3);
+        }
+    }
+
+    #[test]
+    fn test_send_for_sanity() {
+        let endpoints = bus::connect(3, bus::Config::default());
+
+        // unicast only reaches the receiver.
+        endpoints[0].send(2, "foo");
+        assert!(endpoints[1].retrieve().is_empty());
+        assert_eq!(endpoints[2].retrieve(), vec!["foo"]);
+
+        // broadcast reaches everyone but the sender - in order.
+        endpoints[1].broadcast("bar");
+        endpoints[1].broadcast("baz");
+        assert!(endpoints[1].retrieve().is_empty());
+        assert_eq!(endpoints[0].retrieve(), vec!["bar", "baz"]);
+        assert_eq!(endpoints[2].retrieve(), vec!["bar", "baz"]);
+
+        // messages are consumed.
+        assert!(endpoints[0].retrieve().is_empty());
+
+        // works across threads.
+        let mut endpoints = endpoints.into_iter();
+        let sender = endpoints.next().unwrap();
+        let receiver = endpoints.next().unwrap();
+        thread::spawn(move || sender.send(1, "qux")).join().unwrap();
+        assert_eq!(receiver.retrieve(), vec!["qux"]);
+    }
+
+    #[test]
+    fn test_latency_for_sanity() {
+        let config = bus::Config {
+            latency: (50, 50),
+            ..Default::default()
+        };
+        let endpoints = bus::connect(2, config);
+        endpoints[0].send(1, "foo");
+
+        // not yet due...
+        assert!(endpoints[1].retrieve().is_empty());
+        thread::sleep(time::Duration::from_millis(60));
+        assert_eq!(endpoints[1].retrieve(), vec!["foo"]);
+    }
+
+    #[test]
+    fn test_drop_rate_for_sanity() {
+        // all get lost.
+        let config = bus::Config {
+            drop_rate: 1.0,
+            ..Default::default()
+        };
+        let endpoints = bus::connect(2, config);
+        endpoints[0].send(1, "foo");
+        assert!(endpoints[1].retrieve().is_empty());
+
+        // some get lost - deterministic given the seed.
+        let config = bus::Config {
+            drop_rate: 0.5,
+            seed: 7,
+            ..Default::default()
+        };
+        let endpoints = bus::connect(2, config);
+        for i in 0..100 {
+            endpoints[0].send(1, &i.to_string());
+        }
+        let n = endpoints[1].retrieve().len();
+        assert!(n > 25 && n < 75);
+    }
+}
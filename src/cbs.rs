@@ -0,0 +1,728 @@
+use std::collections;
+use std::hash;
+
+use crate::cooperative;
+use crate::planner;
+use crate::util;
+
+///
+/// Objective the high-level search minimizes.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Objective {
+    /// Sum of the costs of all paths.
+    SumOfCosts,
+    /// Cost of the most expensive path.
+    Makespan,
+}
+
+///
+/// Configuration for the conflict-based search.
+///
+pub struct Config {
+    /// Objective the high-level search minimizes - the low-level search always minimizes the cost
+    /// of the agent's path.
+    pub objective: Objective,
+    /// Suboptimality bound - 1.0 for (optimal) CBS, larger values enable ECBS.
+    pub suboptimality: f64,
+    /// Time step after which the low-level search gives up.
+    pub max_time: usize,
+    /// Cost of waiting a time step.
+    pub wait_cost: f64,
+    /// Number of constraint tree nodes after which the search gives up.
+    pub max_nodes: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            objective: Objective::SumOfCosts,
+            suboptimality: 1.0,
+            max_time: 100,
+            wait_cost: 1.0,
+            max_nodes: 10000,
+        }
+    }
+}
+
+///
+/// Paths for all agents - the state at index t being the position at time t; once at the end of
+/// its path the agent stays at its goal.
+///
+#[derive(Debug, PartialEq)]
+pub struct Solution<S> {
+    /// Path of each agent - in the order the agents were given.
+    pub paths: Vec<Vec<S>>,
+    /// Objective value of the solution.
+    pub cost: f64,
+    /// Number of constraint tree nodes expanded.
+    pub expanded: usize,
+}
+
+/// Constraints for an agent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Constraint<S> {
+    /// Agent can't be in the state at the given time.
+    Vertex(usize, S, usize),
+    /// Agent can't move from the 1st to the 2nd state starting at the given time.
+    Edge(usize, S, S, usize),
+}
+
+/// Conflicts between two agents.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Conflict<S> {
+    /// Both agents are in the same state at the given time.
+    Vertex(usize, usize, S, usize),
+    /// Agents swap places - the 1st one moving from the 1st to the 2nd state.
+    Edge(usize, usize, S, S, usize),
+}
+
+/// Position of an agent at a given time.
+fn at<S: Copy>(path: &[S], time: usize) -> S {
+    path[time.min(path.len() - 1)]
+}
+
+/// All conflicts between the paths - ordered by time.
+fn find_conflicts<S: Copy + Eq>(paths: &[Vec<S>]) -> Vec<Conflict<S>> {
+    let mut res = Vec::new();
+    let length = paths.iter().map(|p| p.len()).max().unwrap_or(0);
+    for t in 0..length {
+        for a in 0..paths.len() {
+            for b in a + 1..paths.len() {
+                if at(&paths[a], t) == at(&paths[b], t) {
+                    res.push(Conflict::Vertex(a, b, at(&paths[a], t), t));
+                } else if t + 1 < length
+                    && at(&paths[a], t) == at(&paths[b], t + 1)
+                    && at(&paths[a], t + 1) == at(&paths[b], t)
+                {
+                    res.push(Conflict::Edge(
+                        a,
+                        b,
+                        at(&paths[a], t),
+                        at(&paths[a], t + 1),
+                        t,
+                    ));
+                }
+            }
+        }
+    }
+    res
+}
+
+/// Number of conflicts a move of an agent would cause with the paths of the other agents.
+fn count_conflicts<S: Copy + Eq>(
+    agent: usize,
+    paths: &[Vec<S>],
+    from: &S,
+    to: &S,
+    time: usize,
+) -> usize {
+    paths
+        .iter()
+        .enumerate()
+        .filter(|(i, p)| {
+            *i != agent
+                && !p.is_empty()
+                && (at(p, time + 1) == *to || (at(p, time) == *to && at(p, time + 1) == *from))
+        })
+        .count()
+}
+
+///
+/// Open list of a focal search. All nodes within the suboptimality bound of the smallest lower
+/// bound form the focal list, from which the node with the smallest key gets expanded next.
+///
+struct Focal<N> {
+    /// Nodes not expanded yet.
+    nodes: Vec<Option<N>>,
+    /// Keys of the nodes in the focal list.
+    keys: Vec<(f64, f64)>,
+    /// All nodes not expanded yet - by their lower bound.
+    open: collections::BinaryHeap<util::HeapEntry<usize>>,
+    /// Nodes within the bound - by their key.
+    focal: collections::BinaryHeap<util::HeapEntry<usize>>,
+    /// Nodes beyond the bound - by their value.
+    later: collections::BinaryHeap<util::HeapEntry<usize>>,
+    suboptimality: f64,
+}
+
+impl<N> Focal<N> {
+    fn new(suboptimality: f64) -> Focal<N> {
+        Focal {
+            nodes: Vec::new(),
+            keys: Vec::new(),
+            open: collections::BinaryHeap::new(),
+            focal: collections::BinaryHeap::new(),
+            later: collections::BinaryHeap::new(),
+            suboptimality,
+        }
+    }
+
+    /// Add a node - its lower bound, its value which needs to be within the bound & its key.
+    fn push(&mut self, node: N, lower: f64, value: f64, key: (f64, f64)) {
+        let id = self.nodes.len();
+        self.nodes.push(Some(node));
+        self.keys.push(key);
+        self.open.push(util::HeapEntry::new_entry(id, (lower, 0.0)));
+        self.later
+            .push(util::HeapEntry::new_entry(id, (value, 0.0)));
+    }
+
+    /// Node with the smallest key in the focal list & the smallest lower bound of all nodes.
+    fn pop(&mut self) -> Option<(N, f64)> {
+        while self
+            .open
+            .peek()
+            .is_some_and(|top| self.nodes[top.state].is_none())
+        {
+            self.open.pop();
+        }
+        let lower = self.open.peek()?.keys.0;
+        let bound = lower * self.suboptimality + 1e-9;
+        while self.later.peek().is_some_and(|top| top.keys.0 <= bound) {
+            let id = self.later.pop().unwrap().state;
+            self.focal
+                .push(util::HeapEntry::new_entry(id, self.keys[id]));
+        }
+        // nothing within the bound - fall back to the smallest value.
+        let id = match self.focal.pop() {
+            Some(entry) => entry.state,
+            None => self.later.pop()?.state,
+        };
+        Some((self.nodes[id].take().unwrap(), lower))
+    }
+}
+
+/// Node in the low-level search.
+struct LowNode<S> {
+    state: (S, usize),
+    g_val: f64,
+    conflicts: usize,
+}
+
+///
+/// Space-time A* for one agent which obeys its constraints. With a suboptimality bound larger
+/// than 1 a focal search is done - preferring nodes which conflict less with the other paths.
+/// Returns the path, its cost & a lower bound on the cost of the optimal path.
+///
+#[allow(clippy::too_many_arguments)]
+fn low_level<PS: planner::ProblemSpace>(
+    ps: &PS,
+    dist: &mut cooperative::TrueDistance<PS::State>,
+    agent: usize,
+    start: PS::State,
+    goal: PS::State,
+    constraints: &[Constraint<PS::State>],
+    paths: &[Vec<PS::State>],
+    config: &Config,
+) -> Option<(Vec<PS::State>, f64, f64)> {
+    let mut vertices = collections::HashSet::new();
+    let mut edges = collections::HashSet::new();
+    let mut last_goal = None;
+    for constraint in constraints {
+        match *constraint {
+            Constraint::Vertex(a, s, t) if a == agent => {
+                vertices.insert((s, t));
+                if s == goal {
+                    last_goal = last_goal.max(Some(t));
+                }
+            }
+            Constraint::Edge(a, s, s_dash, t) if a == agent => {
+                edges.insert((s, s_dash, t));
+            }
+            _ => {}
+        }
+    }
+    if vertices.contains(&(start, 0)) {
+        return None;
+    }
+
+    let mut open = Focal::new(config.suboptimality);
+    let mut g_vals: collections::HashMap<(PS::State, usize), f64> = collections::HashMap::new();
+    let mut parents: collections::HashMap<(PS::State, usize), (PS::State, usize)> =
+        collections::HashMap::new();
+    let mut closed: collections::HashSet<(PS::State, usize)> = collections::HashSet::new();
+
+    let h_val = dist.distance(ps, &start);
+    if h_val.is_infinite() {
+        return None;
+    }
+    g_vals.insert((start, 0), 0.0);
+    let root = LowNode {
+        state: (start, 0),
+        g_val: 0.0,
+        conflicts: 0,
+    };
+    open.push(root, h_val, h_val, (0.0, h_val));
+
+    // focal list: all nodes within the bound of the best f value - fewest conflicts first.
+    while let Some((node, f_min)) = open.pop() {
+        if closed.contains(&node.state) {
+            continue;
+        }
+        closed.insert(node.state);
+        let (s, t) = node.state;
+
+        if s == goal && last_goal.is_none_or(|l| l < t) {
+            let mut path = vec![s];
+            let mut curr = node.state;
+            while let Some(parent) = parents.get(&curr) {
+                path.push(parent.0);
+                curr = *parent;
+            }
+            path.reverse();
+            return Some((path, node.g_val, f_min));
+        }
+        if t >= config.max_time {
+            continue;
+        }
+
+        let moves = ps.succ(&s).chain(std::iter::once((s, config.wait_cost)));
+        for (s_dash, cost) in moves {
+            let next = (s_dash, t + 1);
+            if closed.contains(&next) || vertices.contains(&next) || edges.contains(&(s, s_dash, t))
+            {
+                continue;
+            }
+            let h_val = dist.distance(ps, &s_dash);
+            if h_val.is_infinite() {
+                continue;
+            }
+            let g_val = node.g_val + cost;
+            if g_val < *g_vals.get(&next).unwrap_or(&f64::INFINITY) {
+                g_vals.insert(next, g_val);
+                parents.insert(next, node.state);
+                let conflicts = node.conflicts + count_conflicts(agent, paths, &s, &s_dash, t);
+                let child = LowNode {
+                    state: next,
+                    g_val,
+                    conflicts,
+                };
+                let f_val = g_val + h_val;
+                open.push(child, f_val, f_val, (conflicts as f64, f_val));
+            }
+        }
+    }
+    None
+}
+
+/// Node of the constraint tree.
+struct HighNode<S> {
+    constraints: Vec<Constraint<S>>,
+    paths: Vec<Vec<S>>,
+    costs: Vec<f64>,
+    bounds: Vec<f64>,
+    conflicts: usize,
+}
+
+impl<S: Copy + Eq> HighNode<S> {
+    fn cost(&self, objective: Objective) -> f64 {
+        match objective {
+            Objective::SumOfCosts => self.costs.iter().sum(),
+            Objective::Makespan => self.costs.iter().copied().fold(0.0, f64::max),
+        }
+    }
+
+    fn lower_bound(&self, objective: Objective) -> f64 {
+        match objective {
+            Objective::SumOfCosts => self.bounds.iter().sum(),
+            Objective::Makespan => self.bounds.iter().copied().fold(0.0, f64::max),
+        }
+    }
+
+    /// Add the node to the open list - CBS expands the cheapest nodes first, ECBS the ones with the
+    /// fewest conflicts within the bound.
+    fn push(self, open: &mut Focal<HighNode<S>>, config: &Config) {
+        let cost = self.cost(config.objective);
+        let conflicts = self.conflicts as f64;
+        if config.suboptimality <= 1.0 {
+            open.push(self, cost, cost, (cost, conflicts));
+        } else {
+            let lower = self.lower_bound(config.objective);
+            open.push(self, lower, cost, (conflicts, cost));
+        }
+    }
+}
+
+///
+/// Conflict-based search (CBS) - finds paths for all agents which do not collide. The high-level
+/// search explores a tree of constraints; each time two agents collide, two branches get added
+/// which forbid one of the agents to be at the given state (or do the given move) at that time.
+/// The low-level search plans each agent on its own while obeying the constraints. With a
+/// suboptimality bound w > 1 both levels do a focal search (ECBS) and the solution costs at most w
+/// times the optimum. Each agent is given as its problem space, start & goal.
+///
+/// # Example
+/// ```
+/// use std::vec;
+///
+/// use rusty_planner::cbs;
+/// use rusty_planner::planner;
+///
+/// // a ring of 4 states.
+/// struct Ring {}
+///
+/// impl planner::ProblemSpace for Ring {
+///     type State = i32;
+///     type Iter = vec::IntoIter<(Self::State, f64)>;
+///
+///     fn heuristic(&self, _: &Self::State, _: &Self::State) -> f64 {
+///         0.0
+///     }
+///
+///     fn succ(&self, state: &Self::State) -> Self::Iter {
+///         vec![((state + 1) % 4, 1.0), ((state + 3) % 4, 1.0)].into_iter()
+///     }
+///
+///     fn pred(&self, state: &Self::State) -> Self::Iter {
+///         self.succ(state)
+///     }
+/// }
+///
+/// // agents would swap places on the short way - one goes the other way round.
+/// let ps = Ring {};
+/// let agents = vec![(&ps, 0, 2), (&ps, 1, 0)];
+/// let res = cbs::solve(&agents, &cbs::Config::default()).unwrap();
+/// assert_eq!(res.paths, vec![vec![0, 3, 2], vec![1, 0]]);
+/// assert_eq!(res.cost, 3.0);
+/// ```
+///
+pub fn solve<PS: planner::ProblemSpace>(
+    agents: &[(&PS, PS::State, PS::State)],
+    config: &Config,
+) -> Option<Solution<PS::State>>
+where
+    PS::State: hash::Hash,
+{
+    let mut dists: Vec<cooperative::TrueDistance<PS::State>> = agents
+        .iter()
+        .map(|a| cooperative::TrueDistance::new(a.2))
+        .collect();
+
+    // root node - everyone on their own.
+    let mut root = HighNode {
+        constraints: Vec::new(),
+        paths: vec![Vec::new(); agents.len()],
+        costs: vec![0.0; agents.len()],
+        bounds: vec![0.0; agents.len()],
+        conflicts: 0,
+    };
+    for (i, (ps, start, goal)) in agents.iter().enumerate() {
+        let (path, cost, bound) = low_level(
+            *ps,
+            &mut dists[i],
+            i,
+            *start,
+            *goal,
+            &[],
+            &root.paths,
+            config,
+        )?;
+        root.paths[i] = path;
+        root.costs[i] = cost;
+        root.bounds[i] = bound;
+    }
+    root.conflicts = find_conflicts(&root.paths).len();
+
+    // focal list: nodes within the bound of the best lower bound.
+    let mut open = Focal::new(config.suboptimality);
+    root.push(&mut open, config);
+    let mut expanded = 0;
+    while expanded < config.max_nodes {
+        let (node, _) = open.pop()?;
+        expanded += 1;
+
+        let conflict = match find_conflicts(&node.paths).first() {
+            None => {
+                return Some(Solution {
+                    cost: node.cost(config.objective),
+                    paths: node.paths,
+                    expanded,
+                })
+            }
+            Some(conflict) => *conflict,
+        };
+        let constraints = match conflict {
+            Conflict::Vertex(a, b, s, t) => {
+                [Constraint::Vertex(a, s, t), Constraint::Vertex(b, s, t)]
+            }
+            Conflict::Edge(a, b, s, s_dash, t) => [
+                Constraint::Edge(a, s, s_dash, t),
+                Constraint::Edge(b, s_dash, s, t),
+            ],
+        };
+        for constraint in constraints {
+            let agent = match constraint {
+                Constraint::Vertex(a, _, _) => a,
+                Constraint::Edge(a, _, _, _) => a,
+            };
+            let mut child = HighNode {
+                constraints: node.constraints.clone(),
+                paths: node.paths.clone(),
+                costs: node.costs.clone(),
+                bounds: node.bounds.clone(),
+                conflicts: 0,
+            };
+            child.constraints.push(constraint);
+            let (ps, start, goal) = agents[agent];
+            if let Some((path, cost, bound)) = low_level(
+                ps,
+                &mut dists[agent],
+                agent,
+                start,
+                goal,
+                &child.constraints,
+                &child.paths,
+                config,
+            ) {
+                child.paths[agent] = path;
+                child.costs[agent] = cost;
+                child.bounds[agent] = bound;
+                child.conflicts = find_conflicts(&child.paths).len();
+                child.push(&mut open, config);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec;
+
+    use crate::cbs;
+    use crate::cooperative;
+    use crate::util;
+
+    type Cell = (i32, i32);
+
+    /// Corridor of 5 cells with a side pocket below the middle one.
+    fn corridor() -> util::Grid {
+        util::Grid {
+            width: 5,
+            height: 2,
+            blocked: vec![(0, 1), (1, 1), (3, 1), (4, 1)],
+        }
+    }
+
+    /// 4 x 4 grid with agents having to cross each other.
+    fn crossing() -> (util::Grid, Vec<(Cell, Cell)>) {
+        let ps = util::Grid {
+            width: 4,
+            height: 4,
+            blocked: vec![(1, 1), (2, 2)],
+        };
+        let tasks = vec![
+            ((0, 0), (3, 3)),
+            ((3, 3), (0, 0)),
+            ((0, 3), (3, 0)),
+            ((3, 0), (0, 3)),
+        ];
+        (ps, tasks)
+    }
+
+    fn low_level(
+        ps: &util::Grid,
+        start: (i32, i32),
+        goal: (i32, i32),
+        constraints: &[cbs::Constraint<(i32, i32)>],
+    ) -> Option<(Vec<Cell>, f64, f64)> {
+        let mut dist = cooperative::TrueDistance::new(goal);
+        let config = cbs::Config::default();
+        cbs::low_level(ps, &mut dist, 0, start, goal, constraints, &[], &config)
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_find_conflicts_for_success() {
+        cbs::find_conflicts(&[vec![1, 2, 3], vec![3, 2, 1]]);
+    }
+
+    #[test]
+    fn test_low_level_for_success() {
+        let ps = corridor();
+        low_level(&ps, (0, 0), (4, 0), &[]);
+    }
+
+    #[test]
+    fn test_solve_for_success() {
+        let ps = corridor();
+        let agents = vec![(&ps, (0, 0), (4, 0)), (&ps, (4, 0), (0, 0))];
+        cbs::solve(&agents, &cbs::Config::default());
+    }
+
+    // Test for failure.
+
+    #[test]
+    fn test_low_level_for_failure() {
+        let ps = corridor();
+        // start forbidden.
+        let res = low_level(
+            &ps,
+            (0, 0),
+            (4, 0),
+            &[cbs::Constraint::Vertex(0, (0, 0), 0)],
+        );
+        assert_eq!(res, None);
+        // goal unreachable.
+        assert_eq!(low_level(&ps, (0, 0), (0, 1), &[]), None);
+    }
+
+    #[test]
+    fn test_solve_for_failure() {
+        // no pocket to step aside.
+        let ps = util::Grid {
+            width: 3,
+            height: 1,
+            blocked: vec![],
+        };
+        let agents = vec![(&ps, (0, 0), (2, 0)), (&ps, (2, 0), (0, 0))];
+        let config = cbs::Config {
+            max_time: 8,
+            max_nodes: 200,
+            ..Default::default()
+        };
+        assert_eq!(cbs::solve(&agents, &config), None);
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_find_conflicts_for_sanity() {
+        // vertex conflict.
+        let res = cbs::find_conflicts(&[vec![1, 2, 3], vec![4, 2, 5]]);
+        assert_eq!(res, vec![cbs::Conflict::Vertex(0, 1, 2, 1)]);
+
+        // agents swap places.
+        let res = cbs::find_conflicts(&[vec![1, 2, 3], vec![4, 3, 2]]);
+        assert_eq!(res, vec![cbs::Conflict::Edge(0, 1, 2, 3, 1)]);
+
+        // agent resting at its goal.
+        let res = cbs::find_conflicts(&[vec![1], vec![3, 2, 1]]);
+        assert_eq!(res, vec![cbs::Conflict::Vertex(0, 1, 1, 2)]);
+
+        // following each other is fine.
+        assert_eq!(cbs::find_conflicts(&[vec![1, 2, 3], vec![0, 1, 2]]), vec![]);
+    }
+
+    #[test]
+    fn test_count_conflicts_for_sanity() {
+        // 3rd agent not planned yet.
+        let paths = vec![vec![1, 2, 3], vec![4, 3, 3], vec![]];
+        assert_eq!(cbs::count_conflicts(0, &paths, &1, &2, 0), 0);
+        assert_eq!(cbs::count_conflicts(0, &paths, &2, &3, 1), 1);
+        assert_eq!(cbs::count_conflicts(2, &paths, &5, &3, 0), 1);
+        assert_eq!(cbs::count_conflicts(2, &paths, &5, &3, 1), 2);
+        // swapping places.
+        assert_eq!(cbs::count_conflicts(1, &paths, &3, &2, 1), 1);
+    }
+
+    #[test]
+    fn test_focal_for_sanity() {
+        // nodes within the bound - fewest conflicts first.
+        let mut open = cbs::Focal::new(1.5);
+        open.push('a', 4.0, 4.0, (2.0, 4.0));
+        open.push('b', 5.0, 5.0, (1.0, 5.0));
+        open.push('c', 7.0, 7.0, (0.0, 7.0));
+        assert_eq!(open.pop(), Some(('b', 4.0)));
+        assert_eq!(open.pop(), Some(('a', 4.0)));
+        assert_eq!(open.pop(), Some(('c', 7.0)));
+        assert_eq!(open.pop(), None);
+
+        // bound grows with the lower bound.
+        let mut open = cbs::Focal::new(1.0);
+        open.push('a', 2.0, 2.0, (1.0, 2.0));
+        open.push('b', 2.0, 2.0, (0.0, 2.0));
+        open.push('c', 3.0, 3.0, (0.0, 3.0));
+        assert_eq!(open.pop(), Some(('b', 2.0)));
+        open.push('d', 3.0, 3.0, (2.0, 3.0));
+        assert_eq!(open.pop(), Some(('a', 2.0)));
+        assert_eq!(open.pop(), Some(('c', 3.0)));
+        assert_eq!(open.pop(), Some(('d', 3.0)));
+
+        // nothing within the bound - smallest value.
+        let mut open = cbs::Focal::new(1.0);
+        open.push('a', 1.0, 3.0, (0.0, 3.0));
+        open.push('b', 2.0, 2.0, (1.0, 2.0));
+        assert_eq!(open.pop(), Some(('b', 1.0)));
+    }
+
+    #[test]
+    fn test_low_level_for_sanity() {
+        let ps = corridor();
+
+        // shortest path.
+        let (path, cost, bound) = low_level(&ps, (0, 0), (2, 0), &[]).unwrap();
+        assert_eq!(path, vec![(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(cost, 2.0);
+        assert_eq!(bound, 2.0);
+
+        // wait a step.
+        let constraints = [cbs::Constraint::Vertex(0, (1, 0), 1)];
+        let (path, cost, _) = low_level(&ps, (0, 0), (2, 0), &constraints).unwrap();
+        assert_eq!(path, vec![(0, 0), (0, 0), (1, 0), (2, 0)]);
+        assert_eq!(cost, 3.0);
+
+        // can't move - so wait.
+        let constraints = [cbs::Constraint::Edge(0, (0, 0), (1, 0), 0)];
+        let (path, _, _) = low_level(&ps, (0, 0), (2, 0), &constraints).unwrap();
+        assert_eq!(path, vec![(0, 0), (0, 0), (1, 0), (2, 0)]);
+
+        // someone else's constraint.
+        let constraints = [cbs::Constraint::Vertex(1, (1, 0), 1)];
+        let (path, _, _) = low_level(&ps, (0, 0), (2, 0), &constraints).unwrap();
+        assert_eq!(path.len(), 3);
+
+        // goal is needed later - so can't rest there yet.
+        let constraints = [cbs::Constraint::Vertex(0, (2, 0), 4)];
+        let (path, cost, _) = low_level(&ps, (0, 0), (2, 0), &constraints).unwrap();
+        assert_eq!(path.len(), 6);
+        assert_eq!(cost, 5.0);
+    }
+
+    #[test]
+    fn test_solve_for_sanity() {
+        // swapping in a corridor - one needs to step into the pocket.
+        let ps = corridor();
+        let agents = vec![(&ps, (0, 0), (4, 0)), (&ps, (4, 0), (0, 0))];
+        let res = cbs::solve(&agents, &cbs::Config::default()).unwrap();
+        assert_eq!(res.cost, 11.0);
+        assert_eq!(cbs::find_conflicts(&res.paths), vec![]);
+        let config = cbs::Config {
+            objective: cbs::Objective::Makespan,
+            ..Default::default()
+        };
+        let res = cbs::solve(&agents, &config).unwrap();
+        assert_eq!(res.cost, 6.0);
+        assert_eq!(cbs::find_conflicts(&res.paths), vec![]);
+
+        // cooperative pathfinding can't do this one.
+        let coop = cooperative::solve(&agents, &cooperative::Config::default());
+        assert_eq!(coop, None);
+
+        // crossing agents.
+        let (ps, tasks) = crossing();
+        let agents: Vec<(&util::Grid, Cell, Cell)> =
+            tasks.iter().map(|t| (&ps, t.0, t.1)).collect();
+        let res = cbs::solve(&agents, &cbs::Config::default()).unwrap();
+        assert_eq!(cbs::find_conflicts(&res.paths), vec![]);
+        for (i, path) in res.paths.iter().enumerate() {
+            assert_eq!(path[0], tasks[i].0);
+            assert_eq!(*path.last().unwrap(), tasks[i].1);
+        }
+        // nobody needs to wait or detour - the sum of the distances.
+        let optimum = res.cost;
+        assert_eq!(optimum, 24.0);
+
+        // ECBS - bounded suboptimal.
+        let config = cbs::Config {
+            suboptimality: 1.5,
+            ..Default::default()
+        };
+        let res = cbs::solve(&agents, &config).unwrap();
+        assert_eq!(cbs::find_conflicts(&res.paths), vec![]);
+        assert!(res.cost >= optimum);
+        assert!(res.cost <= 1.5 * optimum);
+    }
+}
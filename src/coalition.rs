@@ -0,0 +1,423 @@
+///
+/// A coalition - bit i is set if agent i is a member.
+///
+pub type Coalition = u64;
+
+///
+/// Public trait which - once implemented - describes a characteristic function game.
+///
+pub trait Game {
+    /// Number of agents - at most 64.
+    fn n_agents(&self) -> usize;
+    /// Value a coalition of agents can achieve on its own.
+    fn value(&self, _: Coalition) -> f64;
+}
+
+///
+/// Result of a coalition structure search.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    /// Best coalition structure found - the coalitions partition the set of agents.
+    pub structure: Vec<Coalition>,
+    /// Sum of the values of the coalitions in the structure.
+    pub value: f64,
+    /// Worst case guarantee - the optimal structure is at most bound times better.
+    pub bound: f64,
+    /// Number of coalition structures (or coalitions for the exact solver) evaluated.
+    pub evaluated: usize,
+}
+
+///
+/// Ids of the agents within a coalition.
+///
+pub fn members(coalition: Coalition) -> Vec<usize> {
+    (0..64).filter(|i| coalition & (1 << i) != 0).collect()
+}
+
+fn grand_coalition(n_agents: usize) -> Coalition {
+    assert!(n_agents <= 64, "At most 64 agents are supported.");
+    if n_agents == 64 {
+        u64::MAX
+    } else {
+        (1 << n_agents) - 1
+    }
+}
+
+/// Bound after the levels n down to level (from the top) have been searched.
+fn level_bound(n_agents: usize, level: usize) -> f64 {
+    if level <= 3 {
+        // all levels have been searched.
+        return 1.0;
+    }
+    let h = (n_agents - level) / 2 + 2;
+    if n_agents % h == h - 1 && n_agents % 2 == level % 2 {
+        n_agents.div_ceil(h) as f64
+    } else {
+        (n_agents / h) as f64
+    }
+}
+
+/// Call back for all partitions of the agents into exactly k coalitions; stops if the callback
+/// returns false.
+fn partitions<F: FnMut(&[Coalition]) -> bool>(n_agents: usize, k: usize, callback: &mut F) -> bool {
+    fn assign<F: FnMut(&[Coalition]) -> bool>(
+        agent: usize,
+        n_agents: usize,
+        k: usize,
+        blocks: &mut Vec<Coalition>,
+        callback: &mut F,
+    ) -> bool {
+        if agent == n_agents {
+            return callback(blocks);
+        }
+        // put the agent into one of the existing coalitions...
+        if n_agents - agent > k - blocks.len() {
+            for i in 0..blocks.len() {
+                blocks[i] |= 1 << agent;
+                let proceed = assign(agent + 1, n_agents, k, blocks, callback);
+                blocks[i] &= !(1 << agent);
+                if !proceed {
+                    return false;
+                }
+            }
+        }
+        // ...or let it start a new one.
+        if blocks.len() < k {
+            blocks.push(1 << agent);
+            let proceed = assign(agent + 1, n_agents, k, blocks, callback);
+            blocks.pop();
+            if !proceed {
+                return false;
+            }
+        }
+        true
+    }
+    if k == 0 || k > n_agents {
+        return true;
+    }
+    assign(0, n_agents, k, &mut Vec::with_capacity(k), callback)
+}
+
+///
+/// Anytime coalition structure generation - first searches the bottom two levels of the
+/// coalition structure graph (which guarantees a bound of n), followed by a breadth-first search
+/// from the top which tightens the bound level by level. Stops once the given number of
+/// structures has been evaluated; the bottom two levels are always searched.
+///
+/// *Note*: The bound is only guaranteed for games with non-negative values.
+///
+/// # Example
+/// ```
+/// use rusty_planner::coalition;
+///
+/// struct Pairs {}
+///
+/// impl coalition::Game for Pairs {
+///     fn n_agents(&self) -> usize {
+///         4
+///     }
+///     // pairs of agents work best together.
+///     fn value(&self, coalition: coalition::Coalition) -> f64 {
+///         match coalition.count_ones() {
+///             2 => 3.0,
+///             n => n as f64,
+///         }
+///     }
+/// }
+///
+/// let res = coalition::solve(&Pairs {}, None);
+/// assert_eq!(res.value, 6.0);
+/// assert_eq!(res.bound, 1.0);
+/// ```
+///
+pub fn solve<G: Game>(game: &G, budget: Option<usize>) -> Report {
+    let n_agents = game.n_agents();
+    let all = grand_coalition(n_agents);
+    let mut res = Report {
+        structure: vec![all],
+        value: game.value(all),
+        bound: n_agents as f64,
+        evaluated: 1,
+    };
+    if n_agents == 0 {
+        res.structure.clear();
+        res.value = 0.0;
+        res.bound = 1.0;
+        return res;
+    }
+    let evaluate = |structure: &[Coalition], res: &mut Report| {
+        let value: f64 = structure.iter().map(|c| game.value(*c)).sum();
+        res.evaluated += 1;
+        if value > res.value {
+            res.value = value;
+            res.structure = structure.to_vec();
+        }
+    };
+
+    // bottom two levels.
+    partitions(n_agents, 2, &mut |structure| {
+        evaluate(structure, &mut res);
+        true
+    });
+    if n_agents <= 2 {
+        res.bound = 1.0;
+    }
+
+    // top-down breadth-first search.
+    for level in (3..=n_agents).rev() {
+        let complete = partitions(n_agents, level, &mut |structure| {
+            if budget.is_some_and(|b| res.evaluated >= b) {
+                return false;
+            }
+            evaluate(structure, &mut res);
+            true
+        });
+        if !complete {
+            break;
+        }
+        res.bound = level_bound(n_agents, level);
+    }
+    res.structure.sort_unstable();
+    res
+}
+
+///
+/// Find the optimal coalition structure using dynamic programming - evaluates all 3^n splits of
+/// coalitions, so only feasible for small numbers of agents.
+///
+pub fn solve_optimal<G: Game>(game: &G) -> Report {
+    let n_agents = game.n_agents();
+    assert!(n_agents <= 24, "Too many agents for the exact solver.");
+    let size = 1usize << n_agents;
+    let mut best = vec![0.0; size];
+    let mut split: Vec<usize> = vec![0; size];
+    let mut evaluated = 0;
+    for coalition in 1..size {
+        best[coalition] = game.value(coalition as Coalition);
+        evaluated += 1;
+        // iterate over all subsets containing the lowest member - each split is only seen once.
+        let low = coalition & coalition.wrapping_neg();
+        let rest = coalition & !low;
+        let mut sub = rest;
+        loop {
+            let first = sub | low;
+            if first != coalition {
+                let value = best[first] + best[coalition & !first];
+                if value > best[coalition] {
+                    best[coalition] = value;
+                    split[coalition] = first;
+                }
+            }
+            if sub == 0 {
+                break;
+            }
+            sub = (sub - 1) & rest;
+        }
+    }
+
+    let mut structure = Vec::new();
+    let mut todo = vec![size - 1];
+    while let Some(coalition) = todo.pop() {
+        if coalition == 0 {
+            continue;
+        }
+        if split[coalition] == 0 {
+            structure.push(coalition as Coalition);
+        } else {
+            todo.push(split[coalition]);
+            todo.push(coalition & !split[coalition]);
+        }
+    }
+    structure.sort_unstable();
+    Report {
+        structure,
+        value: best[size - 1],
+        bound: 1.0,
+        evaluated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coalition;
+
+    /// Agents gain by working in coalitions of size 2 - larger ones get inefficient.
+    struct Pairs {
+        n: usize,
+    }
+
+    impl coalition::Game for Pairs {
+        fn n_agents(&self) -> usize {
+            self.n
+        }
+
+        fn value(&self, coalition: coalition::Coalition) -> f64 {
+            match coalition.count_ones() {
+                2 => 3.0,
+                n => n as f64,
+            }
+        }
+    }
+
+    /// Random looking, non-negative values for each coalition.
+    struct Table {
+        n: usize,
+    }
+
+    impl coalition::Game for Table {
+        fn n_agents(&self) -> usize {
+            self.n
+        }
+
+        fn value(&self, coalition: coalition::Coalition) -> f64 {
+            ((coalition * 7919 + 13) % 31) as f64 * coalition.count_ones() as f64
+        }
+    }
+
+    /// Count the structures with k coalitions.
+    fn n_partitions(n: usize, k: usize) -> usize {
+        let mut res = 0;
+        coalition::partitions(n, k, &mut |_| {
+            res += 1;
+            true
+        });
+        res
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_members_for_success() {
+        coalition::members(5);
+    }
+
+    #[test]
+    fn test_partitions_for_success() {
+        coalition::partitions(4, 2, &mut |_| true);
+    }
+
+    #[test]
+    fn test_solve_for_success() {
+        coalition::solve(&Pairs { n: 5 }, None);
+        coalition::solve(&Pairs { n: 5 }, Some(20));
+    }
+
+    #[test]
+    fn test_solve_optimal_for_success() {
+        coalition::solve_optimal(&Pairs { n: 5 });
+    }
+
+    // Test for failure.
+
+    #[test]
+    #[should_panic]
+    fn test_solve_for_failure() {
+        coalition::solve(&Pairs { n: 65 }, None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_solve_optimal_for_failure() {
+        coalition::solve_optimal(&Pairs { n: 40 });
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_members_for_sanity() {
+        assert_eq!(coalition::members(0), Vec::<usize>::new());
+        assert_eq!(coalition::members(0b1011), vec![0, 1, 3]);
+        assert_eq!(coalition::members(1 << 63), vec![63]);
+    }
+
+    #[test]
+    fn test_level_bound_for_sanity() {
+        // after the top level the bound roughly halves.
+        assert_eq!(coalition::level_bound(6, 6), 3.0);
+        assert_eq!(coalition::level_bound(7, 7), 4.0);
+        assert_eq!(coalition::level_bound(8, 6), 3.0);
+        assert_eq!(coalition::level_bound(8, 5), 2.0);
+        assert_eq!(coalition::level_bound(8, 3), 1.0);
+    }
+
+    #[test]
+    fn test_partitions_for_sanity() {
+        // stirling numbers of the 2nd kind.
+        assert_eq!(n_partitions(4, 1), 1);
+        assert_eq!(n_partitions(4, 2), 7);
+        assert_eq!(n_partitions(4, 3), 6);
+        assert_eq!(n_partitions(5, 3), 25);
+        assert_eq!(n_partitions(3, 4), 0);
+
+        // each structure partitions the agents.
+        coalition::partitions(5, 3, &mut |structure| {
+            assert_eq!(structure.iter().fold(0, |a, c| a | c), 0b11111);
+            assert_eq!(structure.iter().map(|c| c.count_ones()).sum::<u32>(), 5);
+            true
+        });
+
+        // stops early.
+        let mut seen = 0;
+        assert!(!coalition::partitions(5, 3, &mut |_| {
+            seen += 1;
+            seen < 4
+        }));
+        assert_eq!(seen, 4);
+    }
+
+    #[test]
+    fn test_solve_for_sanity() {
+        // full search.
+        let res = coalition::solve(&Pairs { n: 4 }, None);
+        assert_eq!(res.value, 6.0);
+        assert_eq!(res.bound, 1.0);
+        assert_eq!(res.evaluated, 15);
+        assert_eq!(res.structure.len(), 2);
+
+        // only bottom two levels.
+        let res = coalition::solve(&Pairs { n: 6 }, Some(1));
+        assert_eq!(res.bound, 6.0);
+        assert_eq!(res.evaluated, 32);
+        assert_eq!(res.value, 7.0);
+
+        // top level as well.
+        let res = coalition::solve(&Pairs { n: 6 }, Some(33));
+        assert_eq!(res.bound, 3.0);
+        assert_eq!(res.evaluated, 33);
+
+        // anytime - value never gets worse and ends with the optimum.
+        let optimum = coalition::solve_optimal(&Table { n: 6 }).value;
+        let mut last = 0.0;
+        for budget in [1, 40, 100, 150, 200, 300] {
+            let res = coalition::solve(&Table { n: 6 }, Some(budget));
+            assert!(res.value >= last);
+            assert!(res.value * res.bound >= optimum);
+            last = res.value;
+        }
+        assert_eq!(last, optimum);
+
+        // trivial games.
+        let res = coalition::solve(&Pairs { n: 0 }, None);
+        assert_eq!(res.structure.len(), 0);
+        let res = coalition::solve(&Pairs { n: 1 }, None);
+        assert_eq!(res.structure, vec![1]);
+        assert_eq!(res.bound, 1.0);
+    }
+
+    #[test]
+    fn test_solve_optimal_for_sanity() {
+        let res = coalition::solve_optimal(&Pairs { n: 5 });
+        assert_eq!(res.value, 7.0);
+        assert_eq!(res.structure.len(), 3);
+        assert_eq!(res.structure.iter().fold(0, |a, c| a | c), 0b11111);
+
+        for n in 1..7 {
+            let game = Table { n };
+            assert_eq!(
+                coalition::solve_optimal(&game).value,
+                coalition::solve(&game, None).value
+            );
+        }
+    }
+}
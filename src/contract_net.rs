@@ -0,0 +1,382 @@
+use std::thread;
+use std::time;
+
+use rusty_agent::agent;
+
+///
+/// Configuration of the contract net protocol.
+///
+pub struct Config {
+    /// Time (in ms) the manager waits for bids after announcing a task.
+    pub bid_timeout: u64,
+    /// Time (in ms) between checking for new messages.
+    pub poll_interval: u64,
+    /// Number of times a task gets announced before giving up on it.
+    pub max_rounds: u32,
+    /// Stop waiting for bids once this many have been received.
+    pub expected_bids: Option<usize>,
+    /// Time (in ms) a contractor waits for new messages before giving up.
+    pub idle_timeout: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bid_timeout: 500,
+            poll_interval: 10,
+            max_rounds: 3,
+            expected_bids: None,
+            idle_timeout: 5000,
+        }
+    }
+}
+
+///
+/// Outcome of announcing a task.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Award {
+    /// Index of the task in the list of tasks announced.
+    pub task: usize,
+    /// Name of the contractor which won the task - None if no one bid.
+    pub winner: Option<String>,
+    /// Cost of the winning bid.
+    pub cost: f64,
+    /// Number of times the task was announced.
+    pub rounds: u32,
+}
+
+/// Messages exchanged between the manager & the contractors.
+#[derive(Clone, Debug, PartialEq)]
+enum Message {
+    Announce(usize, u32, String),
+    Bid(usize, u32, String, f64),
+    Award(usize, String, f64),
+    Done,
+}
+
+impl Message {
+    fn encode(&self) -> String {
+        match self {
+            Message::Announce(task, round, payload) => {
+                format!("announce;{};{};{}", task, round, payload)
+            }
+            Message::Bid(task, round, bidder, cost) => {
+                format!("bid;{};{};{};{}", task, round, cost, bidder)
+            }
+            Message::Award(task, winner, cost) => format!("award;{};{};{}", task, cost, winner),
+            Message::Done => String::from("done"),
+        }
+    }
+
+    /// Decode a message - None if it is not part of the protocol.
+    fn decode(msg: &str) -> Option<Message> {
+        let (kind, rest) = msg.split_once(';').unwrap_or((msg, ""));
+        match kind {
+            "announce" => {
+                let data: Vec<&str> = rest.splitn(3, ';').collect();
+                Some(Message::Announce(
+                    data[0].parse().ok()?,
+                    data.get(1)?.parse().ok()?,
+                    data.get(2)?.to_string(),
+                ))
+            }
+            "bid" => {
+                let data: Vec<&str> = rest.splitn(4, ';').collect();
+                Some(Message::Bid(
+                    data[0].parse().ok()?,
+                    data.get(1)?.parse().ok()?,
+                    data.get(3)?.to_string(),
+                    data.get(2)?.parse().ok()?,
+                ))
+            }
+            "award" => {
+                let data: Vec<&str> = rest.splitn(3, ';').collect();
+                Some(Message::Award(
+                    data[0].parse().ok()?,
+                    data.get(2)?.to_string(),
+                    data.get(1)?.parse().ok()?,
+                ))
+            }
+            "done" => Some(Message::Done),
+            _ => None,
+        }
+    }
+}
+
+/// Announce a task & collect bids until the timeout or enough bids have been received.
+fn collect_bids<A: agent::Agent>(
+    agent: &A,
+    task: usize,
+    round: u32,
+    payload: &str,
+    config: &Config,
+) -> Vec<(String, f64)> {
+    agent.broadcast(&Message::Announce(task, round, payload.to_string()).encode());
+    let deadline = time::Instant::now() + time::Duration::from_millis(config.bid_timeout);
+    let mut bids = Vec::new();
+    while time::Instant::now() < deadline && config.expected_bids.is_none_or(|n| bids.len() < n) {
+        let revd = agent.retrieve();
+        if revd.is_empty() {
+            thread::sleep(time::Duration::from_millis(config.poll_interval));
+        }
+        for msg in revd {
+            if let Some(Message::Bid(t, r, bidder, cost)) = Message::decode(&msg) {
+                if t == task && r == round {
+                    bids.push((bidder, cost));
+                }
+            }
+        }
+    }
+    bids
+}
+
+///
+/// Manager side of the contract net protocol - announces the tasks one after another, awards each
+/// to the cheapest bidder and re-announces tasks no one bid on. Signals the contractors that it
+/// is done at the end.
+///
+pub fn manage<A: agent::Agent>(agent: &A, tasks: &[String], config: &Config) -> Vec<Award> {
+    let mut res = Vec::new();
+    for (task, payload) in tasks.iter().enumerate() {
+        let mut award = Award {
+            task,
+            winner: None,
+            cost: f64::INFINITY,
+            rounds: 0,
+        };
+        while award.winner.is_none() && award.rounds < config.max_rounds {
+            let bids = collect_bids(agent, task, award.rounds, payload, config);
+            award.rounds += 1;
+            // cheapest bid wins - ties go to the first one received.
+            for (bidder, cost) in bids {
+                if cost < award.cost {
+                    award.winner = Some(bidder);
+                    award.cost = cost;
+                }
+            }
+        }
+        if let Some(winner) = &award.winner {
+            agent.broadcast(&Message::Award(task, winner.clone(), award.cost).encode());
+        }
+        res.push(award);
+    }
+    agent.broadcast(&Message::Done.encode());
+    res
+}
+
+///
+/// Contractor side of the contract net protocol - bids on announced tasks using the cost function
+/// (None means no bid) until the manager is done or nothing was heard for a while. Returns the
+/// tasks (id & description) awarded to this contractor.
+///
+pub fn participate<A: agent::Agent, F: FnMut(&str) -> Option<f64>>(
+    agent: &A,
+    name: &str,
+    mut cost: F,
+    config: &Config,
+) -> Vec<(usize, String)> {
+    let mut res = Vec::new();
+    let mut bids: Vec<(usize, String)> = Vec::new();
+    let mut last_seen = time::Instant::now();
+    while last_seen.elapsed() < time::Duration::from_millis(config.idle_timeout) {
+        let revd = agent.retrieve();
+        if revd.is_empty() {
+            thread::sleep(time::Duration::from_millis(config.poll_interval));
+            continue;
+        }
+        last_seen = time::Instant::now();
+        for msg in revd {
+            match Message::decode(&msg) {
+                Some(Message::Announce(task, round, payload)) => {
+                    if let Some(c) = cost(&payload) {
+                        agent.broadcast(&Message::Bid(task, round, name.to_string(), c).encode());
+                        bids.retain(|b| b.0 != task);
+                        bids.push((task, payload));
+                    }
+                }
+                Some(Message::Award(task, winner, _)) if winner == name => {
+                    if let Some(i) = bids.iter().position(|b| b.0 == task) {
+                        res.push(bids.remove(i));
+                    }
+                }
+                Some(Message::Done) => return res,
+                _ => {}
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::bus;
+    use crate::contract_net;
+
+    use rusty_agent::agent;
+
+    fn config() -> contract_net::Config {
+        contract_net::Config {
+            bid_timeout: 100,
+            poll_interval: 1,
+            idle_timeout: 2000,
+            ..Default::default()
+        }
+    }
+
+    /// Robots at given positions on a line - cost is the distance to the task's position.
+    fn robot(
+        agent: bus::Endpoint,
+        name: &str,
+        position: f64,
+    ) -> thread::JoinHandle<Vec<(usize, String)>> {
+        let name = name.to_string();
+        thread::spawn(move || {
+            contract_net::participate(
+                &agent,
+                &name,
+                |task: &str| task.parse::<f64>().ok().map(|x| (x - position).abs()),
+                &config(),
+            )
+        })
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_encode_for_success() {
+        contract_net::Message::Done.encode();
+    }
+
+    #[test]
+    fn test_manage_for_success() {
+        let mut agents = bus::connect(1, bus::Config::default());
+        contract_net::manage(&agents.remove(0), &[String::from("foo")], &config());
+    }
+
+    #[test]
+    fn test_participate_for_success() {
+        let mut agents = bus::connect(2, bus::Config::default());
+        let contractor = agents.remove(1);
+        agent::Agent::broadcast(&agents[0], &contract_net::Message::Done.encode());
+        contract_net::participate(&contractor, "bar", |_| Some(1.0), &config());
+    }
+
+    // Test for failure.
+
+    #[test]
+    fn test_decode_for_failure() {
+        assert_eq!(contract_net::Message::decode("foo"), None);
+        assert_eq!(contract_net::Message::decode("bid;1;2"), None);
+        assert_eq!(contract_net::Message::decode("award;x;1.0;a"), None);
+        assert_eq!(contract_net::Message::decode(""), None);
+    }
+
+    #[test]
+    fn test_manage_for_failure() {
+        // no one to bid.
+        let mut agents = bus::connect(1, bus::Config::default());
+        let config = contract_net::Config {
+            bid_timeout: 10,
+            max_rounds: 2,
+            ..Default::default()
+        };
+        let res = contract_net::manage(&agents.remove(0), &[String::from("foo")], &config);
+        assert_eq!(res[0].winner, None);
+        assert_eq!(res[0].rounds, 2);
+    }
+
+    #[test]
+    fn test_participate_for_failure() {
+        // manager never shows up.
+        let mut agents = bus::connect(1, bus::Config::default());
+        let config = contract_net::Config {
+            idle_timeout: 20,
+            ..Default::default()
+        };
+        let res = contract_net::participate(&agents.remove(0), "foo", |_| Some(1.0), &config);
+        assert_eq!(res.len(), 0);
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_decode_for_sanity() {
+        let msgs = vec![
+            contract_net::Message::Announce(1, 2, String::from("pick;up")),
+            contract_net::Message::Bid(3, 0, String::from("robot"), 1.5),
+            contract_net::Message::Award(3, String::from("robot"), 1.5),
+            contract_net::Message::Done,
+        ];
+        for msg in msgs {
+            assert_eq!(contract_net::Message::decode(&msg.encode()), Some(msg));
+        }
+    }
+
+    #[test]
+    fn test_manage_for_sanity() {
+        let mut agents = bus::connect(3, bus::Config::default());
+        let r_1 = robot(agents.pop().unwrap(), "r_1", 10.0);
+        let r_0 = robot(agents.pop().unwrap(), "r_0", 0.0);
+        let config = contract_net::Config {
+            expected_bids: Some(2),
+            ..config()
+        };
+
+        // tasks go to the closest robot - the last one nobody can do.
+        let tasks: Vec<String> = vec!["2.0", "9.0", "4.0", "n/a"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let res = contract_net::manage(&agents[0], &tasks, &config);
+        let winners: Vec<Option<String>> = res.iter().map(|a| a.winner.clone()).collect();
+        assert_eq!(
+            winners,
+            vec![
+                Some(String::from("r_0")),
+                Some(String::from("r_1")),
+                Some(String::from("r_0")),
+                None
+            ]
+        );
+        assert_eq!(res[1].cost, 1.0);
+        assert_eq!(res[3].rounds, 3);
+
+        let won_0 = r_0.join().unwrap();
+        let won_1 = r_1.join().unwrap();
+        assert_eq!(
+            won_0,
+            vec![(0, String::from("2.0")), (2, String::from("4.0"))]
+        );
+        assert_eq!(won_1, vec![(1, String::from("9.0"))]);
+    }
+
+    #[test]
+    fn test_participate_for_sanity() {
+        // only bids once it is ready - task gets re-announced.
+        let mut agents = bus::connect(2, bus::Config::default());
+        let contractor = agents.pop().unwrap();
+        let handle = thread::spawn(move || {
+            let mut calls = 0;
+            contract_net::participate(
+                &contractor,
+                "busy",
+                |_| {
+                    calls += 1;
+                    if calls > 1 {
+                        Some(1.0)
+                    } else {
+                        None
+                    }
+                },
+                &config(),
+            )
+        });
+        let res = contract_net::manage(&agents[0], &[String::from("foo")], &config());
+        assert_eq!(res[0].winner, Some(String::from("busy")));
+        assert_eq!(res[0].rounds, 2);
+        assert_eq!(handle.join().unwrap(), vec![(0, String::from("foo"))]);
+    }
+}
@@ -0,0 +1,572 @@
+use std::collections;
+use std::hash;
+
+use crate::planner;
+use crate::util;
+
+///
+/// Configuration for the cooperative pathfinding.
+///
+pub struct Config {
+    /// Depth of the space-time search (WHCA*) - None plans all the way to the goal (HCA*). With a
+    /// window half of it gets executed before replanning.
+    pub window: Option<usize>,
+    /// Time step after which the search gives up.
+    pub max_time: usize,
+    /// Cost of waiting a time step.
+    pub wait_cost: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            window: None,
+            max_time: 100,
+            wait_cost: 1.0,
+        }
+    }
+}
+
+///
+/// Space-time reservation table - states reserved by agents at given time steps.
+///
+pub struct ReservationTable<S> {
+    vertices: collections::HashSet<(S, usize)>,
+    /// Moves from the 1st to the 2nd state starting at the given time.
+    edges: collections::HashSet<(S, S, usize)>,
+    /// States occupied from the given time on - e.g. by agents resting at their goal.
+    resting: collections::HashMap<S, usize>,
+    /// Latest time a state is reserved at.
+    last: collections::HashMap<S, usize>,
+}
+
+impl<S: Copy + Eq + hash::Hash> ReservationTable<S> {
+    pub fn new() -> ReservationTable<S> {
+        ReservationTable {
+            vertices: collections::HashSet::new(),
+            edges: collections::HashSet::new(),
+            resting: collections::HashMap::new(),
+            last: collections::HashMap::new(),
+        }
+    }
+
+    /// Reserve a path starting at the given time - if rest is set the last state stays reserved.
+    pub fn reserve(&mut self, path: &[S], start: usize, rest: bool) {
+        for (i, state) in path.iter().enumerate() {
+            self.vertices.insert((*state, start + i));
+            let last = self.last.entry(*state).or_insert(start + i);
+            *last = (*last).max(start + i);
+            if i + 1 < path.len() {
+                self.edges.insert((*state, path[i + 1], start + i));
+            }
+        }
+        if let (true, Some(state)) = (rest, path.last()) {
+            self.resting.insert(*state, start + path.len() - 1);
+        }
+    }
+
+    /// Check if a state is free at a given time.
+    pub fn is_free(&self, state: &S, time: usize) -> bool {
+        !self.vertices.contains(&(*state, time))
+            && self.resting.get(state).is_none_or(|t| time < *t)
+    }
+
+    /// Check if an agent can move between two states starting at the given time - the target
+    /// needs to be free & no one can be moving the opposite direction.
+    pub fn can_move(&self, from: &S, to: &S, time: usize) -> bool {
+        self.is_free(to, time + 1) && !self.edges.contains(&(*to, *from, time))
+    }
+
+    /// Check if a state stays free from the given time on.
+    pub fn is_free_after(&self, state: &S, time: usize) -> bool {
+        self.is_free(state, time)
+            && !self.resting.contains_key(state)
+            && self.last.get(state).is_none_or(|t| *t < time)
+    }
+}
+
+impl<S: Copy + Eq + hash::Hash> Default for ReservationTable<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///
+/// True distance heuristic - a backwards search from the goal (using pred) which is resumed
+/// whenever the distance of a state not yet seen is requested.
+///
+pub struct TrueDistance<S> {
+    open: collections::BinaryHeap<util::HeapEntry<S>>,
+    dist: collections::HashMap<S, f64>,
+    closed: collections::HashSet<S>,
+}
+
+impl<S: Copy + Eq + hash::Hash> TrueDistance<S> {
+    pub fn new(goal: S) -> TrueDistance<S> {
+        let mut open = collections::BinaryHeap::new();
+        open.push(util::HeapEntry::new_entry(goal, (0.0, 0.0)));
+        let mut dist = collections::HashMap::new();
+        dist.insert(goal, 0.0);
+        TrueDistance {
+            open,
+            dist,
+            closed: collections::HashSet::new(),
+        }
+    }
+
+    /// Cost of the cheapest path from the state to the goal - infinity if unreachable.
+    pub fn distance<PS: planner::ProblemSpace<State = S>>(&mut self, ps: &PS, state: &S) -> f64 {
+        if self.closed.contains(state) {
+            return self.dist[state];
+        }
+        while let Some(entry) = self.open.pop() {
+            let s = entry.state;
+            if self.closed.contains(&s) {
+                continue;
+            }
+            self.closed.insert(s);
+            let d = self.dist[&s];
+            for (pred, cost) in ps.pred(&s) {
+                if d + cost < *self.dist.get(&pred).unwrap_or(&f64::INFINITY) {
+                    self.dist.insert(pred, d + cost);
+                    self.open
+                        .push(util::HeapEntry::new_entry(pred, (d + cost, 0.0)));
+                }
+            }
+            if s == *state {
+                return d;
+            }
+        }
+        f64::INFINITY
+    }
+}
+
+///
+/// Space-time A* for one agent - avoids the reservations made by other agents. With a window the
+/// search stops at the given depth and uses the true distance as remaining cost; otherwise it
+/// stops at the goal once no one else will need it later on.
+///
+fn search<PS: planner::ProblemSpace>(
+    ps: &PS,
+    dist: &mut TrueDistance<PS::State>,
+    table: &ReservationTable<PS::State>,
+    start: PS::State,
+    goal: PS::State,
+    t_0: usize,
+    config: &Config,
+) -> Option<Vec<PS::State>> {
+    let mut open: collections::BinaryHeap<util::HeapEntry<(PS::State, usize)>> =
+        collections::BinaryHeap::new();
+    let mut g_vals: collections::HashMap<(PS::State, usize), f64> = collections::HashMap::new();
+    let mut parents: collections::HashMap<(PS::State, usize), (PS::State, usize)> =
+        collections::HashMap::new();
+    let mut closed: collections::HashSet<(PS::State, usize)> = collections::HashSet::new();
+
+    let h_val = dist.distance(ps, &start);
+    if h_val.is_infinite() {
+        return None;
+    }
+    g_vals.insert((start, t_0), 0.0);
+    open.push(util::HeapEntry::new_entry((start, t_0), (h_val, h_val)));
+
+    while let Some(entry) = open.pop() {
+        let node = entry.state;
+        if closed.contains(&node) {
+            continue;
+        }
+        closed.insert(node);
+        let (s, t) = node;
+        let done = match config.window {
+            Some(window) => t >= t_0 + window,
+            None => s == goal && table.is_free_after(&s, t),
+        };
+        if done {
+            let mut path = vec![s];
+            let mut curr = node;
+            while let Some(parent) = parents.get(&curr) {
+                path.push(parent.0);
+                curr = *parent;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if config.window.is_none() && t >= config.max_time {
+            continue;
+        }
+
+        // waiting at the goal is for free within a window.
+        let wait_cost = if s == goal && config.window.is_some() {
+            0.0
+        } else {
+            config.wait_cost
+        };
+        let moves = ps.succ(&s).chain(std::iter::once((s, wait_cost)));
+        for (s_dash, cost) in moves {
+            let next = (s_dash, t + 1);
+            if closed.contains(&next) || !table.can_move(&s, &s_dash, t) {
+                continue;
+            }
+            let h_val = dist.distance(ps, &s_dash);
+            if h_val.is_infinite() {
+                continue;
+            }
+            let g_val = g_vals[&node] + cost;
+            if g_val < *g_vals.get(&next).unwrap_or(&f64::INFINITY) {
+                g_vals.insert(next, g_val);
+                parents.insert(next, node);
+                open.push(util::HeapEntry::new_entry(next, (g_val + h_val, h_val)));
+            }
+        }
+    }
+    None
+}
+
+/// Remove the states at the end of a path in which the agent just keeps waiting at its goal.
+fn trim<S: Eq>(path: &mut Vec<S>, goal: &S) {
+    while path.len() > 1 && path[path.len() - 1] == *goal && path[path.len() - 2] == *goal {
+        path.pop();
+    }
+}
+
+///
+/// Cooperative pathfinding (HCA*/WHCA*) - plans the agents one after another through space-time,
+/// each avoiding the paths reserved by the agents before it. Each agent is given as its problem
+/// space, start & goal. Returns the path for each agent - the state at index t being the position
+/// at time t; once at the end of its path the agent stays at its goal. None is returned if an
+/// agent can not reach its goal in time.
+///
+/// # Example
+/// ```
+/// use std::vec;
+///
+/// use rusty_planner::cooperative;
+/// use rusty_planner::planner;
+///
+/// // a line of 3 states - plus a side pocket (3) next to the middle one.
+/// struct Corridor {}
+///
+/// impl planner::ProblemSpace for Corridor {
+///     type State = i32;
+///     type Iter = vec::IntoIter<(Self::State, f64)>;
+///
+///     fn heuristic(&self, _: &Self::State, _: &Self::State) -> f64 {
+///         0.0
+///     }
+///
+///     fn succ(&self, state: &Self::State) -> Self::Iter {
+///         match *state {
+///             0 => vec![(1, 1.0)].into_iter(),
+///             1 => vec![(0, 1.0), (2, 1.0), (3, 1.0)].into_iter(),
+///             2 => vec![(1, 1.0)].into_iter(),
+///             _ => vec![(1, 1.0)].into_iter(),
+///         }
+///     }
+///
+///     fn pred(&self, state: &Self::State) -> Self::Iter {
+///         self.succ(state)
+///     }
+/// }
+///
+/// let ps = Corridor {};
+/// let agents = vec![(&ps, 0, 2), (&ps, 3, 0)];
+/// let paths = cooperative::solve(&agents, &cooperative::Config::default()).unwrap();
+/// assert_eq!(paths[0], vec![0, 1, 2]);
+/// // 2nd agent waits for the 1st one to pass.
+/// assert_eq!(paths[1], vec![3, 3, 1, 0]);
+/// ```
+///
+pub fn solve<PS: planner::ProblemSpace>(
+    agents: &[(&PS, PS::State, PS::State)],
+    config: &Config,
+) -> Option<Vec<Vec<PS::State>>> {
+    let mut dists: Vec<TrueDistance<PS::State>> =
+        agents.iter().map(|a| TrueDistance::new(a.2)).collect();
+    match config.window {
+        None => {
+            let mut table = ReservationTable::new();
+            let mut res = Vec::new();
+            for (i, (ps, start, goal)) in agents.iter().enumerate() {
+                let path = search(*ps, &mut dists[i], &table, *start, *goal, 0, config)?;
+                table.reserve(&path, 0, true);
+                res.push(path);
+            }
+            Some(res)
+        }
+        Some(window) => {
+            let window = window.max(1);
+            let step = (window / 2).max(1);
+            let mut res: Vec<Vec<PS::State>> = agents.iter().map(|a| vec![a.1]).collect();
+            let mut time = 0;
+            while agents.iter().enumerate().any(|(i, a)| res[i][time] != a.2) {
+                if time >= config.max_time {
+                    return None;
+                }
+                // plan the next window; then execute half of it.
+                let mut table = ReservationTable::new();
+                let mut partial = Vec::new();
+                for (i, (ps, _, goal)) in agents.iter().enumerate() {
+                    let path = search(
+                        *ps,
+                        &mut dists[i],
+                        &table,
+                        res[i][time],
+                        *goal,
+                        time,
+                        config,
+                    )?;
+                    table.reserve(&path, time, false);
+                    partial.push(path);
+                }
+                for (i, path) in partial.iter().enumerate() {
+                    res[i].extend_from_slice(&path[1..=step]);
+                }
+                time += step;
+            }
+            for (i, path) in res.iter_mut().enumerate() {
+                trim(path, &agents[i].2);
+            }
+            Some(res)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec;
+
+    use crate::cooperative;
+    use crate::util;
+
+    /// 5 x 3 grid with a wall in the middle row - but one gap.
+    fn grid() -> util::Grid {
+        util::Grid {
+            width: 5,
+            height: 3,
+            blocked: vec![(0, 1), (1, 1), (3, 1), (4, 1)],
+        }
+    }
+
+    /// Count vertex & swap collisions between the paths - agents rest at the end of their path.
+    fn collisions(paths: &[Vec<(i32, i32)>]) -> usize {
+        let length = paths.iter().map(|p| p.len()).max().unwrap_or(0);
+        let at = |p: &Vec<(i32, i32)>, t: usize| p[t.min(p.len() - 1)];
+        let mut res = 0;
+        for t in 0..length {
+            for i in 0..paths.len() {
+                for j in i + 1..paths.len() {
+                    if at(&paths[i], t) == at(&paths[j], t) {
+                        res += 1;
+                    }
+                    if t + 1 < length
+                        && at(&paths[i], t) == at(&paths[j], t + 1)
+                        && at(&paths[i], t + 1) == at(&paths[j], t)
+                    {
+                        res += 1;
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_reserve_for_success() {
+        let mut table = cooperative::ReservationTable::new();
+        table.reserve(&[1, 2, 3], 0, true);
+    }
+
+    #[test]
+    fn test_distance_for_success() {
+        let ps = grid();
+        let mut dist = cooperative::TrueDistance::new((0, 0));
+        dist.distance(&ps, &(4, 2));
+    }
+
+    #[test]
+    fn test_search_for_success() {
+        let ps = grid();
+        let mut dist = cooperative::TrueDistance::new((4, 2));
+        let table = cooperative::ReservationTable::new();
+        let config = cooperative::Config::default();
+        cooperative::search(&ps, &mut dist, &table, (0, 0), (4, 2), 0, &config);
+    }
+
+    #[test]
+    fn test_solve_for_success() {
+        let ps = grid();
+        let agents = vec![(&ps, (0, 0), (4, 2)), (&ps, (4, 2), (0, 0))];
+        cooperative::solve(&agents, &cooperative::Config::default());
+    }
+
+    // Test for failure.
+
+    #[test]
+    fn test_distance_for_failure() {
+        let ps = grid();
+        let mut dist = cooperative::TrueDistance::new((0, 0));
+        assert!(dist.distance(&ps, &(1, 1)).is_infinite());
+    }
+
+    #[test]
+    fn test_search_for_failure() {
+        let ps = grid();
+        let mut dist = cooperative::TrueDistance::new((4, 2));
+        let config = cooperative::Config::default();
+
+        // gap blocked forever.
+        let mut table = cooperative::ReservationTable::new();
+        table.reserve(&[(2, 1)], 0, true);
+        let res = cooperative::search(&ps, &mut dist, &table, (0, 0), (4, 2), 0, &config);
+        assert_eq!(res, None);
+
+        // goal unreachable.
+        let mut dist = cooperative::TrueDistance::new((1, 1));
+        let table = cooperative::ReservationTable::new();
+        let res = cooperative::search(&ps, &mut dist, &table, (0, 0), (1, 1), 0, &config);
+        assert_eq!(res, None);
+    }
+
+    #[test]
+    fn test_solve_for_failure() {
+        // two agents can't pass each other in a corridor.
+        let ps = util::Grid {
+            width: 3,
+            height: 1,
+            blocked: vec![],
+        };
+        let agents = vec![(&ps, (0, 0), (2, 0)), (&ps, (2, 0), (0, 0))];
+        let config = cooperative::Config {
+            max_time: 10,
+            ..Default::default()
+        };
+        assert_eq!(cooperative::solve(&agents, &config), None);
+        let config = cooperative::Config {
+            window: Some(4),
+            max_time: 10,
+            ..Default::default()
+        };
+        assert_eq!(cooperative::solve(&agents, &config), None);
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_reserve_for_sanity() {
+        let mut table = cooperative::ReservationTable::new();
+        table.reserve(&[1, 2, 3], 2, true);
+        assert!(table.is_free(&1, 1));
+        assert!(!table.is_free(&1, 2));
+        assert!(!table.is_free(&2, 3));
+        assert!(table.is_free(&3, 3));
+        assert!(!table.is_free(&3, 4));
+        assert!(!table.is_free(&3, 40));
+
+        // no swapping places.
+        assert!(!table.can_move(&2, &1, 2));
+        assert!(table.can_move(&2, &1, 3));
+        assert!(table.can_move(&4, &1, 3));
+        assert!(!table.can_move(&4, &2, 2));
+
+        // states needed later on.
+        assert!(table.is_free_after(&1, 3));
+        assert!(!table.is_free_after(&1, 1));
+        assert!(!table.is_free_after(&3, 1));
+        assert!(table.is_free_after(&5, 0));
+    }
+
+    #[test]
+    fn test_distance_for_sanity() {
+        let ps = grid();
+        let mut dist = cooperative::TrueDistance::new((0, 0));
+        assert_eq!(dist.distance(&ps, &(0, 0)), 0.0);
+        // needs to go through the gap - manhattan distance would be 2.
+        assert_eq!(dist.distance(&ps, &(0, 2)), 6.0);
+        assert_eq!(dist.distance(&ps, &(4, 2)), 6.0);
+        // already known.
+        assert_eq!(dist.distance(&ps, &(2, 1)), 3.0);
+    }
+
+    #[test]
+    fn test_search_for_sanity() {
+        let ps = grid();
+        let mut dist = cooperative::TrueDistance::new((2, 2));
+        let config = cooperative::Config::default();
+
+        // shortest path.
+        let table = cooperative::ReservationTable::new();
+        let res = cooperative::search(&ps, &mut dist, &table, (2, 0), (2, 2), 0, &config);
+        assert_eq!(res, Some(vec![(2, 0), (2, 1), (2, 2)]));
+
+        // gap reserved for a while --> need to wait.
+        let mut table = cooperative::ReservationTable::new();
+        table.reserve(&[(2, 1), (2, 1)], 1, false);
+        let res = cooperative::search(&ps, &mut dist, &table, (2, 0), (2, 2), 0, &config).unwrap();
+        assert_eq!(res.len(), 5);
+        assert_eq!(res[3], (2, 1));
+
+        // goal used later by someone else --> pass through & come back.
+        let mut table = cooperative::ReservationTable::new();
+        table.reserve(&[(2, 2)], 3, false);
+        let res = cooperative::search(&ps, &mut dist, &table, (2, 0), (2, 2), 0, &config).unwrap();
+        assert_eq!(res.len(), 5);
+        assert_eq!(res[2], (2, 2));
+        assert_ne!(res[3], (2, 2));
+
+        // windowed search ends after the window.
+        let config = cooperative::Config {
+            window: Some(4),
+            ..Default::default()
+        };
+        let table = cooperative::ReservationTable::new();
+        let res = cooperative::search(&ps, &mut dist, &table, (2, 0), (2, 2), 3, &config);
+        assert_eq!(res, Some(vec![(2, 0), (2, 1), (2, 2), (2, 2), (2, 2)]));
+    }
+
+    #[test]
+    fn test_trim_for_sanity() {
+        let mut path = vec![1, 2, 2, 3, 3, 3];
+        cooperative::trim(&mut path, &3);
+        assert_eq!(path, vec![1, 2, 2, 3]);
+        let mut path = vec![3, 3];
+        cooperative::trim(&mut path, &3);
+        assert_eq!(path, vec![3]);
+    }
+
+    #[test]
+    fn test_solve_for_sanity() {
+        let ps = grid();
+        let agents = vec![
+            (&ps, (0, 0), (4, 2)),
+            (&ps, (4, 2), (0, 0)),
+            (&ps, (4, 0), (0, 2)),
+        ];
+
+        // HCA*.
+        let paths = cooperative::solve(&agents, &cooperative::Config::default()).unwrap();
+        assert_eq!(collisions(&paths), 0);
+        assert_eq!(paths[0].len(), 7);
+        for (i, path) in paths.iter().enumerate() {
+            assert_eq!(path[0], agents[i].1);
+            assert_eq!(*path.last().unwrap(), agents[i].2);
+        }
+
+        // WHCA*.
+        let config = cooperative::Config {
+            window: Some(4),
+            ..Default::default()
+        };
+        let paths = cooperative::solve(&agents, &config).unwrap();
+        assert_eq!(collisions(&paths), 0);
+        for (i, path) in paths.iter().enumerate() {
+            assert_eq!(path[0], agents[i].1);
+            assert_eq!(*path.last().unwrap(), agents[i].2);
+        }
+
+        // nothing to do.
+        let agents = vec![(&ps, (0, 0), (0, 0))];
+        let paths = cooperative::solve(&agents, &config).unwrap();
+        assert_eq!(paths, vec![vec![(0, 0)]]);
+    }
+}
@@ -0,0 +1,408 @@
+///
+/// Sparse matrix for exact cover problems - stored as toroidal doubly linked lists. Node 0 is the
+/// root, nodes 1..=n_columns are the column headers, all other nodes are the 1s of the rows.
+///
+/// Primary columns need to be covered exactly once; secondary columns at most once.
+///
+/// # Example
+/// ```
+/// use rusty_planner::dlx;
+///
+/// // example from Knuth's paper.
+/// let mut matrix = dlx::Matrix::new(7, 0);
+/// matrix.add_row(&[2, 4, 5]);
+/// matrix.add_row(&[0, 3, 6]);
+/// matrix.add_row(&[1, 2, 5]);
+/// matrix.add_row(&[0, 3]);
+/// matrix.add_row(&[1, 6]);
+/// matrix.add_row(&[3, 4, 6]);
+///
+/// let res = dlx::solve(&matrix, None);
+/// assert_eq!(res, vec![vec![0, 3, 4]]);
+/// ```
+///
+#[derive(Clone)]
+pub struct Matrix {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    col: Vec<usize>,
+    row: Vec<usize>,
+    size: Vec<usize>,
+    n_rows: usize,
+}
+
+impl Matrix {
+    /// Create a new matrix - columns 0..n_primary are primary columns, the following n_secondary
+    /// columns are secondary ones.
+    pub fn new(n_primary: usize, n_secondary: usize) -> Matrix {
+        let n_columns = n_primary + n_secondary;
+        let mut matrix = Matrix {
+            left: Vec::new(),
+            right: Vec::new(),
+            up: Vec::new(),
+            down: Vec::new(),
+            col: Vec::new(),
+            row: Vec::new(),
+            size: vec![0; n_columns + 1],
+            n_rows: 0,
+        };
+        for i in 0..=n_columns {
+            matrix.left.push(i);
+            matrix.right.push(i);
+            matrix.up.push(i);
+            matrix.down.push(i);
+            matrix.col.push(i);
+            matrix.row.push(usize::MAX);
+        }
+        // only primary columns are linked into the header list - secondary ones are never chosen.
+        for i in 1..=n_primary {
+            matrix.left[i] = i - 1;
+            matrix.right[i - 1] = i;
+        }
+        matrix.left[0] = n_primary;
+        matrix.right[n_primary] = 0;
+        matrix
+    }
+
+    /// Add a row covering the given columns; returns the id of the row.
+    pub fn add_row(&mut self, columns: &[usize]) -> usize {
+        let id = self.n_rows;
+        self.n_rows += 1;
+        let mut columns = columns.to_vec();
+        columns.sort_unstable();
+        columns.dedup();
+        let first = self.col.len();
+        for (i, column) in columns.iter().enumerate() {
+            assert!(*column < self.size.len() - 1, "Column out of range.");
+            let header = column + 1;
+            let node = first + i;
+            self.col.push(header);
+            self.row.push(id);
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+            self.left.push(if i == 0 { node } else { node - 1 });
+            self.right.push(first);
+            if i > 0 {
+                self.right[node - 1] = node;
+                self.left[first] = node;
+            }
+        }
+        id
+    }
+
+    /// Number of rows added so far.
+    pub fn n_rows(&self) -> usize {
+        self.n_rows
+    }
+
+    /// Iterate over all solutions - each one being the sorted ids of the rows chosen.
+    pub fn solutions(&self) -> Solutions {
+        Solutions {
+            matrix: self.clone(),
+            stack: Vec::new(),
+            step: Step::Enter,
+        }
+    }
+
+    /// Remove a column from the header list & all rows using it from the other columns.
+    fn cover(&mut self, c: usize) {
+        let (l, r) = (self.left[c], self.right[c]);
+        self.right[l] = r;
+        self.left[r] = l;
+        let mut i = self.down[c];
+        while i != c {
+            let mut j = self.right[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = d;
+                self.up[d] = u;
+                self.size[self.col[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    /// Undo cover - in reverse order.
+    fn uncover(&mut self, c: usize) {
+        let mut i = self.up[c];
+        while i != c {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.col[j]] += 1;
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = j;
+                self.up[d] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        let (l, r) = (self.left[c], self.right[c]);
+        self.right[l] = c;
+        self.left[r] = c;
+    }
+
+    /// Primary column with the fewest rows left.
+    fn choose_column(&self) -> usize {
+        let mut res = self.right[0];
+        let mut c = self.right[res];
+        while c != 0 {
+            if self.size[c] < self.size[res] {
+                res = c;
+            }
+            c = self.right[c];
+        }
+        res
+    }
+}
+
+enum Step {
+    Enter,
+    Try,
+    Leave,
+    Done,
+}
+
+///
+/// Iterator over the solutions of an exact cover problem - Algorithm X w/o recursion so solutions
+/// can be produced lazily.
+///
+pub struct Solutions {
+    matrix: Matrix,
+    /// Node of the row chosen on each level.
+    stack: Vec<usize>,
+    step: Step,
+}
+
+impl Iterator for Solutions {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let m = &mut self.matrix;
+        loop {
+            match self.step {
+                Step::Enter => {
+                    if m.right[0] == 0 {
+                        self.step = Step::Leave;
+                        let mut res: Vec<usize> = self.stack.iter().map(|x| m.row[*x]).collect();
+                        res.sort_unstable();
+                        return Some(res);
+                    }
+                    let c = m.choose_column();
+                    m.cover(c);
+                    self.stack.push(m.down[c]);
+                    self.step = Step::Try;
+                }
+                Step::Try => {
+                    let x = *self.stack.last().unwrap();
+                    if m.col[x] == x {
+                        // all rows of this column tried.
+                        m.uncover(x);
+                        self.stack.pop();
+                        self.step = Step::Leave;
+                    } else {
+                        let mut j = m.right[x];
+                        while j != x {
+                            m.cover(m.col[j]);
+                            j = m.right[j];
+                        }
+                        self.step = Step::Enter;
+                    }
+                }
+                Step::Leave => match self.stack.last_mut() {
+                    None => self.step = Step::Done,
+                    Some(x) => {
+                        let mut j = m.left[*x];
+                        while j != *x {
+                            m.uncover(m.col[j]);
+                            j = m.left[j];
+                        }
+                        *x = m.down[*x];
+                        self.step = Step::Try;
+                    }
+                },
+                Step::Done => return None,
+            }
+        }
+    }
+}
+
+///
+/// Solve an exact cover problem - returns up to limit solutions (or all if no limit is given).
+///
+pub fn solve(matrix: &Matrix, limit: Option<usize>) -> Vec<Vec<usize>> {
+    match limit {
+        Some(n) => matrix.solutions().take(n).collect(),
+        None => matrix.solutions().collect(),
+    }
+}
+
+///
+/// Count the solutions of an exact cover problem - stops counting once the limit is reached.
+///
+pub fn count(matrix: &Matrix, limit: Option<usize>) -> usize {
+    match limit {
+        Some(n) => matrix.solutions().take(n).count(),
+        None => matrix.solutions().count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dlx;
+
+    /// Example from Knuth's paper - single solution.
+    fn knuth() -> dlx::Matrix {
+        let mut matrix = dlx::Matrix::new(7, 0);
+        for row in [
+            vec![2, 4, 5],
+            vec![0, 3, 6],
+            vec![1, 2, 5],
+            vec![0, 3],
+            vec![1, 6],
+            vec![3, 4, 6],
+        ] {
+            matrix.add_row(&row);
+        }
+        matrix
+    }
+
+    /// Domino tilings of a 2 x n board - column per cell.
+    fn dominoes(n: usize) -> dlx::Matrix {
+        let mut matrix = dlx::Matrix::new(2 * n, 0);
+        for i in 0..n {
+            matrix.add_row(&[i, n + i]);
+            if i + 1 < n {
+                matrix.add_row(&[i, i + 1]);
+                matrix.add_row(&[n + i, n + i + 1]);
+            }
+        }
+        matrix
+    }
+
+    /// n-queens - ranks & files are primary, diagonals are secondary columns.
+    fn queens(n: usize) -> dlx::Matrix {
+        let mut matrix = dlx::Matrix::new(2 * n, 2 * (2 * n - 1));
+        for r in 0..n {
+            for f in 0..n {
+                let diag = 2 * n + r + f;
+                let anti = 2 * n + (2 * n - 1) + (n - 1 + r - f);
+                matrix.add_row(&[r, n + f, diag, anti]);
+            }
+        }
+        matrix
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_add_row_for_success() {
+        let mut matrix = dlx::Matrix::new(3, 1);
+        matrix.add_row(&[0, 3]);
+    }
+
+    #[test]
+    fn test_solutions_for_success() {
+        let matrix = knuth();
+        for _ in matrix.solutions() {}
+    }
+
+    #[test]
+    fn test_solve_for_success() {
+        let matrix = dominoes(4);
+        dlx::solve(&matrix, None);
+        dlx::solve(&matrix, Some(2));
+    }
+
+    #[test]
+    fn test_count_for_success() {
+        let matrix = queens(4);
+        dlx::count(&matrix, None);
+    }
+
+    // Test for failure.
+
+    #[test]
+    #[should_panic]
+    fn test_add_row_for_failure() {
+        let mut matrix = dlx::Matrix::new(2, 1);
+        matrix.add_row(&[3]);
+    }
+
+    #[test]
+    fn test_solve_for_failure() {
+        // column 2 can't be covered.
+        let mut matrix = dlx::Matrix::new(3, 0);
+        matrix.add_row(&[0, 1]);
+        matrix.add_row(&[1]);
+        assert_eq!(dlx::solve(&matrix, None).len(), 0);
+
+        // rows overlap.
+        let mut matrix = dlx::Matrix::new(3, 0);
+        matrix.add_row(&[0, 1]);
+        matrix.add_row(&[1, 2]);
+        assert_eq!(dlx::solve(&matrix, None).len(), 0);
+
+        // 3 queens can not be placed on a 3 x 3 board.
+        assert_eq!(dlx::count(&queens(3), None), 0);
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_add_row_for_sanity() {
+        let mut matrix = dlx::Matrix::new(2, 0);
+        assert_eq!(matrix.add_row(&[1, 0, 1]), 0);
+        assert_eq!(matrix.add_row(&[]), 1);
+        assert_eq!(matrix.n_rows(), 2);
+
+        // duplicate columns are ignored.
+        assert_eq!(dlx::solve(&matrix, None), vec![vec![0]]);
+    }
+
+    #[test]
+    fn test_solutions_for_sanity() {
+        let mut res = dlx::Matrix::new(0, 2).solutions();
+        assert_eq!(res.next(), Some(vec![]));
+        assert_eq!(res.next(), None);
+
+        let mut res = knuth().solutions();
+        assert_eq!(res.next(), Some(vec![0, 3, 4]));
+        assert_eq!(res.next(), None);
+        assert_eq!(res.next(), None);
+    }
+
+    #[test]
+    fn test_solve_for_sanity() {
+        // fibonacci number of tilings.
+        let res = dlx::solve(&dominoes(4), None);
+        assert_eq!(res.len(), 5);
+        for solution in res.iter() {
+            assert_eq!(solution.len(), 4);
+        }
+        assert_eq!(dlx::solve(&dominoes(6), Some(3)).len(), 3);
+
+        // secondary columns need not be covered - but at most once.
+        let mut matrix = dlx::Matrix::new(2, 1);
+        matrix.add_row(&[0, 2]);
+        matrix.add_row(&[1, 2]);
+        matrix.add_row(&[1]);
+        assert_eq!(dlx::solve(&matrix, None), vec![vec![0, 2]]);
+    }
+
+    #[test]
+    fn test_count_for_sanity() {
+        assert_eq!(dlx::count(&queens(4), None), 2);
+        assert_eq!(dlx::count(&queens(6), None), 4);
+        assert_eq!(dlx::count(&queens(8), None), 92);
+        assert_eq!(dlx::count(&queens(8), Some(10)), 10);
+        assert_eq!(dlx::count(&dominoes(10), None), 89);
+        assert_eq!(dlx::count(&dominoes(10), Some(0)), 0);
+    }
+}
@@ -327,7 +327,7 @@ mod tests {
         // s not visited...
         let s = 1;
         dstar_lite::update_state(&mut ps, s, start, goal, &mut data, &mut open);
-        assert_eq!(data.contains_key(&s), true);
+        assert!(data.contains_key(&s));
 
         // s != goal
         let s = 2;
@@ -341,7 +341,7 @@ mod tests {
                 contains = true;
             }
         }
-        assert_eq!(contains, true);
+        assert!(contains);
     }
 
     #[test]
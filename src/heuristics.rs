@@ -0,0 +1,590 @@
+use std::collections;
+
+use crate::strips;
+use crate::util;
+
+///
+/// Domain-independent heuristic used to estimate the cost of reaching a set of facts.
+///
+/// # Example
+/// ```
+/// use rusty_planner::any_dyn_astar;
+/// use rusty_planner::heuristics;
+/// use rusty_planner::pddl;
+/// use rusty_planner::planner::ProblemSpace;
+/// use rusty_planner::strips;
+///
+/// let domain = pddl::Domain::parse(
+///     "(define (domain switches)
+///        (:predicates (on ?s) (ready))
+///        (:action prepare :parameters () :effect (ready))
+///        (:action flip :parameters (?s) :precondition (ready) :effect (on ?s)))",
+/// )
+/// .unwrap();
+/// let problem = pddl::Problem::parse(
+///     "(define (problem all-on) (:domain switches) (:objects a b)
+///        (:init) (:goal (and (on a) (on b))))",
+/// )
+/// .unwrap();
+/// let task = strips::Task::from_pddl(&pddl::Task::ground(&domain, &problem).unwrap())
+///     .with_heuristic(heuristics::Heuristic::LmCut);
+/// assert_eq!(
+///     task.heuristic(&task.initial_state(), &task.goal_state()),
+///     3.0
+/// );
+///
+/// fn callback(plan: Vec<usize>) {
+///     // prepare, flip both switches & reach the artificial goal state.
+///     assert_eq!(plan.len(), 4);
+/// }
+/// any_dyn_astar::solve(&task, task.initial_state(), task.goal_state(), callback);
+/// ```
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Heuristic {
+    /// Always 0.0.
+    Blind,
+    /// Cost of the most expensive fact in the delete relaxation - admissible.
+    Max,
+    /// Sum of the costs of the facts in the delete relaxation.
+    Add,
+    /// Cost of a relaxed plan extracted using h_add.
+    FF,
+    /// Landmark-cut - admissible.
+    LmCut,
+    /// Number of fact landmarks not holding yet.
+    Landmarks,
+}
+
+/// Estimate the cost of reaching the goal facts from the state - conditional effects are relaxed to
+/// happen regardless of their conditions.
+pub fn evaluate(
+    heuristic: Heuristic,
+    task: &strips::Task,
+    state: &strips::FactSet,
+    goal: &[usize],
+) -> f64 {
+    match heuristic {
+        Heuristic::Blind => 0.0,
+        Heuristic::Max => h_max(task, state, goal),
+        Heuristic::Add => h_add(task, state, goal),
+        Heuristic::FF => h_ff(task, state, goal).0,
+        Heuristic::LmCut => lm_cut(task, state, goal),
+        Heuristic::Landmarks => landmark_count(task, state, goal),
+    }
+}
+
+///
+/// Result of exploring the delete relaxation - cost per fact & the operator achieving it.
+///
+struct Exploration {
+    facts: Vec<f64>,
+    supporter: Vec<Option<usize>>,
+}
+
+///
+/// Generalized Dijkstra over the delete relaxation - the costs of an operator's preconditions are
+/// either summed up (h_add) or maximized (h_max).
+///
+fn explore(task: &strips::Task, state: &strips::FactSet, costs: &[f64], sum: bool) -> Exploration {
+    let n_facts = task.facts().len();
+    let mut res = Exploration {
+        facts: vec![f64::INFINITY; n_facts],
+        supporter: vec![None; n_facts],
+    };
+    let mut pre_of = vec![Vec::new(); n_facts];
+    let mut unsatisfied = Vec::new();
+    let mut values = vec![0.0; costs.len()];
+    for (i, op) in task.operators().iter().enumerate() {
+        for fact in &op.pre {
+            pre_of[*fact].push(i);
+        }
+        unsatisfied.push(op.pre.len());
+    }
+
+    let mut open = collections::BinaryHeap::new();
+    for fact in state.iter().filter(|f| *f < n_facts) {
+        res.facts[fact] = 0.0;
+        open.push(util::HeapEntry::new_entry(fact, (0.0, 0.0)));
+    }
+    for op in (0..costs.len()).filter(|op| unsatisfied[*op] == 0) {
+        achieve(task, op, costs[op], &mut res, &mut open);
+    }
+    let mut done = vec![false; n_facts];
+    while let Some(entry) = open.pop() {
+        let fact = entry.state;
+        if done[fact] {
+            continue;
+        }
+        done[fact] = true;
+        for op in &pre_of[fact] {
+            values[*op] = if sum {
+                values[*op] + res.facts[fact]
+            } else {
+                f64::max(values[*op], res.facts[fact])
+            };
+            unsatisfied[*op] -= 1;
+            if unsatisfied[*op] == 0 {
+                achieve(task, *op, costs[*op] + values[*op], &mut res, &mut open);
+            }
+        }
+    }
+    res
+}
+
+/// Update the facts added by the operator if it reaches them cheaper.
+fn achieve(
+    task: &strips::Task,
+    op: usize,
+    cost: f64,
+    res: &mut Exploration,
+    open: &mut collections::BinaryHeap<util::HeapEntry<usize>>,
+) {
+    for fact in task.operators()[op].adds() {
+        if cost < res.facts[*fact] {
+            res.facts[*fact] = cost;
+            res.supporter[*fact] = Some(op);
+            open.push(util::HeapEntry::new_entry(*fact, (cost, 0.0)));
+        }
+    }
+}
+
+fn op_costs(task: &strips::Task) -> Vec<f64> {
+    task.operators().iter().map(|op| op.cost).collect()
+}
+
+///
+/// h_max - cost of the most expensive goal fact when ignoring delete effects.
+///
+pub fn h_max(task: &strips::Task, state: &strips::FactSet, goal: &[usize]) -> f64 {
+    let exploration = explore(task, state, &op_costs(task), false);
+    goal.iter()
+        .map(|f| exploration.facts[*f])
+        .fold(0.0, f64::max)
+}
+
+///
+/// h_add - sum of the costs of the goal facts when ignoring delete effects.
+///
+pub fn h_add(task: &strips::Task, state: &strips::FactSet, goal: &[usize]) -> f64 {
+    let exploration = explore(task, state, &op_costs(task), true);
+    goal.iter().map(|f| exploration.facts[*f]).sum()
+}
+
+///
+/// h_FF - cost of a relaxed plan using the cheapest achievers according to h_add. Also returns
+/// the helpful actions: operators applicable in the state which achieve a fact needed by the
+/// relaxed plan.
+///
+pub fn h_ff(task: &strips::Task, state: &strips::FactSet, goal: &[usize]) -> (f64, Vec<usize>) {
+    let exploration = explore(task, state, &op_costs(task), true);
+    if goal.iter().any(|f| exploration.facts[*f].is_infinite()) {
+        return (f64::INFINITY, vec![]);
+    }
+    let mut plan = Vec::new();
+    let mut needed: Vec<usize> = goal
+        .iter()
+        .filter(|f| !state.contains(**f))
+        .copied()
+        .collect();
+    let mut marked = strips::FactSet::new(task.facts().len());
+    let mut i = 0;
+    while i < needed.len() {
+        let fact = needed[i];
+        i += 1;
+        if marked.contains(fact) {
+            continue;
+        }
+        marked.insert(fact);
+        let op = exploration.supporter[fact].unwrap();
+        if !plan.contains(&op) {
+            plan.push(op);
+            let pre = &task.operators()[op].pre;
+            needed.extend(pre.iter().filter(|f| !state.contains(**f)));
+        }
+    }
+    let helpful = task
+        .operators()
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| state.contains_all(&op.pre))
+        .filter(|(_, op)| op.adds().any(|f| marked.contains(*f)))
+        .map(|(i, _)| i)
+        .collect();
+    let cost = plan.iter().map(|op| task.operators()[*op].cost).sum();
+    (cost, helpful)
+}
+
+///
+/// LM-cut - repeatedly finds a cut of operators in the justification graph of h_max which every
+/// relaxed plan needs & sums up the cheapest costs of the cuts.
+///
+pub fn lm_cut(task: &strips::Task, state: &strips::FactSet, goal: &[usize]) -> f64 {
+    let goal: Vec<usize> = goal
+        .iter()
+        .filter(|f| !state.contains(**f))
+        .copied()
+        .collect();
+    if goal.is_empty() {
+        return 0.0;
+    }
+    let ops = task.operators();
+    let mut costs = op_costs(task);
+    let mut res = 0.0;
+    let mut add_of = vec![Vec::new(); task.facts().len()];
+    for (i, op) in ops.iter().enumerate() {
+        for fact in op.adds() {
+            add_of[*fact].push(i);
+        }
+    }
+    loop {
+        let exploration = explore(task, state, &costs, false);
+        let value = |f: &usize| exploration.facts[*f];
+        let g_max = *goal
+            .iter()
+            .max_by(|a, b| value(a).total_cmp(&value(b)))
+            .unwrap();
+        if value(&g_max).is_infinite() {
+            return f64::INFINITY;
+        }
+        if value(&g_max) == 0.0 {
+            return res;
+        }
+        // precondition choice function - the most expensive precondition, None if there is none.
+        let pcf: Vec<Option<usize>> = ops
+            .iter()
+            .map(|op| {
+                op.pre
+                    .iter()
+                    .copied()
+                    .max_by(|a, b| value(a).total_cmp(&value(b)))
+            })
+            .collect();
+        let reached = |i: usize| ops[i].pre.iter().all(|f| value(f).is_finite());
+        let mut pcf_of = vec![Vec::new(); task.facts().len()];
+        for (i, p) in pcf.iter().enumerate() {
+            if let (Some(p), true) = (p, reached(i)) {
+                pcf_of[*p].push(i);
+            }
+        }
+
+        // goal zone - facts reaching the goal through zero cost operators.
+        let mut zone = strips::FactSet::new(task.facts().len());
+        zone.insert(g_max);
+        let mut stack = vec![g_max];
+        while let Some(fact) = stack.pop() {
+            for i in add_of[fact]
+                .iter()
+                .filter(|i| reached(**i) && costs[**i] == 0.0)
+            {
+                if let Some(p) = pcf[*i] {
+                    if !zone.contains(p) {
+                        zone.insert(p);
+                        stack.push(p);
+                    }
+                }
+            }
+        }
+
+        // facts reachable from the state without entering the goal zone.
+        let mut before = strips::FactSet::new(task.facts().len());
+        let mut stack: Vec<usize> = Vec::new();
+        let mut cut = Vec::new();
+        let mut visit = |i: usize, before: &mut strips::FactSet, stack: &mut Vec<usize>| {
+            if ops[i].adds().any(|f| zone.contains(*f)) {
+                if !cut.contains(&i) {
+                    cut.push(i);
+                }
+                return;
+            }
+            for fact in ops[i].adds() {
+                if !before.contains(*fact) {
+                    before.insert(*fact);
+                    stack.push(*fact);
+                }
+            }
+        };
+        for fact in state.iter().filter(|f| *f < task.facts().len()) {
+            before.insert(fact);
+            stack.push(fact);
+        }
+        for i in (0..ops.len()).filter(|i| pcf[*i].is_none()) {
+            visit(i, &mut before, &mut stack);
+        }
+        while let Some(fact) = stack.pop() {
+            for i in &pcf_of[fact] {
+                visit(*i, &mut before, &mut stack);
+            }
+        }
+
+        let min = cut.iter().map(|i| costs[*i]).fold(f64::INFINITY, f64::min);
+        res += min;
+        for i in cut {
+            costs[i] -= min;
+        }
+    }
+}
+
+///
+/// Fact landmarks of the goal - facts which hold at some point in every plan from the state. Found
+/// by propagating labels through the delete relaxation; `None` if the goal is unreachable.
+///
+pub fn landmarks(
+    task: &strips::Task,
+    state: &strips::FactSet,
+    goal: &[usize],
+) -> Option<Vec<usize>> {
+    let n_facts = task.facts().len();
+    // facts which need to hold before a fact is reached - None if not reached (yet).
+    let mut labels: Vec<Option<strips::FactSet>> = vec![None; n_facts];
+    for fact in state.iter().filter(|f| *f < n_facts) {
+        labels[fact] = Some(strips::FactSet::from_facts(n_facts, &[fact]));
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for op in task.operators() {
+            let mut label = strips::FactSet::new(n_facts);
+            let mut reached = true;
+            for fact in &op.pre {
+                match &labels[*fact] {
+                    Some(pre) => label.union_with(pre),
+                    None => reached = false,
+                }
+            }
+            if !reached {
+                continue;
+            }
+            for fact in op.adds().filter(|f| !state.contains(**f)) {
+                let mut new = label.clone();
+                new.insert(*fact);
+                if let Some(old) = &labels[*fact] {
+                    new.intersect_with(old);
+                }
+                if labels[*fact].as_ref() != Some(&new) {
+                    labels[*fact] = Some(new);
+                    changed = true;
+                }
+            }
+        }
+    }
+    let mut res = strips::FactSet::new(n_facts);
+    for fact in goal {
+        res.union_with(labels[*fact].as_ref()?);
+    }
+    Some(res.iter().collect())
+}
+
+///
+/// Number of fact landmarks of the goal not holding in the state - each needs to be achieved.
+///
+pub fn landmark_count(task: &strips::Task, state: &strips::FactSet, goal: &[usize]) -> f64 {
+    match landmarks(task, state, goal) {
+        Some(facts) => facts.iter().filter(|f| !state.contains(**f)).count() as f64,
+        None => f64::INFINITY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic;
+
+    use crate::any_dyn_astar;
+    use crate::cooperative;
+    use crate::heuristics;
+    use crate::planner::ProblemSpace;
+    use crate::strips;
+
+    static PLAN_LENGTH: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+
+    /// Rooms in a row - the robot moves to c, picks up the ball & comes back to a.
+    fn task() -> strips::Task {
+        let facts = vec!["(at a)", "(at b)", "(at c)", "(ball c)", "(holding)"];
+        let operator =
+            |name: &str, pre: Vec<usize>, add: Vec<usize>, del: Vec<usize>| strips::Operator {
+                name: String::from(name),
+                pre,
+                add,
+                del,
+                conditional: vec![],
+                cost: 1.0,
+            };
+        let operators = vec![
+            operator("(move a b)", vec![0], vec![1], vec![0]),
+            operator("(move b a)", vec![1], vec![0], vec![1]),
+            operator("(move b c)", vec![1], vec![2], vec![1]),
+            operator("(move c b)", vec![2], vec![1], vec![2]),
+            operator("(pick c)", vec![2, 3], vec![4], vec![3]),
+        ];
+        strips::Task::new(
+            facts.into_iter().map(String::from).collect(),
+            operators,
+            &[0, 3],
+            vec![0, 4],
+        )
+    }
+
+    /// Rooms in a row - walk to the last one or take the expensive shortcut.
+    fn row(n_facts: usize) -> strips::Task {
+        let walk = |i: usize| strips::Operator {
+            name: format!("(walk {})", i),
+            pre: vec![i],
+            add: vec![i + 1],
+            del: vec![i],
+            conditional: vec![],
+            cost: 1.0,
+        };
+        let mut operators: Vec<strips::Operator> = (0..n_facts - 1).map(walk).collect();
+        operators.push(strips::Operator {
+            name: String::from("(shortcut)"),
+            pre: vec![0],
+            add: vec![n_facts - 1],
+            del: vec![0],
+            conditional: vec![],
+            cost: 200.0,
+        });
+        strips::Task::new(
+            (0..n_facts).map(|i| format!("(at {})", i)).collect(),
+            operators,
+            &[0],
+            vec![n_facts - 1],
+        )
+    }
+
+    fn callback(plan: Vec<usize>) {
+        PLAN_LENGTH.store(plan.len(), atomic::Ordering::SeqCst);
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_heuristics_for_success() {
+        let task = task();
+        let init = task.state(task.initial_state());
+        heuristics::h_max(&task, &init, task.goal());
+        heuristics::h_add(&task, &init, task.goal());
+        heuristics::h_ff(&task, &init, task.goal());
+        heuristics::lm_cut(&task, &init, task.goal());
+        heuristics::landmarks(&task, &init, task.goal());
+        heuristics::landmark_count(&task, &init, task.goal());
+        heuristics::evaluate(heuristics::Heuristic::FF, &task, &init, task.goal());
+    }
+
+    #[test]
+    fn test_task_for_success() {
+        let task = task().with_heuristic(heuristics::Heuristic::Add);
+        task.heuristic(&task.initial_state(), &task.goal_state());
+    }
+
+    // Test for failure.
+
+    #[test]
+    fn test_heuristics_for_failure() {
+        // the ball can't be put back.
+        let task = task();
+        let init = task.state(task.initial_state());
+        let picked = strips::FactSet::from_facts(5, &[1, 4]);
+        for heuristic in [
+            heuristics::Heuristic::Max,
+            heuristics::Heuristic::Add,
+            heuristics::Heuristic::FF,
+            heuristics::Heuristic::LmCut,
+            heuristics::Heuristic::Landmarks,
+        ] {
+            let h = heuristics::evaluate(heuristic, &task, &picked, &[3]);
+            assert!(h.is_infinite());
+            assert!(heuristics::evaluate(heuristic, &task, &init, &[3]) == 0.0);
+        }
+        assert_eq!(
+            heuristics::h_ff(&task, &picked, &[3]).1,
+            Vec::<usize>::new()
+        );
+        assert_eq!(heuristics::landmarks(&task, &picked, &[3]), None);
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_heuristics_for_sanity() {
+        let task = task();
+        let init = task.state(task.initial_state());
+        let goal = task.goal();
+        assert_eq!(heuristics::h_max(&task, &init, goal), 3.0);
+        assert_eq!(heuristics::h_add(&task, &init, goal), 3.0);
+        assert_eq!(heuristics::h_add(&task, &init, &[2, 4]), 5.0);
+        assert_eq!(heuristics::h_ff(&task, &init, &[2, 4]), (3.0, vec![0]));
+        assert_eq!(heuristics::lm_cut(&task, &init, goal), 3.0);
+        assert_eq!(
+            heuristics::landmarks(&task, &init, goal),
+            Some(vec![0, 1, 2, 3, 4])
+        );
+        assert_eq!(heuristics::landmark_count(&task, &init, goal), 3.0);
+
+        // holding the ball at c - only the way back is left.
+        let mut state = strips::FactSet::from_facts(5, &[2, 4]);
+        assert_eq!(heuristics::h_max(&task, &state, goal), 2.0);
+        assert_eq!(heuristics::h_ff(&task, &state, goal), (2.0, vec![3]));
+        assert_eq!(heuristics::lm_cut(&task, &state, goal), 2.0);
+        assert_eq!(heuristics::landmark_count(&task, &state, goal), 2.0);
+
+        // goal reached.
+        state.insert(0);
+        assert_eq!(heuristics::h_add(&task, &state, goal), 0.0);
+        assert_eq!(heuristics::lm_cut(&task, &state, goal), 0.0);
+
+        // more than 64 facts - every walk is a landmark, the shortcut is cut each time.
+        let task = row(70);
+        let init = task.state(task.initial_state());
+        assert_eq!(heuristics::h_max(&task, &init, task.goal()), 69.0);
+        assert_eq!(heuristics::lm_cut(&task, &init, task.goal()), 69.0);
+        assert_eq!(heuristics::landmark_count(&task, &init, task.goal()), 1.0);
+    }
+
+    #[test]
+    fn test_admissible_for_sanity() {
+        for heuristic in [heuristics::Heuristic::Max, heuristics::Heuristic::LmCut] {
+            let task = task().with_heuristic(heuristic);
+            let mut dist = cooperative::TrueDistance::new(task.goal_state());
+            let mut states = vec![task.initial_state()];
+            let mut i = 0;
+            while i < states.len() {
+                let h = task.heuristic(&states[i], &task.goal_state());
+                assert!(h <= dist.distance(&task, &states[i]));
+                for (next, _) in task.succ(&states[i]) {
+                    if !states.contains(&next) {
+                        states.push(next);
+                    }
+                }
+                i += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_task_for_sanity() {
+        let blind = task();
+        let task = task().with_heuristic(heuristics::Heuristic::LmCut);
+        let init = task.initial_state();
+        let goal = task.goal_state();
+        assert_eq!(task.heuristic(&init, &goal), 3.0);
+        assert_eq!(task.heuristic(&goal, &init), 3.0);
+        assert_eq!(task.heuristic(&goal, &goal), 0.0);
+
+        // between two states - minimum of both directions.
+        let b = task.apply(init, 0).unwrap();
+        assert_eq!(task.heuristic(&init, &b), 1.0);
+        assert_eq!(blind.heuristic(&init, &goal), 0.0);
+
+        // backward search using the heuristic - 5 steps & the artificial goal state.
+        any_dyn_astar::solve(&task, init, goal, callback);
+        assert_eq!(PLAN_LENGTH.load(atomic::Ordering::SeqCst), 6);
+
+        // more than 64 facts - 69 walks & the artificial goal state; the shortcut costs more than
+        // twice as much (the suboptimality bound of the search).
+        let task = row(70).with_heuristic(heuristics::Heuristic::LmCut);
+        let mut dist = cooperative::TrueDistance::new(task.goal_state());
+        assert_eq!(dist.distance(&task, &task.initial_state()), 69.0);
+        any_dyn_astar::solve(&task, task.initial_state(), task.goal_state(), callback);
+        assert_eq!(PLAN_LENGTH.load(atomic::Ordering::SeqCst), 70);
+    }
+}
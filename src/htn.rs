@@ -0,0 +1,480 @@
+use std::collections;
+use std::error;
+use std::fmt;
+
+use crate::util;
+
+///
+/// Public trait which - once implemented - describes the hierarchical planning domain. Tasks are
+/// either primitive - executed by an operator - or compound - decomposed by methods.
+///
+pub trait Domain {
+    /// Defines the type of your state.
+    type State: Clone;
+    /// Defines the type of your tasks.
+    type Task: Clone;
+
+    /// Determine if a task is primitive.
+    fn is_primitive(&self, _: &Self::Task) -> bool;
+    /// Apply the operator of a primitive task - returns the new state & the cost, `None` if the
+    /// operator's preconditions are not satisfied.
+    fn apply(&self, _: &Self::State, _: &Self::Task) -> Option<(Self::State, f64)>;
+    /// Methods which can decompose the compound task in the given state - only those whose
+    /// preconditions are satisfied, in the order they should be tried.
+    fn methods(&self, _: &Self::State, _: &Self::Task) -> Vec<Method<Self::Task>>;
+    /// Estimate of the cost of the remaining tasks - used to order the search by cost. Defaults to
+    /// 0.0, for which the cheapest plan is found.
+    fn estimate(&self, _: &Self::State, _: &[Self::Task]) -> f64 {
+        0.0
+    }
+}
+
+///
+/// Decomposition of a compound task into subtasks - executed in the given order.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Method<T> {
+    pub name: String,
+    pub subtasks: Vec<T>,
+}
+
+/// Order in which partial decompositions get explored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Order {
+    /// Methods in the order given by the domain, backtracking on failure - returns the first plan
+    /// found (as SHOP2 does).
+    DepthFirst,
+    /// Cheapest partial decomposition first - by cost so far plus `Domain::estimate`.
+    BestFirst,
+}
+
+///
+/// Configuration for the HTN planner.
+///
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub order: Order,
+    /// Number of partial decompositions after which the search gives up.
+    pub max_nodes: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            order: Order::DepthFirst,
+            max_nodes: 100000,
+        }
+    }
+}
+
+///
+/// Reasons the HTN planner can fail.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Error {
+    /// No decomposition leads to an executable plan.
+    NoPlan,
+    /// The search explored the maximum number of partial decompositions.
+    NodeLimit,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NoPlan => write!(f, "no plan exists"),
+            Error::NodeLimit => write!(f, "node limit reached"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+///
+/// Node of the decomposition tree - primitive tasks are the leaves.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node<T> {
+    pub task: T,
+    /// Name of the method used to decompose the task - `None` for primitive tasks.
+    pub method: Option<String>,
+    pub children: Vec<Node<T>>,
+}
+
+///
+/// Result of the HTN planner.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Plan<T> {
+    /// Primitive tasks in the order of execution.
+    pub actions: Vec<T>,
+    /// Total cost of the primitive tasks.
+    pub cost: f64,
+    /// Decomposition tree of each of the tasks given.
+    pub tree: Vec<Node<T>>,
+}
+
+/// Task in the decomposition tree under construction.
+#[derive(Clone)]
+struct Entry<T> {
+    task: T,
+    method: Option<String>,
+    children: Vec<usize>,
+}
+
+/// Partial decomposition - tasks left to do are on the agenda (next one last).
+#[derive(Clone)]
+struct Partial<S, T> {
+    state: S,
+    cost: f64,
+    agenda: Vec<usize>,
+    actions: Vec<T>,
+    entries: Vec<Entry<T>>,
+}
+
+impl<S, T: Clone> Partial<S, T> {
+    fn build(&self, id: usize) -> Node<T> {
+        let entry = &self.entries[id];
+        Node {
+            task: entry.task.clone(),
+            method: entry.method.clone(),
+            children: entry.children.iter().map(|c| self.build(*c)).collect(),
+        }
+    }
+
+    fn remaining(&self) -> Vec<T> {
+        self.agenda
+            .iter()
+            .rev()
+            .map(|id| self.entries[*id].task.clone())
+            .collect()
+    }
+}
+
+/// Partial decompositions resulting from working on the next task of the agenda.
+fn expand<D: Domain>(
+    domain: &D,
+    mut node: Partial<D::State, D::Task>,
+) -> Vec<Partial<D::State, D::Task>> {
+    let id = match node.agenda.pop() {
+        Some(id) => id,
+        None => return vec![],
+    };
+    let task = node.entries[id].task.clone();
+    if domain.is_primitive(&task) {
+        return match domain.apply(&node.state, &task) {
+            Some((state, cost)) => {
+                node.state = state;
+                node.cost += cost;
+                node.actions.push(task);
+                vec![node]
+            }
+            None => vec![],
+        };
+    }
+    let mut res = Vec::new();
+    for method in domain.methods(&node.state, &task) {
+        let mut child = node.clone();
+        let first = child.entries.len();
+        child.entries[id].method = Some(method.name);
+        child.entries[id].children = (first..first + method.subtasks.len()).collect();
+        for subtask in method.subtasks {
+            child.entries.push(Entry {
+                task: subtask,
+                method: None,
+                children: vec![],
+            });
+        }
+        child.agenda.extend((first..child.entries.len()).rev());
+        res.push(child);
+    }
+    res
+}
+
+///
+/// Decompose the tasks into a plan of primitive tasks - `None` if that is not possible.
+///
+pub fn solve<D: Domain>(domain: &D, state: D::State, tasks: Vec<D::Task>) -> Option<Plan<D::Task>> {
+    solve_with_config(domain, state, tasks, &Config::default()).ok()
+}
+
+///
+/// Decompose the tasks into a plan of primitive tasks (totally ordered) - tasks get decomposed in
+/// the order they will be executed, so methods & operators see the state their tasks are executed
+/// in.
+///
+pub fn solve_with_config<D: Domain>(
+    domain: &D,
+    state: D::State,
+    tasks: Vec<D::Task>,
+    config: &Config,
+) -> Result<Plan<D::Task>, Error> {
+    let n_tasks = tasks.len();
+    let root = Partial {
+        state,
+        cost: 0.0,
+        agenda: (0..n_tasks).rev().collect(),
+        actions: vec![],
+        entries: tasks
+            .into_iter()
+            .map(|task| Entry {
+                task,
+                method: None,
+                children: vec![],
+            })
+            .collect(),
+    };
+
+    // depth-first uses a stack, best-first a heap of ids of partial decompositions.
+    let mut stack = vec![root];
+    let mut nodes: Vec<Option<Partial<D::State, D::Task>>> = Vec::new();
+    let mut open = collections::BinaryHeap::new();
+    if config.order == Order::BestFirst {
+        nodes.push(stack.pop());
+        open.push(util::HeapEntry::new_entry(0, (0.0, 0.0)));
+    }
+
+    let mut n_nodes = 0;
+    loop {
+        let node = match config.order {
+            Order::DepthFirst => stack.pop(),
+            Order::BestFirst => open.pop().and_then(|e| nodes[e.state].take()),
+        };
+        let node = node.ok_or(Error::NoPlan)?;
+        if node.agenda.is_empty() {
+            return Ok(Plan {
+                tree: (0..n_tasks).map(|id| node.build(id)).collect(),
+                actions: node.actions,
+                cost: node.cost,
+            });
+        }
+        n_nodes += 1;
+        if n_nodes > config.max_nodes {
+            return Err(Error::NodeLimit);
+        }
+        let children = expand(domain, node);
+        match config.order {
+            Order::DepthFirst => stack.extend(children.into_iter().rev()),
+            Order::BestFirst => {
+                for child in children {
+                    let f = child.cost + domain.estimate(&child.state, &child.remaining());
+                    // ties are broken in favour of the most recent - deeper - decomposition.
+                    let keys = (f, -(nodes.len() as f64));
+                    open.push(util::HeapEntry::new_entry(nodes.len(), keys));
+                    nodes.push(Some(child));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::htn;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Task {
+        Travel(u32),
+        Walk(u32),
+        CallTaxi,
+        Ride(u32),
+        Pay(u32),
+        Wander,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct State {
+        at: u32,
+        cash: u32,
+        taxi: bool,
+    }
+
+    /// Travelling by foot - up to a distance of 4 - or by taxi, costing 1 plus the distance.
+    struct Travel {
+        taxi_first: bool,
+    }
+
+    fn distance(from: u32, to: u32) -> u32 {
+        from.abs_diff(to)
+    }
+
+    impl htn::Domain for Travel {
+        type State = State;
+        type Task = Task;
+
+        fn is_primitive(&self, task: &Self::Task) -> bool {
+            !matches!(task, Task::Travel(_) | Task::Wander)
+        }
+
+        fn apply(&self, state: &Self::State, task: &Self::Task) -> Option<(Self::State, f64)> {
+            let mut next = state.clone();
+            match task {
+                Task::Walk(to) => {
+                    next.at = *to;
+                    Some((next, distance(state.at, *to) as f64))
+                }
+                Task::CallTaxi if !state.taxi => {
+                    next.taxi = true;
+                    Some((next, 0.5))
+                }
+                Task::Ride(to) if state.taxi => {
+                    next.at = *to;
+                    Some((next, 0.2 * distance(state.at, *to) as f64))
+                }
+                Task::Pay(fare) if state.cash >= *fare => {
+                    next.cash -= fare;
+                    next.taxi = false;
+                    Some((next, 0.0))
+                }
+                _ => None,
+            }
+        }
+
+        fn methods(&self, state: &Self::State, task: &Self::Task) -> Vec<htn::Method<Self::Task>> {
+            let mut res = Vec::new();
+            match task {
+                Task::Travel(to) => {
+                    let dist = distance(state.at, *to);
+                    if dist <= 4 {
+                        res.push(htn::Method {
+                            name: String::from("walk"),
+                            subtasks: vec![Task::Walk(*to)],
+                        });
+                    }
+                    // the fare is only checked when paying.
+                    if state.cash > 0 {
+                        let taxi = htn::Method {
+                            name: String::from("taxi"),
+                            subtasks: vec![Task::CallTaxi, Task::Ride(*to), Task::Pay(1 + dist)],
+                        };
+                        match self.taxi_first {
+                            true => res.insert(0, taxi),
+                            false => res.push(taxi),
+                        }
+                    }
+                }
+                Task::Wander => res.push(htn::Method {
+                    name: String::from("wander"),
+                    subtasks: vec![Task::Walk(state.at + 1), Task::Wander],
+                }),
+                _ => {}
+            }
+            res
+        }
+    }
+
+    fn home(cash: u32) -> State {
+        State {
+            at: 0,
+            cash,
+            taxi: false,
+        }
+    }
+
+    fn best_first() -> htn::Config {
+        htn::Config {
+            order: htn::Order::BestFirst,
+            ..Default::default()
+        }
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_solve_for_success() {
+        let domain = Travel { taxi_first: false };
+        htn::solve(&domain, home(10), vec![Task::Travel(3)]).unwrap();
+        htn::solve_with_config(&domain, home(10), vec![Task::Travel(3)], &best_first()).unwrap();
+    }
+
+    // Test for failure.
+
+    #[test]
+    fn test_solve_for_failure() {
+        // too far to walk & not enough cash for the taxi.
+        let domain = Travel { taxi_first: false };
+        assert_eq!(htn::solve(&domain, home(5), vec![Task::Travel(8)]), None);
+        for config in [htn::Config::default(), best_first()] {
+            assert_eq!(
+                htn::solve_with_config(&domain, home(5), vec![Task::Travel(8)], &config),
+                Err(htn::Error::NoPlan)
+            );
+        }
+
+        // endless decomposition.
+        let config = htn::Config {
+            max_nodes: 100,
+            ..Default::default()
+        };
+        assert_eq!(
+            htn::solve_with_config(&domain, home(0), vec![Task::Wander], &config),
+            Err(htn::Error::NodeLimit)
+        );
+        assert_eq!(htn::Error::NodeLimit.to_string(), "node limit reached");
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_solve_for_sanity() {
+        // first method which works.
+        let domain = Travel { taxi_first: false };
+        let plan = htn::solve(&domain, home(10), vec![Task::Travel(3)]).unwrap();
+        assert_eq!(plan.actions, vec![Task::Walk(3)]);
+        assert_eq!(plan.cost, 3.0);
+        assert_eq!(
+            plan.tree,
+            vec![htn::Node {
+                task: Task::Travel(3),
+                method: Some(String::from("walk")),
+                children: vec![htn::Node {
+                    task: Task::Walk(3),
+                    method: None,
+                    children: vec![]
+                }]
+            }]
+        );
+
+        // cheapest plan.
+        let plan = htn::solve_with_config(&domain, home(10), vec![Task::Travel(3)], &best_first())
+            .unwrap();
+        assert_eq!(
+            plan.actions,
+            vec![Task::CallTaxi, Task::Ride(3), Task::Pay(4)]
+        );
+        assert!((plan.cost - 1.1).abs() < 1e-9);
+        assert_eq!(plan.tree[0].method, Some(String::from("taxi")));
+        assert_eq!(plan.tree[0].children.len(), 3);
+    }
+
+    #[test]
+    fn test_backtracking_for_sanity() {
+        // taking the taxi fails when paying - walking instead.
+        let domain = Travel { taxi_first: true };
+        let plan = htn::solve(&domain, home(2), vec![Task::Travel(3)]).unwrap();
+        assert_eq!(plan.actions, vec![Task::Walk(3)]);
+
+        // the state is passed along - the taxi only pays off for the longer trip.
+        let tasks = vec![Task::Travel(8), Task::Travel(5)];
+        let plan = htn::solve(&domain, home(10), tasks.clone()).unwrap();
+        assert_eq!(
+            plan.actions,
+            vec![Task::CallTaxi, Task::Ride(8), Task::Pay(9), Task::Walk(5)]
+        );
+        let methods: Vec<_> = plan
+            .tree
+            .iter()
+            .map(|n| n.method.clone().unwrap())
+            .collect();
+        assert_eq!(methods, vec!["taxi", "walk"]);
+        let plan = htn::solve_with_config(&domain, home(10), tasks, &best_first()).unwrap();
+        assert_eq!(plan.actions.len(), 4);
+        assert_eq!(
+            plan.tree[1].children,
+            vec![htn::Node {
+                task: Task::Walk(5),
+                method: None,
+                children: vec![]
+            }]
+        );
+    }
+}
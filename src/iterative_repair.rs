@@ -1,4 +1,5 @@
 use std::collections;
+use std::hash;
 
 use crate::util;
 
@@ -13,6 +14,167 @@ pub trait Problem {
     fn find_conflicts(&self) -> Self::Iter;
     /// Routine to fix a particular conflict.
     fn fix_conflict(&mut self, _: &Self::Conflict);
+    /// Fix a conflict and report which conflicts got introduced and resolved by the repair. By
+    /// default changes are not tracked (`None`) - solvers then fall back to `find_conflicts`.
+    fn fix_conflict_delta(&mut self, conflict: &Self::Conflict) -> Option<Delta<Self::Conflict>> {
+        self.fix_conflict(conflict);
+        None
+    }
+}
+
+///
+/// Changes to the set of conflicts caused by a repair.
+///
+pub struct Delta<C> {
+    /// Conflicts (and their priorities) introduced by the repair.
+    pub added: Vec<(C, f64)>,
+    /// Conflicts resolved by the repair.
+    pub removed: Vec<C>,
+}
+
+/// Trait for problems offering a choice of values to resolve a conflict - needed by the
+/// min-conflicts strategy, random walks and random restarts.
+pub trait Repairable: Problem {
+    /// Type of the values that can be assigned to resolve a conflict.
+    type Value;
+    /// Type capturing the current assignment - used to remember the best one seen.
+    type Assignment: Clone;
+
+    /// Returns the candidate values for resolving the given conflict.
+    fn values(&self, _: &Self::Conflict) -> Vec<Self::Value>;
+    /// Assign a value to resolve the given conflict.
+    fn assign(&mut self, _: &Self::Conflict, _: &Self::Value);
+    /// Returns the current assignment.
+    fn assignment(&self) -> Self::Assignment;
+    /// Restore a previously seen assignment.
+    fn restore(&mut self, _: &Self::Assignment);
+    /// Start over from a random assignment - seed given for reproducibility.
+    fn randomize(&mut self, seed: u64);
+}
+
+/// Trait for problems with a global score - needed to accept or reject repairs during simulated
+/// annealing.
+pub trait Evaluate: Repairable {
+    /// Type describing an applied repair - holds everything needed to undo it.
+    type Repair;
+
+    /// Returns the cost of the current assignment - lower is better.
+    fn cost(&self) -> f64;
+    /// Apply a candidate repair for the given conflict - sample in [0, 1) can be used to pick
+    /// among alternative repairs.
+    fn propose_repair(&mut self, _: &Self::Conflict, sample: f64) -> Self::Repair;
+    /// Revert a previously proposed repair.
+    fn undo(&mut self, _: Self::Repair);
+}
+
+/// Strategy used to repair a conflict.
+pub enum Strategy {
+    /// Repair the conflict using `fix_conflict`.
+    Greedy,
+    /// Assign the value which leads to the least number of conflicts - ties are broken randomly.
+    MinConflicts,
+}
+
+///
+/// Configuration for the iterative repair algorithm.
+///
+pub struct Config {
+    /// Strategy used to repair conflicts.
+    pub strategy: Strategy,
+    /// Number of repairs per (re)start.
+    pub steps: i32,
+    /// Number of recently repaired conflicts which will not be picked again.
+    pub tabu: usize,
+    /// Probability of assigning a random value instead of following the strategy.
+    pub random_walk: f64,
+    /// Number of times to start over from a random assignment.
+    pub restarts: u32,
+    /// Seed for the random number generator.
+    pub seed: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            strategy: Strategy::Greedy,
+            steps: 100,
+            tabu: 0,
+            random_walk: 0.0,
+            restarts: 0,
+            seed: 0,
+        }
+    }
+}
+
+///
+/// Result of the iterative repair algorithm.
+///
+pub struct Report<A> {
+    /// Indicates if an assignment without conflicts was found.
+    pub found: bool,
+    /// Total number of repairs made.
+    pub steps: i32,
+    /// Number of random restarts made.
+    pub restarts: u32,
+    /// Number of conflicts in the best assignment.
+    pub conflicts: usize,
+    /// Best assignment seen.
+    pub best: A,
+}
+
+/// Temperature schedule used by simulated annealing.
+pub enum Cooling {
+    /// Multiply the temperature by the given factor each step.
+    Geometric(f64),
+    /// Reduce the temperature by the given amount each step.
+    Linear(f64),
+    /// Temperature decreases with the logarithm of the number of steps - never reaches 0.0, hence
+    /// needs a minimum temperature above 0.0.
+    Logarithmic,
+}
+
+///
+/// Configuration for simulated annealing.
+///
+pub struct AnnealingConfig {
+    /// Temperature to start with.
+    pub initial_temperature: f64,
+    /// Search stops once the temperature drops below this value.
+    pub min_temperature: f64,
+    /// Temperature schedule.
+    pub cooling: Cooling,
+    /// Maximum number of repairs proposed.
+    pub steps: i32,
+    /// Seed for the random number generator.
+    pub seed: u64,
+}
+
+impl Default for AnnealingConfig {
+    fn default() -> Self {
+        AnnealingConfig {
+            initial_temperature: 10.0,
+            min_temperature: 0.001,
+            cooling: Cooling::Geometric(0.95),
+            steps: 1000,
+            seed: 0,
+        }
+    }
+}
+
+///
+/// Result of simulated annealing.
+///
+pub struct AnnealingReport {
+    /// Indicates if an assignment without conflicts was found.
+    pub found: bool,
+    /// Number of repairs proposed.
+    pub steps: i32,
+    /// Number of repairs accepted.
+    pub accepted: i32,
+    /// Number of repairs rejected & undone.
+    pub rejected: i32,
+    /// Cost of the best assignment seen.
+    pub cost: f64,
 }
 
 ///
@@ -68,6 +230,287 @@ pub fn solve<PS: Problem>(ps: &mut PS, steps: i32) -> (bool, i32) {
     (found, iterations)
 }
 
+/// Rebuild the heap of conflicts from scratch.
+fn scan_conflicts<PS: Problem>(
+    ps: &PS,
+    live: &mut collections::HashMap<PS::Conflict, u64>,
+    heap: &mut collections::BinaryHeap<util::HeapEntry<(PS::Conflict, u64)>>,
+    version: &mut u64,
+) where
+    PS::Conflict: Clone + Eq + hash::Hash,
+{
+    live.clear();
+    heap.clear();
+    for item in ps.find_conflicts() {
+        add_conflict(item, live, heap, version);
+    }
+}
+
+/// Add a conflict to the heap - older entries of the same conflict become stale.
+fn add_conflict<C: Clone + Eq + hash::Hash>(
+    item: (C, f64),
+    live: &mut collections::HashMap<C, u64>,
+    heap: &mut collections::BinaryHeap<util::HeapEntry<(C, u64)>>,
+    version: &mut u64,
+) {
+    *version += 1;
+    live.insert(item.0.clone(), *version);
+    heap.push(util::HeapEntry::new_entry(
+        (item.0, *version),
+        (item.1, 0.0),
+    ));
+}
+
+///
+/// Solve a problem using iterative repair - keeps track of the conflicts incrementally if the
+/// problem reports the changes a repair made using `fix_conflict_delta`; otherwise falls back to
+/// finding all conflicts after each repair.
+///
+pub fn solve_incremental<PS: Problem>(ps: &mut PS, steps: i32) -> (bool, i32)
+where
+    PS::Conflict: Clone + Eq + hash::Hash,
+{
+    let mut found: bool = false;
+    let mut iterations: i32 = 0;
+    let mut version: u64 = 0;
+    let mut live: collections::HashMap<PS::Conflict, u64> = collections::HashMap::new();
+    let mut heap: collections::BinaryHeap<util::HeapEntry<(PS::Conflict, u64)>> =
+        collections::BinaryHeap::new();
+    scan_conflicts(ps, &mut live, &mut heap, &mut version);
+
+    while iterations < steps {
+        // skip entries of conflicts which have been resolved or re-added since.
+        let mut next: Option<(PS::Conflict, u64, f64)> = None;
+        while let Some(entry) = heap.pop() {
+            if live.get(&entry.state.0) == Some(&entry.state.1) {
+                next = Some((entry.state.0, entry.state.1, entry.keys.0));
+                break;
+            }
+        }
+        let (conflict, conflict_version, priority) = match next {
+            Some(item) => item,
+            None => {
+                found = true;
+                break;
+            }
+        };
+
+        match ps.fix_conflict_delta(&conflict) {
+            Some(delta) => {
+                for item in delta.removed.iter() {
+                    live.remove(item);
+                }
+                for item in delta.added {
+                    add_conflict(item, &mut live, &mut heap, &mut version);
+                }
+                // the conflict was neither resolved nor re-added --> needs to be looked at again.
+                if live.get(&conflict) == Some(&conflict_version) {
+                    add_conflict((conflict, priority), &mut live, &mut heap, &mut version);
+                }
+            }
+            None => scan_conflicts(ps, &mut live, &mut heap, &mut version),
+        }
+        iterations += 1;
+    }
+    if !found {
+        found = live.is_empty();
+    }
+    (found, iterations)
+}
+
+/// Pick the first conflict - in order of priority - which is not on the tabu list; if all are
+/// tabu the first one.
+fn select_conflict<C: PartialEq>(
+    mut heap: collections::BinaryHeap<util::HeapEntry<C>>,
+    tabu: &collections::VecDeque<C>,
+) -> Option<C> {
+    let mut first: Option<C> = None;
+    while let Some(entry) = heap.pop() {
+        if !tabu.contains(&entry.state) {
+            return Some(entry.state);
+        }
+        if first.is_none() {
+            first = Some(entry.state);
+        }
+    }
+    first
+}
+
+/// Determine the value which leads to the least number of conflicts.
+fn min_conflicts<PS: Repairable>(
+    ps: &mut PS,
+    conflict: &PS::Conflict,
+    rng: &mut util::Rng,
+) -> Option<PS::Value> {
+    let mut values = ps.values(conflict);
+    let snapshot = ps.assignment();
+    let mut min_val = usize::MAX;
+    let mut candidates: Vec<usize> = Vec::new();
+    for (i, value) in values.iter().enumerate() {
+        ps.assign(conflict, value);
+        let n_conflicts = ps.find_conflicts().count();
+        ps.restore(&snapshot);
+        if n_conflicts < min_val {
+            min_val = n_conflicts;
+            candidates.clear();
+        }
+        if n_conflicts == min_val {
+            candidates.push(i);
+        }
+    }
+    if candidates.is_empty() {
+        return None;
+    }
+    let index = candidates[rng.gen_range(candidates.len())];
+    Some(values.swap_remove(index))
+}
+
+///
+/// Solve a problem using iterative repair with the given configuration. Supports greedy and
+/// min-conflicts repairs, a tabu list, random walks and random restarts. Returns the best
+/// assignment seen - the problem is left in that assignment.
+///
+pub fn solve_with_config<PS: Repairable>(ps: &mut PS, config: &Config) -> Report<PS::Assignment>
+where
+    PS::Conflict: PartialEq,
+{
+    let mut rng = util::Rng::new(config.seed);
+    let mut best: PS::Assignment = ps.assignment();
+    let mut best_conflicts = usize::MAX;
+    let mut found = false;
+    let mut steps: i32 = 0;
+    let mut restarts: u32 = 0;
+
+    'outer: loop {
+        let mut tabu: collections::VecDeque<PS::Conflict> = collections::VecDeque::new();
+        for i in 0..=config.steps {
+            let mut heap: collections::BinaryHeap<util::HeapEntry<PS::Conflict>> =
+                collections::BinaryHeap::new();
+            for item in ps.find_conflicts() {
+                heap.push(util::HeapEntry::new_entry(item.0, (item.1, 0.0)));
+            }
+            if heap.len() < best_conflicts {
+                best_conflicts = heap.len();
+                best = ps.assignment();
+            }
+            if heap.is_empty() {
+                found = true;
+                break 'outer;
+            }
+            if i == config.steps {
+                break;
+            }
+
+            let conflict: PS::Conflict = select_conflict(heap, &tabu).unwrap();
+            if config.random_walk > 0.0 && rng.next_f64() < config.random_walk {
+                let mut values = ps.values(&conflict);
+                if !values.is_empty() {
+                    let value = values.swap_remove(rng.gen_range(values.len()));
+                    ps.assign(&conflict, &value);
+                }
+            } else {
+                match config.strategy {
+                    Strategy::Greedy => ps.fix_conflict(&conflict),
+                    Strategy::MinConflicts => {
+                        if let Some(value) = min_conflicts(ps, &conflict, &mut rng) {
+                            ps.assign(&conflict, &value);
+                        }
+                    }
+                }
+            }
+            tabu.push_back(conflict);
+            while tabu.len() > config.tabu {
+                tabu.pop_front();
+            }
+            steps += 1;
+        }
+        if restarts >= config.restarts {
+            break;
+        }
+        restarts += 1;
+        ps.randomize(rng.next_u64());
+    }
+    if !found {
+        ps.restore(&best);
+    }
+    Report {
+        found,
+        steps,
+        restarts,
+        conflicts: best_conflicts,
+        best,
+    }
+}
+
+/// Calculates the temperature for the given step.
+fn temperature(config: &AnnealingConfig, step: i32) -> f64 {
+    match config.cooling {
+        Cooling::Geometric(alpha) => config.initial_temperature * alpha.powi(step),
+        Cooling::Linear(delta) => config.initial_temperature - delta * step as f64,
+        Cooling::Logarithmic => config.initial_temperature / (1.0 + (1.0 + step as f64).ln()),
+    }
+}
+
+///
+/// Solve a problem using simulated annealing - a repair for a random conflict is proposed and
+/// accepted if it lowers the cost; otherwise it is accepted with a probability depending on the
+/// current temperature, or undone. The problem is left in the best assignment seen.
+///
+/// *Note*: panics for logarithmic cooling without a minimum temperature above 0.0.
+///
+pub fn anneal<PS: Evaluate>(ps: &mut PS, config: &AnnealingConfig) -> AnnealingReport {
+    assert!(
+        !matches!(config.cooling, Cooling::Logarithmic) || config.min_temperature > 0.0,
+        "Logarithmic cooling needs a minimum temperature above 0.0."
+    );
+    let mut rng = util::Rng::new(config.seed);
+    let mut report = AnnealingReport {
+        found: false,
+        steps: 0,
+        accepted: 0,
+        rejected: 0,
+        cost: ps.cost(),
+    };
+    let mut curr = report.cost;
+    let mut best = ps.assignment();
+
+    for step in 0..config.steps {
+        let mut conflicts: Vec<PS::Conflict> = ps.find_conflicts().map(|item| item.0).collect();
+        if conflicts.is_empty() {
+            report.found = true;
+            break;
+        }
+        let temp = temperature(config, step);
+        if temp < config.min_temperature {
+            break;
+        }
+
+        let conflict = conflicts.swap_remove(rng.gen_range(conflicts.len()));
+        let repair = ps.propose_repair(&conflict, rng.next_f64());
+        let cost = ps.cost();
+        let delta = cost - curr;
+        if delta <= 0.0 || rng.next_f64() < (-delta / temp).exp() {
+            curr = cost;
+            report.accepted += 1;
+            if cost < report.cost {
+                report.cost = cost;
+                best = ps.assignment();
+            }
+        } else {
+            ps.undo(repair);
+            report.rejected += 1;
+        }
+        report.steps += 1;
+    }
+    if report.found {
+        report.cost = curr;
+    } else {
+        ps.restore(&best);
+        report.found = ps.find_conflicts().next().is_none();
+    }
+    report
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections;
@@ -75,6 +518,7 @@ mod tests {
 
     use crate::iterative_repair;
     use crate::iterative_repair::Problem;
+    use crate::util;
 
     const SIZE: i32 = 3;
 
@@ -102,19 +546,19 @@ mod tests {
                         if chan.iden == self.channels[&(iden + i + 1)].iden {
                             res.push((
                                 (*iden, (iden + i + 1)),
-                                -1.0 * (chan.iden - self.channels[&(iden + i + 1)].iden) as f64,
+                                -((chan.iden - self.channels[&(iden + i + 1)].iden) as f64),
                             ));
                         }
                     }
                 }
                 // ngbh below me...
-                if (iden + SIZE) < self.channels.len() as i32 {
-                    if chan.iden == self.channels[&(iden + SIZE)].iden {
-                        res.push((
-                            (*iden, (iden + SIZE)),
-                            -1.0 * (chan.iden - self.channels[&(iden + SIZE)].iden) as f64,
-                        ));
-                    }
+                if (iden + SIZE) < self.channels.len() as i32
+                    && chan.iden == self.channels[&(iden + SIZE)].iden
+                {
+                    res.push((
+                        (*iden, (iden + SIZE)),
+                        -((chan.iden - self.channels[&(iden + SIZE)].iden) as f64),
+                    ));
                 }
             }
             res.into_iter()
@@ -129,6 +573,144 @@ mod tests {
         }
     }
 
+    impl iterative_repair::Repairable for ScheduleProblem {
+        type Value = i32;
+        type Assignment = Vec<i32>;
+
+        fn values(&self, _: &Self::Conflict) -> Vec<Self::Value> {
+            (0..17).collect()
+        }
+
+        fn assign(&mut self, conflict: &Self::Conflict, value: &Self::Value) {
+            self.channels.get_mut(&conflict.0).unwrap().iden = *value;
+        }
+
+        fn assignment(&self) -> Self::Assignment {
+            (0..self.channels.len() as i32)
+                .map(|i| self.channels[&i].iden)
+                .collect()
+        }
+
+        fn restore(&mut self, assignment: &Self::Assignment) {
+            for (i, iden) in assignment.iter().enumerate() {
+                self.channels.get_mut(&(i as i32)).unwrap().iden = *iden;
+            }
+        }
+
+        fn randomize(&mut self, seed: u64) {
+            let mut rng = util::Rng::new(seed);
+            for chan in self.channels.values_mut() {
+                chan.iden = rng.gen_range(17) as i32;
+            }
+        }
+    }
+
+    // more pigeons than holes - can never be solved.
+    struct Pigeons {
+        holes: Vec<usize>,
+        n_holes: usize,
+    }
+
+    impl Problem for Pigeons {
+        type Conflict = (usize, usize);
+        type Iter = vec::IntoIter<(Self::Conflict, f64)>;
+
+        fn find_conflicts(&self) -> Self::Iter {
+            let mut res = Vec::new();
+            for i in 0..self.holes.len() {
+                for j in (i + 1)..self.holes.len() {
+                    if self.holes[i] == self.holes[j] {
+                        res.push(((i, j), 1.0));
+                    }
+                }
+            }
+            res.into_iter()
+        }
+
+        fn fix_conflict(&mut self, conflict: &Self::Conflict) {
+            self.holes[conflict.0] = (self.holes[conflict.0] + 1) % self.n_holes;
+        }
+
+        fn fix_conflict_delta(
+            &mut self,
+            conflict: &Self::Conflict,
+        ) -> Option<iterative_repair::Delta<Self::Conflict>> {
+            let before: Vec<(usize, usize)> = self
+                .find_conflicts()
+                .map(|c| c.0)
+                .filter(|c| c.0 == conflict.0 || c.1 == conflict.0)
+                .collect();
+            self.fix_conflict(conflict);
+            let after: Vec<(usize, usize)> = self
+                .find_conflicts()
+                .map(|c| c.0)
+                .filter(|c| c.0 == conflict.0 || c.1 == conflict.0)
+                .collect();
+            Some(iterative_repair::Delta {
+                added: after
+                    .iter()
+                    .filter(|c| !before.contains(c))
+                    .map(|c| (*c, 1.0))
+                    .collect(),
+                removed: before.into_iter().filter(|c| !after.contains(c)).collect(),
+            })
+        }
+    }
+
+    impl iterative_repair::Repairable for Pigeons {
+        type Value = usize;
+        type Assignment = Vec<usize>;
+
+        fn values(&self, _: &Self::Conflict) -> Vec<Self::Value> {
+            (0..self.n_holes).collect()
+        }
+
+        fn assign(&mut self, conflict: &Self::Conflict, value: &Self::Value) {
+            self.holes[conflict.0] = *value;
+        }
+
+        fn assignment(&self) -> Self::Assignment {
+            self.holes.clone()
+        }
+
+        fn restore(&mut self, assignment: &Self::Assignment) {
+            self.holes = assignment.clone();
+        }
+
+        fn randomize(&mut self, seed: u64) {
+            let mut rng = util::Rng::new(seed);
+            for hole in self.holes.iter_mut() {
+                *hole = rng.gen_range(self.n_holes);
+            }
+        }
+    }
+
+    impl iterative_repair::Evaluate for Pigeons {
+        type Repair = (usize, usize);
+
+        fn cost(&self) -> f64 {
+            self.find_conflicts().len() as f64
+        }
+
+        fn propose_repair(&mut self, conflict: &Self::Conflict, sample: f64) -> Self::Repair {
+            let old = self.holes[conflict.0];
+            self.holes[conflict.0] = (sample * self.n_holes as f64) as usize;
+            (conflict.0, old)
+        }
+
+        fn undo(&mut self, repair: Self::Repair) {
+            self.holes[repair.0] = repair.1;
+        }
+    }
+
+    fn channels(idens: [i32; 9]) -> ScheduleProblem {
+        let mut data = collections::HashMap::new();
+        for (i, iden) in idens.iter().enumerate() {
+            data.insert(i as i32, Channel { iden: *iden });
+        }
+        ScheduleProblem { channels: data }
+    }
+
     // Test for success.
 
     #[test]
@@ -146,9 +728,152 @@ mod tests {
         let mut ps = ScheduleProblem { channels: data };
         iterative_repair::solve(&mut ps, 32);
     }
+
+    #[test]
+    fn test_scan_conflicts_for_success() {
+        let ps = channels([1, 4, 3, 3, 4, 1, 2, 1, 3]);
+        let mut live = collections::HashMap::new();
+        let mut heap = collections::BinaryHeap::new();
+        let mut version = 0;
+        iterative_repair::scan_conflicts(&ps, &mut live, &mut heap, &mut version);
+    }
+
+    #[test]
+    fn test_add_conflict_for_success() {
+        let mut live = collections::HashMap::new();
+        let mut heap = collections::BinaryHeap::new();
+        let mut version = 0;
+        iterative_repair::add_conflict((1, 1.0), &mut live, &mut heap, &mut version);
+    }
+
+    #[test]
+    fn test_solve_incremental_for_success() {
+        let mut ps = channels([1, 4, 3, 3, 4, 1, 2, 1, 3]);
+        iterative_repair::solve_incremental(&mut ps, 32);
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 0],
+            n_holes: 3,
+        };
+        iterative_repair::solve_incremental(&mut ps, 32);
+    }
+
+    #[test]
+    fn test_select_conflict_for_success() {
+        let heap = collections::BinaryHeap::new();
+        let tabu: collections::VecDeque<i32> = collections::VecDeque::new();
+        iterative_repair::select_conflict(heap, &tabu);
+    }
+
+    #[test]
+    fn test_min_conflicts_for_success() {
+        let mut ps = channels([1, 4, 3, 3, 4, 1, 2, 1, 3]);
+        let mut rng = util::Rng::new(0);
+        iterative_repair::min_conflicts(&mut ps, &(2, 8), &mut rng);
+    }
+
+    #[test]
+    fn test_solve_with_config_for_success() {
+        let mut ps = channels([1, 4, 3, 3, 4, 1, 2, 1, 3]);
+        let config = iterative_repair::Config {
+            strategy: iterative_repair::Strategy::MinConflicts,
+            ..Default::default()
+        };
+        iterative_repair::solve_with_config(&mut ps, &config);
+    }
+
+    #[test]
+    fn test_temperature_for_success() {
+        let config = iterative_repair::AnnealingConfig::default();
+        iterative_repair::temperature(&config, 1);
+    }
+
+    #[test]
+    fn test_anneal_for_success() {
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 0],
+            n_holes: 3,
+        };
+        let config = iterative_repair::AnnealingConfig::default();
+        iterative_repair::anneal(&mut ps, &config);
+    }
+
     // Test for failure.
 
-    // TODO: figure out what to do about this...
+    #[test]
+    fn test_solve_incremental_for_failure() {
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 0, 0],
+            n_holes: 3,
+        };
+        let res = iterative_repair::solve_incremental(&mut ps, 20);
+        assert!(!res.0);
+        assert_eq!(res.1, 20);
+    }
+
+    #[test]
+    fn test_anneal_for_failure() {
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 0, 0],
+            n_holes: 3,
+        };
+        let config = iterative_repair::AnnealingConfig {
+            steps: 50,
+            ..Default::default()
+        };
+        let res = iterative_repair::anneal(&mut ps, &config);
+        assert!(!res.found);
+        assert_eq!(res.steps, 50);
+        assert_eq!(res.accepted + res.rejected, 50);
+        assert_eq!(res.cost, ps.find_conflicts().len() as f64);
+
+        // temperature drops too fast - stop early.
+        let config = iterative_repair::AnnealingConfig {
+            cooling: iterative_repair::Cooling::Linear(5.0),
+            ..Default::default()
+        };
+        let res = iterative_repair::anneal(&mut ps, &config);
+        assert!(!res.found);
+        assert_eq!(res.steps, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "minimum temperature")]
+    fn test_anneal_logarithmic_for_failure() {
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 0, 0],
+            n_holes: 3,
+        };
+        let config = iterative_repair::AnnealingConfig {
+            cooling: iterative_repair::Cooling::Logarithmic,
+            min_temperature: 0.0,
+            ..Default::default()
+        };
+        iterative_repair::anneal(&mut ps, &config);
+    }
+
+    #[test]
+    fn test_solve_with_config_for_failure() {
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 0, 0],
+            n_holes: 3,
+        };
+        let config = iterative_repair::Config {
+            strategy: iterative_repair::Strategy::MinConflicts,
+            steps: 5,
+            tabu: 2,
+            random_walk: 0.2,
+            restarts: 3,
+            seed: 7,
+        };
+        let res = iterative_repair::solve_with_config(&mut ps, &config);
+        assert!(!res.found);
+        assert_eq!(res.steps, 20);
+        assert_eq!(res.restarts, 3);
+        // best possible is a single pair of pigeons sharing a hole - problem is left in best state.
+        assert_eq!(res.conflicts, 1);
+        assert_eq!(ps.holes, res.best);
+        assert_eq!(ps.find_conflicts().len(), 1);
+    }
 
     // Test for sanity.
 
@@ -167,8 +892,228 @@ mod tests {
         let mut ps = ScheduleProblem { channels: data };
         // 10 steps will do @ only 2 conflicts.
         let res = iterative_repair::solve(&mut ps, 10);
-        assert_eq!(res.0, true); // solution is possible...
+        assert!(res.0); // solution is possible...
         assert_eq!(res.1, 2); // two conflicts to repair...
         assert_eq!(ps.find_conflicts().len(), 0);
     }
+
+    #[test]
+    fn test_scan_conflicts_for_sanity() {
+        let ps = Pigeons {
+            holes: vec![0, 0, 0],
+            n_holes: 3,
+        };
+        let mut live = collections::HashMap::new();
+        let mut heap = collections::BinaryHeap::new();
+        let mut version = 0;
+        iterative_repair::add_conflict(((5, 6), 1.0), &mut live, &mut heap, &mut version);
+
+        // previous content is dropped.
+        iterative_repair::scan_conflicts(&ps, &mut live, &mut heap, &mut version);
+        assert_eq!(live.len(), 3);
+        assert_eq!(heap.len(), 3);
+        assert!(!live.contains_key(&(5, 6)));
+    }
+
+    #[test]
+    fn test_add_conflict_for_sanity() {
+        let mut live = collections::HashMap::new();
+        let mut heap = collections::BinaryHeap::new();
+        let mut version = 0;
+
+        // re-adding a conflict makes the older heap entry stale.
+        iterative_repair::add_conflict((1, 1.0), &mut live, &mut heap, &mut version);
+        iterative_repair::add_conflict((1, 0.5), &mut live, &mut heap, &mut version);
+        assert_eq!(live[&1], 2);
+        assert_eq!(heap.len(), 2);
+        assert_eq!(heap.peek().unwrap().state, (1, 2));
+        assert_eq!(heap.peek().unwrap().keys, (0.5, 0.0));
+    }
+
+    #[test]
+    fn test_solve_incremental_for_sanity() {
+        // no delta provided --> same result as a full scan each step.
+        let mut ps = channels([7, 12, 16, 8, 3, 16, 4, 4, 11]);
+        let res = iterative_repair::solve_incremental(&mut ps, 10);
+        assert!(res.0);
+        assert_eq!(res.1, 2);
+        assert_eq!(ps.find_conflicts().len(), 0);
+
+        // conflicts tracked using the delta.
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 0, 1],
+            n_holes: 4,
+        };
+        let res = iterative_repair::solve_incremental(&mut ps, 10);
+        assert!(res.0);
+        assert_eq!(ps.find_conflicts().len(), 0);
+
+        // already solved.
+        let res = iterative_repair::solve_incremental(&mut ps, 10);
+        assert_eq!(res, (true, 0));
+    }
+
+    #[test]
+    fn test_temperature_for_sanity() {
+        let mut config = iterative_repair::AnnealingConfig {
+            initial_temperature: 8.0,
+            cooling: iterative_repair::Cooling::Geometric(0.5),
+            ..Default::default()
+        };
+        assert_eq!(iterative_repair::temperature(&config, 0), 8.0);
+        assert_eq!(iterative_repair::temperature(&config, 2), 2.0);
+
+        config.cooling = iterative_repair::Cooling::Linear(1.5);
+        assert_eq!(iterative_repair::temperature(&config, 2), 5.0);
+
+        config.cooling = iterative_repair::Cooling::Logarithmic;
+        assert_eq!(iterative_repair::temperature(&config, 0), 8.0);
+        assert!(iterative_repair::temperature(&config, 10) < 8.0);
+    }
+
+    #[test]
+    fn test_anneal_for_sanity() {
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 0],
+            n_holes: 3,
+        };
+        let config = iterative_repair::AnnealingConfig {
+            seed: 3,
+            ..Default::default()
+        };
+        let res = iterative_repair::anneal(&mut ps, &config);
+        assert!(res.found);
+        assert_eq!(res.cost, 0.0);
+        assert_eq!(ps.find_conflicts().len(), 0);
+        assert!(res.accepted > 0);
+
+        // cold start - only improvements are accepted.
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 1, 2],
+            n_holes: 4,
+        };
+        let config = iterative_repair::AnnealingConfig {
+            initial_temperature: 0.01,
+            min_temperature: 0.0,
+            steps: 1,
+            seed: 1,
+            ..Default::default()
+        };
+        let res = iterative_repair::anneal(&mut ps, &config);
+        assert!(res.cost <= 1.0);
+        assert_eq!(res.cost, ps.find_conflicts().len() as f64);
+
+        // hot & unsolvable - uphill moves late on don't lose the best assignment.
+        let mut ps = Pigeons {
+            holes: vec![0; 9],
+            n_holes: 3,
+        };
+        let config = iterative_repair::AnnealingConfig {
+            initial_temperature: 100.0,
+            cooling: iterative_repair::Cooling::Geometric(1.0),
+            steps: 200,
+            seed: 7,
+            ..Default::default()
+        };
+        let res = iterative_repair::anneal(&mut ps, &config);
+        assert!(!res.found);
+        assert_eq!(res.cost, 9.0);
+        assert_eq!(ps.find_conflicts().len(), 9);
+    }
+
+    #[test]
+    fn test_select_conflict_for_sanity() {
+        let heap = || {
+            let mut heap = collections::BinaryHeap::new();
+            heap.push(util::HeapEntry::new_entry(1, (1.0, 0.0)));
+            heap.push(util::HeapEntry::new_entry(2, (3.0, 0.0)));
+            heap.push(util::HeapEntry::new_entry(3, (2.0, 0.0)));
+            heap
+        };
+        let mut tabu: collections::VecDeque<i32> = collections::VecDeque::new();
+
+        // ordered by priority.
+        let res = iterative_repair::select_conflict(heap(), &tabu);
+        assert_eq!(res, Some(1));
+
+        // skip the tabu ones.
+        tabu.push_back(1);
+        let res = iterative_repair::select_conflict(heap(), &tabu);
+        assert_eq!(res, Some(3));
+
+        // all are tabu --> first one.
+        tabu.push_back(3);
+        tabu.push_back(2);
+        let res = iterative_repair::select_conflict(heap(), &tabu);
+        assert_eq!(res, Some(1));
+
+        // no conflicts.
+        let res = iterative_repair::select_conflict(collections::BinaryHeap::new(), &tabu);
+        assert_eq!(res, None);
+    }
+
+    #[test]
+    fn test_min_conflicts_for_sanity() {
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 1],
+            n_holes: 3,
+        };
+        let mut rng = util::Rng::new(0);
+
+        // only hole 2 resolves all conflicts - problem itself is not changed.
+        let res = iterative_repair::min_conflicts(&mut ps, &(0, 1), &mut rng);
+        assert_eq!(res, Some(2));
+        assert_eq!(ps.holes, vec![0, 0, 1]);
+
+        // no values --> nothing to assign.
+        let mut ps = Pigeons {
+            holes: vec![0, 0],
+            n_holes: 0,
+        };
+        let res = iterative_repair::min_conflicts(&mut ps, &(0, 1), &mut rng);
+        assert_eq!(res, None);
+    }
+
+    #[test]
+    fn test_solve_with_config_for_sanity() {
+        // min-conflicts resolves the conflicts in two steps.
+        let mut ps = channels([7, 12, 16, 8, 3, 16, 4, 4, 11]);
+        let config = iterative_repair::Config {
+            strategy: iterative_repair::Strategy::MinConflicts,
+            ..Default::default()
+        };
+        let res = iterative_repair::solve_with_config(&mut ps, &config);
+        assert!(res.found);
+        assert_eq!(res.steps, 2);
+        assert_eq!(res.restarts, 0);
+        assert_eq!(res.conflicts, 0);
+        assert_eq!(ps.find_conflicts().len(), 0);
+
+        // greedy fix keeps on cycling in hole 0 & 1 (tabu list forces other conflicts) - a random
+        // restart gets us out.
+        let mut ps = Pigeons {
+            holes: vec![0, 0, 1],
+            n_holes: 3,
+        };
+        let config = iterative_repair::Config {
+            steps: 1,
+            tabu: 1,
+            restarts: 10,
+            ..Default::default()
+        };
+        let res = iterative_repair::solve_with_config(&mut ps, &config);
+        assert!(res.found);
+        assert_eq!(res.conflicts, 0);
+        assert_eq!(ps.find_conflicts().len(), 0);
+
+        // already solved.
+        let mut ps = Pigeons {
+            holes: vec![0, 1, 2],
+            n_holes: 3,
+        };
+        let res = iterative_repair::solve_with_config(&mut ps, &config);
+        assert!(res.found);
+        assert_eq!(res.steps, 0);
+        assert_eq!(res.best, vec![0, 1, 2]);
+    }
 }
@@ -11,16 +11,50 @@ pub mod maze;
 
 /// Module with some generic traits used by various algorithms.
 pub mod planner;
+/// Module with the typed, versioned messages exchanged by multi-agent planners.
+pub mod protocol;
 
 /// Module implementing the Anytime Dynamic A* algorithm.
 pub mod any_dyn_astar;
+/// Module implementing conflict-based search (CBS & ECBS) for multi-agent pathfinding.
+pub mod cbs;
+/// Module implementing coalition structure generation with worst case guarantees.
+pub mod coalition;
+/// Module implementing cooperative pathfinding (HCA* & WHCA*).
+pub mod cooperative;
+/// Module implementing Knuth's Dancing Links (Algorithm X) for exact cover problems.
+pub mod dlx;
 /// Module implementing the D* lite algorithm.
 pub mod dstar_lite;
-/// Module implementing an iterative repair algorithm.
+/// Module implementing domain-independent heuristics for STRIPS tasks - h_max, h_add, h_FF, LM-cut
+/// & landmark counting.
+pub mod heuristics;
+/// Module implementing a hierarchical task network (HTN) planner - SHOP2 style totally ordered
+/// decomposition.
+pub mod htn;
+/// Module implementing an iterative repair algorithm - incl. min-conflicts and simulated annealing.
 pub mod iterative_repair;
-/// Module implementing a UCT style Monte-Carlo Tree Search algorithm.
+/// Module implementing a UCT style Monte-Carlo Tree Search algorithm - incl. RAVE and progressive
+/// widening.
 pub mod mcts;
+/// Module implementing a parser & grounder for PDDL domains & problems.
+pub mod pddl;
+/// Module for declaring schedules which get repaired using the iterative repair algorithm.
+pub mod scheduling;
+/// Module implementing grounded STRIPS tasks with bitset states & a match tree successor generator.
+pub mod strips;
+/// Module implementing task allocation via coalition formation.
+pub mod task_allocation;
+/// Module implementing a validator for plans found by the solvers & for PDDL plans.
+pub mod validator;
 
+/// Module implementing an in-process message bus to run multi-agent algorithms without external
+/// infrastructure.
+#[cfg(feature = "multi_agent")]
+pub mod bus;
+/// Module implementing the contract net protocol for task announcement, bidding & awarding.
+#[cfg(feature = "multi_agent")]
+pub mod contract_net;
 /// Module implementing the Multi-Agent Distributed forward A* search algorithm.
 #[cfg(feature = "multi_agent")]
 pub mod mad_astar;
@@ -1,18 +1,344 @@
 use std::cmp;
 use std::collections;
+use std::error;
+use std::fmt;
+use std::hash;
+use std::sync;
+use std::sync::atomic;
 use std::thread;
 use std::time;
 
+use crate::bus;
 use crate::planner;
+use crate::protocol;
 use crate::util;
 
 use rusty_agent::agent;
+use rusty_agent::agent::Agent;
+
+///
+/// Configuration for the distributed search.
+///
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Id of the agent & number of agents in the token ring - enables termination detection. All
+    /// agents need to be able to reach each other through broadcasts.
+    pub ring: Option<(usize, usize)>,
+    /// Time (in ms) to wait before checking for new messages when there is nothing to do.
+    pub poll_interval: u64,
+    /// Time (in ms) after which the search (incl. the traceback) gives up.
+    pub timeout: Option<u64>,
+    /// Number of states expanded per round - the states to share are sent in a single batch at
+    /// the end of the round.
+    pub batch_size: usize,
+    /// Counters of the messages exchanged - shared by all clones of the configuration.
+    pub traffic: sync::Arc<Traffic>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            ring: None,
+            poll_interval: 250,
+            timeout: None,
+            batch_size: 16,
+            traffic: sync::Arc::new(Traffic::default()),
+        }
+    }
+}
+
+///
+/// Counters of the messages exchanged during the search - for tuning the batching.
+///
+#[derive(Debug, Default)]
+pub struct Traffic {
+    sent: atomic::AtomicUsize,
+    received: atomic::AtomicUsize,
+    shared: atomic::AtomicUsize,
+    suppressed: atomic::AtomicUsize,
+}
+
+impl Traffic {
+    /// Number of messages broadcast - a batch counts as one.
+    pub fn sent(&self) -> usize {
+        self.sent.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Number of messages received - a batch counts as one.
+    pub fn received(&self) -> usize {
+        self.received.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Number of states shared with others.
+    pub fn shared(&self) -> usize {
+        self.shared.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Number of duplicate or dominated states dropped - before sending & on receipt.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed.load(atomic::Ordering::Relaxed)
+    }
+
+    fn add(counter: &atomic::AtomicUsize, n: usize) {
+        counter.fetch_add(n, atomic::Ordering::Relaxed);
+    }
+}
+
+///
+/// Reasons the distributed search can fail.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Error {
+    /// All agents ran out of states to expand - the goal can not be reached.
+    NoPlan,
+    /// The search did not finish in time.
+    Timeout,
+    /// The agents ended up with different joint plans.
+    Mismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NoPlan => write!(f, "no plan exists"),
+            Error::Timeout => write!(f, "search timed out"),
+            Error::Mismatch => write!(f, "agents disagree on the plan"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+///
+/// Step of a joint plan & the agent responsible for it.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step<S> {
+    /// Id of the agent in the token ring which acts.
+    pub agent: usize,
+    /// State the agent acts from.
+    pub from: S,
+    /// State reached by the agent's action.
+    pub to: S,
+}
+
+/// Wraps an agent & counts the messages it broadcasts & retrieves.
+struct Counting<'a, A> {
+    agent: &'a A,
+    traffic: &'a Traffic,
+}
+
+impl<'a, A: agent::Agent> agent::Agent for Counting<'a, A> {
+    fn retrieve(&self) -> Vec<String> {
+        let msgs = self.agent.retrieve();
+        Traffic::add(&self.traffic.received, msgs.len());
+        msgs
+    }
+
+    fn broadcast(&self, msg: &str) {
+        Traffic::add(&self.traffic.sent, 1);
+        self.agent.broadcast(msg)
+    }
+}
+
+///
+/// States to share at the end of the current round - only the cheapest path to a state gets
+/// shared, & only if it improves on what was shared before.
+///
+struct Outbox<S> {
+    pending: Vec<protocol::Message<S>>,
+    /// Cheapest g value shared (or about to be) per state.
+    shared: collections::HashMap<S, f64>,
+    suppressed: usize,
+}
+
+impl<S: Copy + Eq + hash::Hash> Outbox<S> {
+    fn new() -> Outbox<S> {
+        Outbox {
+            pending: Vec::new(),
+            shared: collections::HashMap::new(),
+            suppressed: 0,
+        }
+    }
+
+    /// Queue a state or goal message.
+    fn push(&mut self, msg: protocol::Message<S>) {
+        let (state, g) = match msg {
+            protocol::Message::State { state, g, .. }
+            | protocol::Message::Goal { state, g, .. } => (state, g),
+            _ => unreachable!("Only states get batched."),
+        };
+        if self.shared.get(&state).is_some_and(|old| *old <= g) {
+            self.suppressed += 1;
+            return;
+        }
+        self.shared.insert(state, g);
+        let n = self.pending.len();
+        self.pending.retain(|msg| {
+            !matches!(msg, protocol::Message::State { state: s, .. }
+                | protocol::Message::Goal { state: s, .. } if *s == state)
+        });
+        self.suppressed += n - self.pending.len();
+        self.pending.push(msg);
+    }
+
+    /// Remember a state received from others - no need to echo it back.
+    fn seen(&mut self, state: S, g: f64) {
+        if self.shared.get(&state).is_none_or(|old| *old > g) {
+            self.shared.insert(state, g);
+        }
+    }
+
+    /// Send all queued messages as one batch - returns the number of messages sent.
+    fn flush<A: agent::Agent, PS: planner::SharedMessages<State = S>>(
+        &mut self,
+        agent: &A,
+        ps: &PS,
+        traffic: &Traffic,
+    ) -> usize {
+        Traffic::add(&traffic.suppressed, self.suppressed);
+        self.suppressed = 0;
+        let n = self.pending.len();
+        if n > 0 {
+            let msgs: Vec<String> = self.pending.drain(..).map(|msg| ps.encode(&msg)).collect();
+            agent.broadcast(&protocol::batch(&msgs));
+            Traffic::add(&traffic.shared, n);
+        }
+        n
+    }
+}
+
+/// Token passed along the ring during a probe.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Token {
+    /// Messages sent minus messages received by the agents visited so far.
+    sum: i64,
+    black: bool,
+    /// Cheapest goal found by the agents visited so far & the agent which found it.
+    best: f64,
+    owner: usize,
+}
+
+///
+/// Termination detection following Safra's algorithm - a token travels along the ring of agents
+/// whenever they are passive, summing up the number of messages sent minus received. Agents
+/// which received a message since the token last passed are black. Once the token makes it back
+/// to the initiator (agent 0) white and with a sum of 0 no messages are in flight & all agents
+/// are passive. On its way the token also collects the cheapest goal found - once termination is
+/// detected no agent can find a cheaper one, which proves the plan to be optimal.
+///
+struct Termination {
+    id: usize,
+    n_agents: usize,
+    /// Messages sent minus messages received.
+    counter: i64,
+    black: bool,
+    /// Cost of the cheapest goal this agent found itself.
+    own: f64,
+    /// Token if held by this agent.
+    token: Option<Token>,
+    probing: bool,
+}
+
+impl Termination {
+    fn new(id: usize, n_agents: usize) -> Termination {
+        Termination {
+            id,
+            n_agents,
+            counter: 0,
+            black: false,
+            own: f64::INFINITY,
+            token: None,
+            probing: false,
+        }
+    }
+
+    /// Broadcast messages reach all other agents - a batch counts once per message in it.
+    fn sent(&mut self, n_msgs: usize) {
+        self.counter += (n_msgs * (self.n_agents - 1)) as i64;
+    }
+
+    fn received(&mut self) {
+        self.counter -= 1;
+        self.black = true;
+    }
+
+    fn found_goal(&mut self, cost: f64) {
+        self.own = self.own.min(cost);
+    }
+
+    fn on_token(&mut self, target: usize, token: Token) {
+        if target == self.id {
+            self.token = Some(token);
+        }
+    }
+
+    /// Called while the agent is passive - passes on the token; returns the cheapest goal & the
+    /// agent which found it (infinite cost if there is none) if termination got detected.
+    fn passive<A: agent::Agent, PS: planner::SharedMessages>(
+        &mut self,
+        agent: &A,
+        ps: &PS,
+        goal: &PS::State,
+    ) -> Option<(f64, usize)> {
+        if self.n_agents <= 1 {
+            return Some((self.own, self.id));
+        }
+        let next = (self.id + 1) % self.n_agents;
+        match (self.id, self.token.take()) {
+            (0, None) if !self.probing => {
+                self.probing = true;
+            }
+            (0, Some(token)) => {
+                if !token.black && !self.black && token.sum + self.counter == 0 {
+                    return Some((token.best, token.owner));
+                }
+            }
+            (_, Some(token)) => {
+                let (best, owner) = if self.own < token.best {
+                    (self.own, self.id)
+                } else {
+                    (token.best, token.owner)
+                };
+                agent.broadcast(&ps.encode(&protocol::Message::Token {
+                    goal: ps.project(goal),
+                    target: next,
+                    sum: token.sum + self.counter,
+                    black: token.black || self.black,
+                    best,
+                    owner,
+                }));
+                self.black = false;
+                return None;
+            }
+            _ => return None,
+        }
+        // (re)start a probe.
+        self.black = false;
+        agent.broadcast(&ps.encode(&protocol::Message::Token {
+            goal: ps.project(goal),
+            target: next,
+            sum: 0,
+            black: false,
+            best: self.own,
+            owner: self.id,
+        }));
+        None
+    }
+}
+
+/// Check if the deadline has passed.
+fn expired(deadline: Option<time::Instant>) -> bool {
+    deadline.is_some_and(|d| time::Instant::now() >= d)
+}
 
 struct StateValues {
     g_val: f64,
     h_val: f64,
 }
 
+/// Process a state received from others - returns false if it got dropped as we already know a
+/// path to it which is at least as cheap.
 fn process_message<PS: planner::ProblemSpace>(
     ps: &PS,
     s: PS::State,
@@ -20,32 +346,26 @@ fn process_message<PS: planner::ProblemSpace>(
     goal: PS::State,
     data: &mut collections::HashMap<PS::State, StateValues>,
     open: &mut collections::BinaryHeap<util::HeapEntry<PS::State>>,
-    closed: &mut collections::HashMap<PS::State, f64>,
-) {
-    // TODO: once open has contains method - remove this!
-    let mut in_open: bool = false;
-    for item in open.iter() {
-        if item.state == s {
-            in_open = true;
-            break;
-        }
-    }
-    if !(in_open && closed.contains_key(&s)) || data[&s].g_val > para.g_val {
-        open.push(util::HeapEntry::new_entry(s, (para.g_val, 0.0)));
-        let h_val = cmp::max(ps.heuristic(&s, &goal) as i64, para.h_val as i64);
-        data.insert(
-            s,
-            StateValues {
-                g_val: para.g_val,
-                h_val: h_val as f64,
-            },
-        );
+) -> bool {
+    if data.get(&s).is_some_and(|vals| vals.g_val <= para.g_val) {
+        return false;
     }
+    // cheaper path - needs to be expanded (again); keyed like local states by g + h.
+    let h_val = f64::max(ps.heuristic(&s, &goal), para.h_val);
+    open.push(util::HeapEntry::new_entry(s, (para.g_val + h_val, 0.0)));
+    data.insert(
+        s,
+        StateValues {
+            g_val: para.g_val,
+            h_val,
+        },
+    );
+    true
 }
 
-fn expand<A: agent::Agent, PS: planner::ProblemSpace + planner::SharedStates>(
+fn expand<PS: planner::SharedMessages>(
     ps: &PS,
-    agent: &A,
+    outbox: &mut Outbox<PS::State>,
     s: PS::State,
     goal: PS::State,
     data: &mut collections::HashMap<PS::State, StateValues>,
@@ -54,17 +374,26 @@ fn expand<A: agent::Agent, PS: planner::ProblemSpace + planner::SharedStates>(
 ) -> bool {
     // if we've found the goal --> tell others.
     if s == goal {
-        agent.broadcast(&ps.serialize(0, &s, vec![data[&s].g_val, data[&s].h_val]));
+        outbox.push(protocol::Message::Goal {
+            state: ps.project(&s),
+            g: data[&s].g_val,
+            h: ps.public_heuristic(&s, &goal).unwrap_or(data[&s].h_val),
+        });
         closed.insert(s, data[&s].g_val + ps.heuristic(&s, &goal));
         return true;
     }
 
-    // if we've found a public state --> tell others.
-    if ps.is_public(&s)
+    // if we've found a public state --> tell others; only its projection & an estimate which does
+    // not reveal private facts get shared.
+    if ps.is_shared(&s)
         && (!closed.contains_key(&s)
             || closed.get(&s).unwrap() > &(data[&s].g_val + ps.heuristic(&s, &goal)))
     {
-        agent.broadcast(&ps.serialize(0, &s, vec![data[&s].g_val, data[&s].h_val]))
+        outbox.push(protocol::Message::State {
+            state: ps.project(&s),
+            g: data[&s].g_val,
+            h: ps.public_heuristic(&s, &goal).unwrap_or(data[&s].h_val),
+        })
     }
 
     // Add to closed list.
@@ -95,70 +424,231 @@ fn expand<A: agent::Agent, PS: planner::ProblemSpace + planner::SharedStates>(
     false
 }
 
-fn traceback<A: agent::Agent, PS: planner::ProblemSpace + planner::SharedStates>(
+/// Find the predecessor of the state through which it got reached the cheapest - incl. the cost.
+fn best_pred<PS: planner::ProblemSpace>(
+    ps: &PS,
+    s: &PS::State,
+    goal: &PS::State,
+    closed: &collections::HashMap<PS::State, f64>,
+) -> Option<(PS::State, f64)> {
+    let mut res: Option<(PS::State, f64)> = None;
+    for pred in ps.pred(s) {
+        // closed holds f values - so take off the heuristic.
+        if let Some(f_val) = closed.get(&pred.0) {
+            let cost = f_val - ps.heuristic(&pred.0, goal) + pred.1;
+            if res.is_none_or(|(_, min_cost)| cost < min_cost) {
+                res = Some((pred.0, cost));
+            }
+        }
+    }
+    res
+}
+
+///
+/// Trace back the plan from the goal - agents trace their part until they reach a public state &
+/// hand over. With the token ring configured every step is shared & the joint plan gets returned
+/// to all agents; otherwise only the local part is returned.
+///
+#[allow(clippy::too_many_arguments)]
+fn traceback<A: agent::Agent, PS: planner::SharedMessages>(
     agent: &A,
     ps: &PS,
     start: PS::State,
     goal: PS::State,
     closed: collections::HashMap<PS::State, f64>,
-) -> Vec<PS::State> {
-    let mut res = Vec::new();
-    let mut done = false;
+    leader: bool,
+    mut inbox: Vec<String>,
+    config: &Config,
+    deadline: Option<time::Instant>,
+) -> Result<Vec<Step<PS::State>>, Error> {
+    let id = config.ring.map(|(id, _)| id);
+    let me = id.unwrap_or(0);
+    // steps by their distance to the goal.
+    let mut steps: collections::HashMap<usize, Step<PS::State>> = collections::HashMap::new();
+    let mut length: Option<usize> = None;
     let mut curr: PS::State = goal;
+    let mut depth = 0;
+    // only the agent which found the goal starts - others wait for their turn.
+    let mut active = leader;
 
-    while !done {
-        let preds = ps.pred(&curr);
-        if preds.count() == 0 {
-            let revd: Vec<String> = agent.retrieve();
-            if !revd.is_empty() {
-                for msg in revd {
-                    let (msg_id, p_state, _) = ps.deserialize(msg);
-                    if msg_id == 1 {
-                        curr = p_state;
-                    }
+    loop {
+        inbox.extend(agent.retrieve());
+        for msg in inbox.drain(..) {
+            match ps.decode(&msg) {
+                Ok(protocol::Message::Traceback { state, depth: d }) => {
+                    curr = ps.restore(&state);
+                    depth = d;
+                    active = true;
                 }
-            } else {
-                thread::sleep(time::Duration::from_millis(250));
-                continue;
+                Ok(protocol::Message::Step {
+                    state,
+                    agent: a,
+                    depth: d,
+                }) => {
+                    // successor gets filled in once the plan is complete.
+                    let state = ps.restore(&state);
+                    let step = Step {
+                        agent: a,
+                        from: state,
+                        to: state,
+                    };
+                    steps.insert(d, step);
+                }
+                Ok(protocol::Message::Complete { length: n, .. }) => length = Some(n),
+                _ => {}
             }
         }
 
-        // find next shortest step to take...
-        let mut min_cost = f64::INFINITY;
-        let mut next_state = curr;
-        for pred in ps.pred(&curr) {
-            if closed.contains_key(&pred.0) && (closed[&pred.0] + pred.1) < min_cost {
-                min_cost = closed[&pred.0] + pred.1;
-                next_state = pred.0;
+        if active && (curr == start || best_pred(ps, &curr, &goal, &closed).is_some()) {
+            loop {
+                // we are done if we found the start.
+                if curr == start {
+                    length = Some(depth);
+                    if id.is_some() {
+                        agent.broadcast(&ps.encode(&protocol::Message::Complete {
+                            goal: ps.project(&goal),
+                            length: depth,
+                        }));
+                    }
+                    break;
+                }
+                let next = match best_pred(ps, &curr, &goal, &closed) {
+                    Some((next, _)) => next,
+                    None => break,
+                };
+                steps.insert(
+                    depth,
+                    Step {
+                        agent: me,
+                        from: next,
+                        to: curr,
+                    },
+                );
+                if id.is_some() {
+                    agent.broadcast(&ps.encode(&protocol::Message::Step {
+                        state: ps.project(&next),
+                        agent: me,
+                        depth,
+                    }));
+                }
+                depth += 1;
+                curr = next;
+
+                // if we found a public state tell ngbh. sys. - for joint plans only if our own
+                // path to it is not the cheapest one.
+                if curr != start && ps.is_shared(&curr) {
+                    let g_val = closed[&curr] - ps.heuristic(&curr, &goal);
+                    let local = id.is_some()
+                        && best_pred(ps, &curr, &goal, &closed)
+                            .is_some_and(|(_, cost)| cost <= g_val + 1e-9);
+                    if !local {
+                        agent.broadcast(&ps.encode(&protocol::Message::Traceback {
+                            state: ps.project(&curr),
+                            depth,
+                        }));
+                        break;
+                    }
+                }
+            }
+            if id.is_none() {
+                let mut res: Vec<(usize, Step<PS::State>)> = steps.into_iter().collect();
+                res.sort_by_key(|(d, _)| cmp::Reverse(*d));
+                return Ok(res.into_iter().map(|(_, step)| step).collect());
             }
         }
-        res.push(next_state);
-        curr = next_state;
+        active = false;
 
-        // if we found a public state tell ngbh. sys.
-        if ps.is_public(&curr) {
-            agent.broadcast(&ps.serialize(1, &curr, vec![]));
-            done = true;
+        if let Some(n) = length {
+            if (0..n).all(|d| steps.contains_key(&d)) {
+                let mut res = Vec::with_capacity(n);
+                for d in (0..n).rev() {
+                    let mut step = steps[&d];
+                    step.to = if d == 0 { goal } else { steps[&(d - 1)].from };
+                    res.push(step);
+                }
+                return Ok(res);
+            }
         }
-
-        // we are done if we found the start too.
-        if curr == start {
-            done = true;
+        if expired(deadline) {
+            return Err(Error::Timeout);
         }
+        thread::sleep(time::Duration::from_millis(config.poll_interval));
     }
-    res.reverse(); // traceback -> so let's reverse...
-    res
 }
 
 ///
 /// Find a plan for multiple collaborative systems/agents.
 ///
-pub fn solve<A: agent::Agent, PS: planner::ProblemSpace + planner::SharedStates>(
+/// *Note*: Waits forever if no plan can be found - see `solve_with_config` for termination
+/// detection & timeouts.
+///
+pub fn solve<A: agent::Agent, PS: planner::SharedMessages>(
     agent: &A,
     ps: &PS,
     start: PS::State,
     goal: PS::State,
 ) -> Vec<PS::State> {
+    solve_with_config(agent, ps, start, goal, &Config::default()).unwrap_or_default()
+}
+
+///
+/// Find a plan for multiple collaborative systems/agents - fails if all agents ran out of states
+/// to expand (needs the token ring to be configured) or the search timed out. Returns the states
+/// this agent needs to act from.
+///
+/// With the token ring configured finding a goal does not end the search right away: agents keep
+/// expanding states which could still lead to a cheaper goal, until the termination detection
+/// proves that none is left. Only then the agent with the cheapest goal starts the traceback -
+/// hence the plan is cost-optimal (given an admissible heuristic).
+///
+pub fn solve_with_config<A: agent::Agent, PS: planner::SharedMessages>(
+    agent: &A,
+    ps: &PS,
+    start: PS::State,
+    goal: PS::State,
+    config: &Config,
+) -> Result<Vec<PS::State>, Error> {
+    let id = config.ring.map_or(0, |(id, _)| id);
+    let steps = search(agent, ps, start, goal, config)?;
+    Ok(steps
+        .into_iter()
+        .filter(|step| step.agent == id)
+        .map(|step| step.from)
+        .collect())
+}
+
+///
+/// Find the joint plan for multiple collaborative systems/agents - every agent gets the full plan
+/// incl. which agent is responsible for which step. Needs the token ring to be configured.
+///
+pub fn solve_joint<A: agent::Agent, PS: planner::SharedMessages>(
+    agent: &A,
+    ps: &PS,
+    start: PS::State,
+    goal: PS::State,
+    config: &Config,
+) -> Result<Vec<Step<PS::State>>, Error> {
+    assert!(config.ring.is_some(), "Joint plans need the token ring.");
+    search(agent, ps, start, goal, config)
+}
+
+fn search<A: agent::Agent, PS: planner::SharedMessages>(
+    agent: &A,
+    ps: &PS,
+    start: PS::State,
+    goal: PS::State,
+    config: &Config,
+) -> Result<Vec<Step<PS::State>>, Error> {
+    let deadline = config
+        .timeout
+        .map(|t| time::Instant::now() + time::Duration::from_millis(t));
+    let mut termination = config.ring.map(|(id, n)| Termination::new(id, n));
+    let counting = Counting {
+        agent,
+        traffic: &config.traffic,
+    };
+    let agent = &counting;
+    let mut outbox = Outbox::new();
     let mut done: bool = false;
 
     // Open and closed state lists.
@@ -177,48 +667,248 @@ pub fn solve<A: agent::Agent, PS: planner::ProblemSpace + planner::SharedStates>
     );
     open.push(util::HeapEntry::new_entry(start, (0.0, 0.0)));
 
+    // cheapest goal known so far - only used for the goal proof.
+    let id = config.ring.map_or(0, |(id, _)| id);
+    let mut best = f64::INFINITY;
+    let mut leader = true;
+    // traceback messages which arrived before the search ended.
+    let mut inbox: Vec<String> = Vec::new();
     while !done {
-        let revd: Vec<String> = agent.retrieve();
-        if !revd.is_empty() {
-            for msg in revd {
-                let s: (u8, PS::State, Vec<f64>) = ps.deserialize(msg);
-                if s.1 == goal {
+        if expired(deadline) {
+            return Err(Error::Timeout);
+        }
+        // malformed messages are skipped.
+        let msgs = agent
+            .retrieve()
+            .iter()
+            .filter_map(|raw| protocol::unbatch(raw).ok())
+            .flatten()
+            .collect::<Vec<String>>();
+        for raw in msgs {
+            let msg = match ps.decode(&raw) {
+                Ok(msg) => msg,
+                Err(_) => continue,
+            };
+            // the goal might look different in the sender's projection.
+            let reached = matches!(msg, protocol::Message::Goal { .. });
+            match msg {
+                protocol::Message::State { state, g, h }
+                | protocol::Message::Goal { state, g, h }
+                    if !done =>
+                {
+                    if let Some(t) = termination.as_mut() {
+                        t.received();
+                    }
+                    let state = ps.restore(&state);
+                    if reached || state == goal {
+                        // w/o termination detection we can't prove optimality - just stop.
+                        match termination {
+                            Some(_) => best = best.min(g),
+                            None => done = true,
+                        }
+                        continue;
+                    }
+                    if process_message(
+                        ps,
+                        state,
+                        StateValues { g_val: g, h_val: h },
+                        goal,
+                        &mut data,
+                        &mut open,
+                    ) {
+                        outbox.seen(ps.project(&state), g);
+                    } else {
+                        Traffic::add(&config.traffic.suppressed, 1);
+                    }
+                }
+                protocol::Message::Traceback { .. }
+                | protocol::Message::Step { .. }
+                | protocol::Message::Complete { .. } => inbox.push(raw),
+                protocol::Message::Token {
+                    target,
+                    sum,
+                    black,
+                    best,
+                    owner,
+                    ..
+                } => {
+                    if let Some(t) = termination.as_mut() {
+                        t.on_token(
+                            target,
+                            Token {
+                                sum,
+                                black,
+                                best,
+                                owner,
+                            },
+                        );
+                    }
+                }
+                protocol::Message::Terminate { .. } => return Err(Error::NoPlan),
+                protocol::Message::Proved { owner, .. } if !done => {
+                    leader = owner == id;
                     done = true;
-                    break;
                 }
-                process_message(
+                _ => {}
+            }
+        }
+        if done {
+            break;
+        }
+        // states which can not lead to a cheaper goal need no expansion.
+        let idle = open.peek().is_none_or(|e| e.keys.0 >= best);
+        if !idle {
+            // expansion round - the states to share get sent as one batch.
+            for _ in 0..config.batch_size.max(1) {
+                let s = match open.peek() {
+                    Some(e) if e.keys.0 < best => open.pop().unwrap(),
+                    _ => break,
+                };
+                if expand(
                     ps,
-                    s.1,
-                    StateValues {
-                        g_val: s.2[0],
-                        h_val: s.2[1],
-                    },
+                    &mut outbox,
+                    s.state,
                     goal,
                     &mut data,
                     &mut open,
                     &mut closed,
-                );
+                ) {
+                    match termination.as_mut() {
+                        Some(t) => {
+                            t.found_goal(data[&goal].g_val);
+                            best = best.min(data[&goal].g_val);
+                        }
+                        None => {
+                            done = true;
+                            break;
+                        }
+                    }
+                }
+            }
+            let n_msgs = outbox.flush(agent, ps, &config.traffic);
+            if let Some(t) = termination.as_mut() {
+                t.sent(n_msgs);
             }
-            if done {
-                break;
+        } else {
+            // nothing to do - check if everyone is done.
+            if let Some(t) = termination.as_mut() {
+                if let Some((cost, owner)) = t.passive(agent, ps, &goal) {
+                    if cost.is_infinite() {
+                        agent.broadcast(&ps.encode(&protocol::Message::Terminate {
+                            goal: ps.project(&goal),
+                        }));
+                        return Err(Error::NoPlan);
+                    }
+                    agent.broadcast(&ps.encode(&protocol::Message::Proved {
+                        goal: ps.project(&goal),
+                        cost,
+                        owner,
+                    }));
+                    leader = owner == id;
+                    break;
+                }
             }
+            thread::sleep(time::Duration::from_millis(config.poll_interval));
         }
-        if !open.is_empty() {
-            let s: util::HeapEntry<PS::State> = open.pop().unwrap();
-            done = expand(ps, agent, s.state, goal, &mut data, &mut open, &mut closed);
+    }
+    traceback(
+        agent, ps, start, goal, closed, leader, inbox, config, deadline,
+    )
+}
+
+///
+/// Solve the problem with an agent per problem space - each running on its own thread & connected
+/// through an in-memory message bus. Returns the joint plan from start to goal - as seen by the
+/// last agent, the private facts of the others stay hidden behind their projections. Fails with
+/// the first agent's error, or if the agents disagree on the plan. Panics if the bus may lose
+/// messages but there is no timeout - the termination detection would wait forever.
+///
+pub fn solve_in_process<PS>(
+    problems: Vec<PS>,
+    start: PS::State,
+    goal: PS::State,
+    config: &Config,
+    bus_config: bus::Config,
+) -> Result<Vec<Step<PS::State>>, Error>
+where
+    PS: planner::SharedMessages + Send + 'static,
+    PS::State: Send + 'static,
+{
+    assert!(
+        bus_config.drop_rate <= 0.0 || config.timeout.is_some(),
+        "Lossy buses need a timeout."
+    );
+    let n_agents = problems.len();
+    // agents only know the projections of the other agents' private facts.
+    let view = |id: usize, state: PS::State| {
+        problems
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != id)
+            .fold(state, |state, (_, ps)| ps.project(&state))
+    };
+    let views: Vec<_> = (0..n_agents)
+        .map(|id| (view(id, start), view(id, goal)))
+        .collect();
+    let mut handles = Vec::new();
+    for ((endpoint, ps), (start, goal)) in bus::connect(n_agents, bus_config)
+        .into_iter()
+        .zip(problems)
+        .zip(views)
+    {
+        let config = Config {
+            ring: Some((endpoint.id(), n_agents)),
+            ..config.clone()
+        };
+        handles.push(thread::spawn(move || {
+            (solve_joint(&endpoint, &ps, start, goal, &config), ps)
+        }));
+    }
+    // wait for all agents - even if one of them already failed.
+    let results: Vec<_> = handles
+        .into_iter()
+        .map(|handle| handle.join().expect("Agent panicked."))
+        .collect();
+    agree(results)
+}
+
+/// Joint plan found by an agent - or why it failed.
+type Plan<S> = Result<Vec<Step<S>>, Error>;
+
+/// Joint plan all agents agree on - once each of them hides its own private facts too.
+fn agree<PS: planner::SharedMessages>(results: Vec<(Plan<PS::State>, PS)>) -> Plan<PS::State> {
+    let mut res = Err(Error::NoPlan);
+    let mut public: Option<Vec<Step<PS::State>>> = None;
+    for (plan, ps) in results {
+        let plan = plan?;
+        let view: Vec<_> = plan
+            .iter()
+            .map(|step| Step {
+                agent: step.agent,
+                from: ps.project(&step.from),
+                to: ps.project(&step.to),
+            })
+            .collect();
+        if public.as_ref().is_some_and(|other| *other != view) {
+            return Err(Error::Mismatch);
         }
-        thread::sleep(time::Duration::from_millis(250));
+        public = Some(view);
+        res = Ok(plan);
     }
-    traceback(agent, ps, start, goal, closed)
+    res
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell;
     use std::collections;
+    use std::thread;
     use std::vec;
 
+    use crate::bus;
     use crate::mad_astar;
     use crate::planner;
+    use crate::protocol;
     use crate::util;
 
     use rusty_agent::agent;
@@ -274,8 +964,161 @@ mod tests {
 
             let msg_type = data[0].parse::<u8>().unwrap();
             let state: i32 = data[1].parse::<i32>().unwrap();
+            let para = data[2..].iter().map(|x| x.parse().unwrap()).collect();
+
+            (msg_type, state, para)
+        }
+    }
+
+    /// States in a row - each agent only knows its own part of it; every third state is public.
+    struct Line {
+        first: i32,
+        last: i32,
+    }
+
+    impl planner::ProblemSpace for Line {
+        type State = i32;
+        type Iter = vec::IntoIter<(i32, f64)>;
+
+        fn heuristic(&self, _: &Self::State, _: &Self::State) -> f64 {
+            0.0
+        }
+
+        fn succ(&self, state: &Self::State) -> Self::Iter {
+            if *state >= self.first && *state < self.last {
+                vec![(state + 1, 1.0)].into_iter()
+            } else {
+                vec![].into_iter()
+            }
+        }
+
+        fn pred(&self, state: &Self::State) -> Self::Iter {
+            if *state > self.first && *state <= self.last {
+                vec![(state - 1, 1.0)].into_iter()
+            } else {
+                vec![].into_iter()
+            }
+        }
+    }
+
+    impl planner::SharedMessages for Line {
+        fn is_shared(&self, state: &Self::State) -> bool {
+            *state > 0 && *state % 3 == 0
+        }
+
+        fn encode(&self, msg: &protocol::Message<Self::State>) -> String {
+            msg.to_text()
+        }
+
+        fn decode(&self, msg: &str) -> Result<protocol::Message<i32>, protocol::DecodeError> {
+            protocol::Message::from_text(msg)
+        }
+    }
+
+    /// Package position & the positions of the two trucks.
+    type Road = (i32, u64, u64);
+
+    /// Two companies moving a package along a road - each with a truck between its depots, whose
+    /// position is private. The package gets handed over at the public depot 3.
+    struct Depot {
+        id: usize,
+        first: i32,
+        last: i32,
+        secret: protocol::Obfuscator<u64>,
+    }
+
+    impl Depot {
+        fn new(id: usize, first: i32, last: i32) -> Depot {
+            Depot {
+                id,
+                first,
+                last,
+                secret: protocol::Obfuscator::new(id as u64 + 1),
+            }
+        }
 
-            (msg_type, state, vec![])
+        fn truck(&self, state: &Road) -> u64 {
+            if self.id == 0 {
+                state.1
+            } else {
+                state.2
+            }
+        }
+
+        fn with_truck(&self, state: &Road, truck: u64) -> Road {
+            if self.id == 0 {
+                (state.0, truck, state.2)
+            } else {
+                (state.0, state.1, truck)
+            }
+        }
+
+        /// Drive the truck or carry the package in the given direction.
+        fn moves(&self, state: &Road, dir: i32) -> vec::IntoIter<(Road, f64)> {
+            let truck = self.truck(state) as i32;
+            let mut res = Vec::new();
+            for pos in [truck - 1, truck + 1] {
+                if pos >= self.first && pos <= self.last {
+                    res.push((self.with_truck(state, pos as u64), 1.0));
+                }
+            }
+            let next = state.0 + dir;
+            if truck == state.0 && next >= self.first && next <= self.last {
+                let carried = (next, state.1, state.2);
+                res.push((self.with_truck(&carried, next as u64), 1.0));
+            }
+            res.into_iter()
+        }
+    }
+
+    impl planner::ProblemSpace for Depot {
+        type State = Road;
+        type Iter = vec::IntoIter<(Road, f64)>;
+
+        fn heuristic(&self, state: &Self::State, goal: &Self::State) -> f64 {
+            // the truck needs to reach the package first.
+            let mut res = (goal.0 - state.0) as f64;
+            if state.0 >= self.first && state.0 < self.last {
+                res += (self.truck(state) as i32 - state.0).abs() as f64;
+            }
+            res
+        }
+
+        fn succ(&self, state: &Self::State) -> Self::Iter {
+            self.moves(state, 1)
+        }
+
+        fn pred(&self, state: &Self::State) -> Self::Iter {
+            self.moves(state, -1)
+        }
+    }
+
+    impl planner::SharedMessages for Depot {
+        fn is_shared(&self, state: &Self::State) -> bool {
+            state.0 == 3
+        }
+
+        fn encode(&self, msg: &protocol::Message<Self::State>) -> String {
+            msg.to_text()
+        }
+
+        fn decode(&self, msg: &str) -> Result<protocol::Message<Road>, protocol::DecodeError> {
+            protocol::Message::from_text(msg)
+        }
+
+        fn project(&self, state: &Self::State) -> Self::State {
+            self.with_truck(state, self.secret.hide(&self.truck(state)))
+        }
+
+        fn restore(&self, state: &Self::State) -> Self::State {
+            match self.secret.reveal(self.truck(state)) {
+                Some(truck) => self.with_truck(state, truck),
+                None => *state,
+            }
+        }
+
+        fn public_heuristic(&self, state: &Self::State, goal: &Self::State) -> Option<f64> {
+            Some((goal.0 - state.0) as f64)
         }
     }
 
@@ -291,6 +1134,52 @@ mod tests {
         fn broadcast(&self, _: &str) {}
     }
 
+    /// Agent remembering what it broadcasts.
+    struct Recorder {
+        sent: cell::RefCell<Vec<String>>,
+    }
+
+    impl agent::Agent for Recorder {
+        fn retrieve(&self) -> Vec<String> {
+            vec![]
+        }
+
+        fn broadcast(&self, msg: &str) {
+            self.sent.borrow_mut().push(msg.to_string());
+        }
+    }
+
+    fn config() -> mad_astar::Config {
+        mad_astar::Config {
+            poll_interval: 1,
+            ..Default::default()
+        }
+    }
+
+    /// States the steps are taken from.
+    fn states(plan: &[mad_astar::Step<i32>]) -> Vec<i32> {
+        plan.iter().map(|step| step.from).collect()
+    }
+
+    /// Two agents splitting the line at the public state 3.
+    fn run_line(goal: i32) -> Vec<Result<Vec<i32>, mad_astar::Error>> {
+        let endpoints = bus::connect(2, bus::Config::default());
+        let mut handles = Vec::new();
+        for (agent, (first, last)) in endpoints.into_iter().zip(vec![(0, 3), (3, 6)]) {
+            let id = agent.id();
+            handles.push(thread::spawn(move || {
+                let config = mad_astar::Config {
+                    ring: Some((id, 2)),
+                    poll_interval: 5,
+                    timeout: Some(10000),
+                    ..Default::default()
+                };
+                mad_astar::solve_with_config(&agent, &Line { first, last }, 0, goal, &config)
+            }));
+        }
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    }
+
     // Test for success.
 
     #[test]
@@ -304,17 +1193,27 @@ mod tests {
             collections::HashMap::new();
         let mut open: collections::BinaryHeap<util::HeapEntry<i32>> =
             collections::BinaryHeap::new();
-        let mut closed: collections::HashMap<i32, f64> = collections::HashMap::new();
 
-        mad_astar::process_message(&ps, 1, vals, 3, &mut data, &mut open, &mut closed);
+        mad_astar::process_message(&ps, 1, vals, 3, &mut data, &mut open);
+    }
+
+    #[test]
+    fn test_outbox_for_success() {
+        let ps = Line { first: 0, last: 3 };
+        let agent = SimpleAgent { msgs: vec![] };
+        let mut outbox = mad_astar::Outbox::new();
+        outbox.push(protocol::Message::State {
+            state: 3,
+            g: 3.0,
+            h: 0.0,
+        });
+        outbox.flush(&agent, &ps, &mad_astar::Traffic::default());
     }
 
     #[test]
     fn test_expand_for_success() {
         let ps = SimpleExample {};
-        let agent = SimpleAgent {
-            msgs: vec![String::from("foo")],
-        };
+        let mut outbox = mad_astar::Outbox::new();
 
         let mut data: collections::HashMap<i32, mad_astar::StateValues> =
             collections::HashMap::new();
@@ -330,7 +1229,7 @@ mod tests {
                 h_val: 0.3,
             },
         );
-        mad_astar::expand(&ps, &agent, s, 3, &mut data, &mut open, &mut closed);
+        mad_astar::expand(&ps, &mut outbox, s, 3, &mut data, &mut open, &mut closed);
     }
 
     #[test]
@@ -343,7 +1242,7 @@ mod tests {
         closed.insert(1, 1.0);
         closed.insert(2, 1.0);
 
-        mad_astar::traceback(&agent, &ps, 0, 3, closed);
+        mad_astar::traceback(&agent, &ps, 0, 3, closed, true, vec![], &config(), None).unwrap();
     }
 
     #[test]
@@ -353,8 +1252,188 @@ mod tests {
         mad_astar::solve(&agent, &ps, 0, 3);
     }
 
+    #[test]
+    fn test_solve_with_config_for_success() {
+        let ps = Line { first: 0, last: 6 };
+        let agent = SimpleAgent { msgs: vec![] };
+        let config = mad_astar::Config {
+            ring: Some((0, 1)),
+            ..Default::default()
+        };
+        mad_astar::solve_with_config(&agent, &ps, 0, 6, &config).unwrap();
+    }
+
+    #[test]
+    fn test_termination_for_success() {
+        let ps = SimpleExample {};
+        let agent = SimpleAgent { msgs: vec![] };
+        let mut termination = mad_astar::Termination::new(0, 2);
+        termination.passive(&agent, &ps, &0);
+    }
+
+    #[test]
+    fn test_solve_joint_for_success() {
+        let ps = Line { first: 0, last: 6 };
+        let agent = SimpleAgent { msgs: vec![] };
+        let config = mad_astar::Config {
+            ring: Some((0, 1)),
+            ..Default::default()
+        };
+        mad_astar::solve_joint(&agent, &ps, 0, 6, &config).unwrap();
+    }
+
+    #[test]
+    fn test_solve_in_process_for_success() {
+        let problems = vec![Line { first: 0, last: 3 }, Line { first: 3, last: 6 }];
+        let config = mad_astar::Config {
+            poll_interval: 5,
+            ..Default::default()
+        };
+        mad_astar::solve_in_process(problems, 0, 6, &config, bus::Config::default()).unwrap();
+    }
+
     // Test for failure.
 
+    #[test]
+    fn test_privacy_for_success() {
+        let problems = vec![Depot::new(0, 0, 3), Depot::new(1, 3, 6)];
+        let config = mad_astar::Config {
+            poll_interval: 5,
+            timeout: Some(10000),
+            ..Default::default()
+        };
+        mad_astar::solve_in_process(problems, (0, 1, 5), (6, 3, 6), &config, Default::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_solve_in_process_for_failure() {
+        let config = mad_astar::Config {
+            poll_interval: 5,
+            timeout: Some(200),
+            ..Default::default()
+        };
+
+        // goal out of reach.
+        let problems = vec![Line { first: 0, last: 3 }, Line { first: 3, last: 6 }];
+        let res = mad_astar::solve_in_process(problems, 0, 7, &config, bus::Config::default());
+        assert_eq!(res, Err(mad_astar::Error::NoPlan));
+
+        // all messages get lost.
+        let bus_config = bus::Config {
+            drop_rate: 1.0,
+            ..Default::default()
+        };
+        let problems = vec![Line { first: 0, last: 3 }, Line { first: 3, last: 6 }];
+        let res = mad_astar::solve_in_process(problems, 0, 6, &config, bus_config);
+        assert_eq!(res, Err(mad_astar::Error::Timeout));
+    }
+
+    #[test]
+    #[should_panic(expected = "timeout")]
+    fn test_solve_in_process_lossy_for_failure() {
+        let bus_config = bus::Config {
+            drop_rate: 0.1,
+            ..Default::default()
+        };
+        let problems = vec![Line { first: 0, last: 3 }, Line { first: 3, last: 6 }];
+        let _ = mad_astar::solve_in_process(problems, 0, 6, &Default::default(), bus_config);
+    }
+
+    #[test]
+    fn test_solve_with_config_for_failure() {
+        let ps = SimpleExample {};
+        let agent = SimpleAgent { msgs: vec![] };
+
+        // alone & out of states.
+        let config = mad_astar::Config {
+            ring: Some((0, 1)),
+            poll_interval: 1,
+            timeout: None,
+            ..Default::default()
+        };
+        let res = mad_astar::solve_with_config(&agent, &ps, 0, 7, &config);
+        assert_eq!(res, Err(mad_astar::Error::NoPlan));
+
+        // no termination detection - but a timeout.
+        let config = mad_astar::Config {
+            ring: None,
+            poll_interval: 1,
+            timeout: Some(50),
+            ..Default::default()
+        };
+        let res = mad_astar::solve_with_config(&agent, &ps, 0, 7, &config);
+        assert_eq!(res, Err(mad_astar::Error::Timeout));
+
+        // other agent gave up.
+        let agent = SimpleAgent {
+            msgs: vec!["4;0".to_string()],
+        };
+        let res = mad_astar::solve_with_config(&agent, &ps, 0, 7, &config);
+        assert_eq!(res, Err(mad_astar::Error::NoPlan));
+
+        // malformed messages get skipped.
+        let agent = SimpleAgent {
+            msgs: vec!["0;1".to_string(), "9;1".to_string()],
+        };
+        let res = mad_astar::solve_with_config(&agent, &ps, 0, 7, &config);
+        assert_eq!(res, Err(mad_astar::Error::Timeout));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_solve_joint_for_failure() {
+        let ps = SimpleExample {};
+        let agent = SimpleAgent { msgs: vec![] };
+        let _ = mad_astar::solve_joint(&agent, &ps, 0, 3, &mad_astar::Config::default());
+    }
+
+    #[test]
+    fn test_traceback_for_failure() {
+        let ps = SimpleExample {};
+        let agent = SimpleAgent { msgs: vec![] };
+        let closed: collections::HashMap<i32, f64> = collections::HashMap::new();
+
+        // nobody tells us how to continue.
+        let deadline = Some(std::time::Instant::now());
+        let res =
+            mad_astar::traceback(&agent, &ps, 4, 7, closed, true, vec![], &config(), deadline);
+        assert_eq!(res, Err(mad_astar::Error::Timeout));
+    }
+
+    #[test]
+    fn test_agree_for_failure() {
+        let line = || Line { first: 0, last: 3 };
+        let step = |from: i32| mad_astar::Step {
+            agent: 0,
+            from,
+            to: from + 1,
+        };
+
+        // first error counts - even if others found a plan.
+        let res = mad_astar::agree(vec![
+            (Ok(vec![step(0)]), line()),
+            (Err(mad_astar::Error::Timeout), line()),
+            (Err(mad_astar::Error::NoPlan), line()),
+        ]);
+        assert_eq!(res, Err(mad_astar::Error::Timeout));
+
+        // agents disagree.
+        let res = mad_astar::agree(vec![
+            (Ok(vec![step(0)]), line()),
+            (Ok(vec![step(1)]), line()),
+        ]);
+        assert_eq!(res, Err(mad_astar::Error::Mismatch));
+        assert_eq!(
+            mad_astar::Error::Mismatch.to_string(),
+            "agents disagree on the plan"
+        );
+
+        // no agents.
+        let res = mad_astar::agree::<Line>(vec![]);
+        assert_eq!(res, Err(mad_astar::Error::NoPlan));
+    }
+
     // Test for sanity.
 
     #[test]
@@ -368,49 +1447,65 @@ mod tests {
             collections::HashMap::new();
         let mut open: collections::BinaryHeap<util::HeapEntry<i32>> =
             collections::BinaryHeap::new();
-        let mut closed: collections::HashMap<i32, f64> = collections::HashMap::new();
 
         // not previously seen this state --> should update g and h val.
         let s = 1;
-        mad_astar::process_message(&ps, s, vals, 3, &mut data, &mut open, &mut closed);
+        assert!(mad_astar::process_message(
+            &ps, s, vals, 3, &mut data, &mut open
+        ));
         assert_eq!(data[&s].g_val, 1.0);
         assert_eq!(data[&s].h_val, 1.0);
 
-        // now state is in closed & open --> should not change anything.
-        closed.insert(s, 1.0);
+        // duplicate --> should not change anything & not end up in open again.
         let vals_1 = mad_astar::StateValues {
             g_val: 1.0,
             h_val: 1.0,
         };
-        mad_astar::process_message(&ps, s, vals_1, 3, &mut data, &mut open, &mut closed);
+        assert!(!mad_astar::process_message(
+            &ps, s, vals_1, 3, &mut data, &mut open
+        ));
         assert_eq!(data[&s].g_val, 1.0);
         assert_eq!(data[&s].h_val, 1.0);
+        assert_eq!(open.len(), 1);
 
         // newly received state has a better g_val --> should update g_val
         let vals = mad_astar::StateValues {
             g_val: 0.5,
             h_val: 1.0,
         };
-        mad_astar::process_message(&ps, s, vals, 3, &mut data, &mut open, &mut closed);
+        assert!(mad_astar::process_message(
+            &ps, s, vals, 3, &mut data, &mut open
+        ));
         assert_eq!(data[&s].g_val, 0.5);
         assert_eq!(data[&s].h_val, 1.0);
 
+        // fractional estimates are kept as they are - & the state is keyed by g + h.
+        let vals = mad_astar::StateValues {
+            g_val: 0.25,
+            h_val: 1.5,
+        };
+        assert!(mad_astar::process_message(
+            &ps, s, vals, 3, &mut data, &mut open
+        ));
+        assert_eq!(data[&s].h_val, 1.5);
+        assert!(open.iter().any(|e| e.state == s && e.keys == (1.75, 0.0)));
+
         // newly received state has a worse g_val --> should NOT update g_val
         let vals = mad_astar::StateValues {
             g_val: 2.0,
             h_val: 1.0,
         };
-        mad_astar::process_message(&ps, s, vals, 3, &mut data, &mut open, &mut closed);
-        assert_eq!(data[&s].g_val, 0.5);
-        assert_eq!(data[&s].h_val, 1.0);
+        assert!(!mad_astar::process_message(
+            &ps, s, vals, 3, &mut data, &mut open
+        ));
+        assert_eq!(data[&s].g_val, 0.25);
+        assert_eq!(data[&s].h_val, 1.5);
     }
 
     #[test]
     fn test_expand_for_sanity() {
         let ps = SimpleExample {};
-        let agent = SimpleAgent {
-            msgs: vec![String::from("foo")],
-        };
+        let mut outbox = mad_astar::Outbox::new();
 
         let mut data: collections::HashMap<i32, mad_astar::StateValues> =
             collections::HashMap::new();
@@ -426,26 +1521,31 @@ mod tests {
                 h_val: 0.0,
             },
         );
-        let res = mad_astar::expand(&ps, &agent, 0, 3, &mut data, &mut open, &mut closed);
-        assert_eq!(res, false);
+        let res = mad_astar::expand(&ps, &mut outbox, 0, 3, &mut data, &mut open, &mut closed);
+        assert!(!res);
 
         // found a public state.
-        let res = mad_astar::expand(&ps, &agent, 1, 3, &mut data, &mut open, &mut closed);
-        assert_eq!(res, false);
+        let res = mad_astar::expand(&ps, &mut outbox, 1, 3, &mut data, &mut open, &mut closed);
+        assert!(!res);
         assert_eq!(data[&1].g_val, 0.7);
+        assert_eq!(outbox.pending.len(), 1);
 
         // found a better path.
-        mad_astar::expand(&ps, &agent, 2, 3, &mut data, &mut open, &mut closed);
+        mad_astar::expand(&ps, &mut outbox, 2, 3, &mut data, &mut open, &mut closed);
         assert_eq!(data[&2].g_val as i64, 0.8 as i64);
 
         // already expanded...
-        mad_astar::expand(&ps, &agent, 1, 3, &mut data, &mut open, &mut closed);
+        mad_astar::expand(&ps, &mut outbox, 1, 3, &mut data, &mut open, &mut closed);
         assert_eq!(data[&2].g_val as i64, 0.8 as i64);
 
         // found the goal.
-        let res = mad_astar::expand(&ps, &agent, 3, 3, &mut data, &mut open, &mut closed);
-        assert_eq!(res, true);
-        assert_eq!(closed.contains_key(&3), true);
+        let res = mad_astar::expand(&ps, &mut outbox, 3, 3, &mut data, &mut open, &mut closed);
+        assert!(res);
+        assert!(closed.contains_key(&3));
+        assert!(matches!(
+            outbox.pending.last(),
+            Some(protocol::Message::Goal { state: 3, .. })
+        ));
     }
 
     #[test]
@@ -459,8 +1559,8 @@ mod tests {
         closed.insert(6, 2.0);
 
         // simple traceback: start to finish!
-        let res = mad_astar::traceback(&agent, &ps, 4, 6, closed);
-        assert_eq!(res, vec![4, 5]);
+        let res = mad_astar::traceback(&agent, &ps, 4, 6, closed, true, vec![], &config(), None);
+        assert_eq!(states(&res.unwrap()), vec![4, 5]);
 
         // goal state unknown - trigger by other agent
         let agent = SimpleAgent {
@@ -470,8 +1570,67 @@ mod tests {
         closed.insert(4, 0.0);
         closed.insert(5, 1.0);
         closed.insert(6, 2.0);
-        let res = mad_astar::traceback(&agent, &ps, 4, 7, closed);
-        assert_eq!(res, vec![4, 5]);
+        let res = mad_astar::traceback(&agent, &ps, 4, 7, closed, true, vec![], &config(), None);
+        assert_eq!(states(&res.unwrap()), vec![4, 5]);
+
+        // another agent found the goal - wait for it to hand over.
+        let agent = SimpleAgent {
+            msgs: vec!["1;5".to_string()],
+        };
+        let mut closed: collections::HashMap<i32, f64> = collections::HashMap::new();
+        closed.insert(4, 0.0);
+        closed.insert(5, 1.0);
+        closed.insert(6, 2.0);
+        let res = mad_astar::traceback(&agent, &ps, 4, 6, closed, false, vec![], &config(), None);
+        assert_eq!(states(&res.unwrap()), vec![4]);
+
+        // joint plan: other agent took the last step & hands over.
+        let agent = SimpleAgent {
+            msgs: vec!["7;6;0;0".to_string(), "1;6;1".to_string()],
+        };
+        let mut closed: collections::HashMap<i32, f64> = collections::HashMap::new();
+        closed.insert(4, 0.0);
+        closed.insert(5, 1.0);
+        let config = mad_astar::Config {
+            ring: Some((1, 3)),
+            ..config()
+        };
+        let res = mad_astar::traceback(&agent, &ps, 4, 7, closed, false, vec![], &config, None);
+        let expected = vec![
+            mad_astar::Step {
+                agent: 1,
+                from: 4,
+                to: 5,
+            },
+            mad_astar::Step {
+                agent: 1,
+                from: 5,
+                to: 6,
+            },
+            mad_astar::Step {
+                agent: 0,
+                from: 6,
+                to: 7,
+            },
+        ];
+        assert_eq!(res, Ok(expected.clone()));
+
+        // agent w/o a part in the plan gets it as well.
+        let agent = SimpleAgent {
+            msgs: vec![
+                "7;6;0;0".to_string(),
+                "7;5;1;1".to_string(),
+                "7;4;1;2".to_string(),
+                "8;7;3".to_string(),
+            ],
+        };
+        let config = mad_astar::Config {
+            ring: Some((2, 3)),
+            ..config
+        };
+        let closed: collections::HashMap<i32, f64> = collections::HashMap::new();
+        let res = mad_astar::traceback(&agent, &ps, 4, 7, closed, false, vec![], &config, None);
+        assert_eq!(res, Ok(expected));
     }
 
     #[test]
@@ -483,4 +1642,299 @@ mod tests {
         let res = mad_astar::solve(&agent, &ps, 0, 3);
         assert_eq!(res, vec![2]);
     }
+
+    #[test]
+    fn test_solve_with_config_for_sanity() {
+        // both agents contribute their part.
+        let res = run_line(6);
+        assert_eq!(res[0], Ok(vec![0, 1, 2]));
+        assert_eq!(res[1], Ok(vec![3, 4, 5]));
+
+        // goal is out of reach - everybody should notice.
+        let res = run_line(7);
+        assert_eq!(res[0], Err(mad_astar::Error::NoPlan));
+        assert_eq!(res[1], Err(mad_astar::Error::NoPlan));
+    }
+
+    #[test]
+    fn test_solve_joint_for_sanity() {
+        let ps = Line { first: 0, last: 6 };
+        let agent = SimpleAgent { msgs: vec![] };
+        let config = mad_astar::Config {
+            ring: Some((0, 1)),
+            ..Default::default()
+        };
+
+        // single agent does it all - incl. passing through the public state.
+        let res = mad_astar::solve_joint(&agent, &ps, 0, 6, &config).unwrap();
+        assert_eq!(states(&res), vec![0, 1, 2, 3, 4, 5]);
+        assert!(res
+            .iter()
+            .all(|step| step.agent == 0 && step.to == step.from + 1));
+    }
+
+    #[test]
+    fn test_solve_in_process_for_sanity() {
+        let config = mad_astar::Config {
+            poll_interval: 5,
+            timeout: Some(10000),
+            ..Default::default()
+        };
+
+        // three agents.
+        let problems = vec![
+            Line { first: 0, last: 3 },
+            Line { first: 3, last: 6 },
+            Line { first: 6, last: 9 },
+        ];
+        let res = mad_astar::solve_in_process(problems, 0, 9, &config, bus::Config::default());
+        let expected: Vec<mad_astar::Step<i32>> = (0..9)
+            .map(|i| mad_astar::Step {
+                agent: i as usize / 3,
+                from: i,
+                to: i + 1,
+            })
+            .collect();
+        assert_eq!(res, Ok(expected));
+
+        // slow & unordered network.
+        let bus_config = bus::Config {
+            latency: (0, 20),
+            seed: 3,
+            ..Default::default()
+        };
+        let problems = vec![Line { first: 0, last: 3 }, Line { first: 3, last: 6 }];
+        let res = mad_astar::solve_in_process(problems, 0, 6, &config, bus_config);
+        assert_eq!(states(&res.unwrap()), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_privacy_for_sanity() {
+        let ps = Depot::new(1, 3, 6);
+        let agent = Recorder {
+            sent: cell::RefCell::new(vec![]),
+        };
+        let mut data: collections::HashMap<Road, mad_astar::StateValues> =
+            collections::HashMap::new();
+        let mut open: collections::BinaryHeap<util::HeapEntry<Road>> =
+            collections::BinaryHeap::new();
+        let mut closed: collections::HashMap<Road, f64> = collections::HashMap::new();
+
+        // only the projection & the public estimate get shared.
+        let state = (3, 3, 5);
+        let h_val = planner::ProblemSpace::heuristic(&ps, &state, &(6, 3, 6));
+        assert_eq!(h_val, 5.0);
+        data.insert(state, mad_astar::StateValues { g_val: 4.0, h_val });
+        let mut outbox = mad_astar::Outbox::new();
+        mad_astar::expand(
+            &ps,
+            &mut outbox,
+            state,
+            (6, 3, 6),
+            &mut data,
+            &mut open,
+            &mut closed,
+        );
+        outbox.flush(&agent, &ps, &mad_astar::Traffic::default());
+        let sent = agent.sent.borrow();
+        assert_eq!(sent.len(), 1);
+        match protocol::Message::<Road>::from_text(&sent[0]).unwrap() {
+            protocol::Message::State {
+                state: shared,
+                g,
+                h,
+            } => {
+                assert_ne!(shared.2, 5);
+                assert_eq!(planner::SharedMessages::restore(&ps, &shared), state);
+                assert_eq!((g, h), (4.0, 3.0));
+            }
+            msg => panic!("unexpected message {:?}", msg),
+        }
+
+        // joint plan - the first truck is only known through its projection.
+        let problems = vec![Depot::new(0, 0, 3), Depot::new(1, 3, 6)];
+        let config = mad_astar::Config {
+            poll_interval: 5,
+            timeout: Some(10000),
+            ..Default::default()
+        };
+        let res = mad_astar::solve_in_process(
+            problems,
+            (0, 1, 5),
+            (6, 3, 6),
+            &config,
+            Default::default(),
+        );
+        let other = Depot::new(0, 0, 3);
+        let froms = vec![
+            (0, 1, 5),
+            (0, 0, 5),
+            (1, 1, 5),
+            (2, 2, 5),
+            (3, 3, 5),
+            (3, 3, 4),
+            (3, 3, 3),
+            (4, 3, 4),
+            (5, 3, 5),
+        ];
+        let expected: Vec<mad_astar::Step<Road>> = froms
+            .iter()
+            .enumerate()
+            .map(|(i, from)| mad_astar::Step {
+                agent: if i < 4 { 0 } else { 1 },
+                from: planner::SharedMessages::project(&other, from),
+                to: planner::SharedMessages::project(
+                    &other,
+                    froms.get(i + 1).unwrap_or(&(6, 3, 6)),
+                ),
+            })
+            .collect();
+        assert_eq!(res, Ok(expected));
+    }
+
+    #[test]
+    fn test_outbox_for_sanity() {
+        let ps = Line { first: 0, last: 3 };
+        let agent = Recorder {
+            sent: cell::RefCell::new(vec![]),
+        };
+        let traffic = mad_astar::Traffic::default();
+        let mut outbox = mad_astar::Outbox::new();
+        let state = |state: i32, g: f64| protocol::Message::State { state, g, h: 0.0 };
+
+        // cheaper path replaces the queued one; dominated ones are dropped.
+        outbox.push(state(3, 5.0));
+        outbox.push(state(3, 3.0));
+        outbox.push(state(3, 4.0));
+        outbox.push(state(6, 6.0));
+        assert_eq!(outbox.pending, vec![state(3, 3.0), state(6, 6.0)]);
+
+        // one batch per round.
+        assert_eq!(outbox.flush(&agent, &ps, &traffic), 2);
+        assert_eq!(agent.sent.borrow().len(), 1);
+        assert_eq!(
+            protocol::unbatch(&agent.sent.borrow()[0]),
+            Ok(vec![state(3, 3.0).to_text(), state(6, 6.0).to_text()])
+        );
+        assert_eq!((traffic.shared(), traffic.suppressed()), (2, 2));
+
+        // duplicates of earlier rounds are dropped too - nothing to send.
+        outbox.push(state(3, 3.0));
+        assert_eq!(outbox.flush(&agent, &ps, &traffic), 0);
+        assert_eq!(agent.sent.borrow().len(), 1);
+        assert_eq!((traffic.shared(), traffic.suppressed()), (2, 3));
+
+        // states received from others are not echoed.
+        outbox.seen(9, 9.0);
+        outbox.push(state(9, 9.0));
+        assert!(outbox.pending.is_empty());
+    }
+
+    #[test]
+    fn test_traffic_for_sanity() {
+        let problems = vec![Line { first: 0, last: 3 }, Line { first: 3, last: 6 }];
+        let config = mad_astar::Config {
+            poll_interval: 5,
+            timeout: Some(10000),
+            ..Default::default()
+        };
+        let res = mad_astar::solve_in_process(problems, 0, 6, &config, bus::Config::default());
+        assert!(res.is_ok());
+
+        // public state 3 & the goal got shared.
+        let traffic = &config.traffic;
+        assert_eq!(traffic.shared(), 2);
+        assert!(traffic.sent() >= traffic.shared());
+        assert!(traffic.received() > 0);
+
+        // clones share the counters.
+        assert_eq!(config.clone().traffic.sent(), traffic.sent());
+    }
+
+    #[test]
+    fn test_termination_for_sanity() {
+        let ps = SimpleExample {};
+        let agent = SimpleAgent { msgs: vec![] };
+
+        // single agent - passive means done.
+        let mut termination = mad_astar::Termination::new(0, 1);
+        assert_eq!(
+            termination.passive(&agent, &ps, &0),
+            Some((f64::INFINITY, 0))
+        );
+        termination.found_goal(2.0);
+        assert_eq!(termination.passive(&agent, &ps, &0), Some((2.0, 0)));
+
+        // initiator starts a probe & waits for the token.
+        let mut termination = mad_astar::Termination::new(0, 2);
+        assert_eq!(termination.passive(&agent, &ps, &0), None);
+        assert!(termination.probing);
+        assert_eq!(termination.passive(&agent, &ps, &0), None);
+
+        // token came back but a message is still in flight.
+        termination.sent(1);
+        termination.on_token(
+            0,
+            mad_astar::Token {
+                sum: 0,
+                black: false,
+                best: f64::INFINITY,
+                owner: 0,
+            },
+        );
+        assert_eq!(termination.passive(&agent, &ps, &0), None);
+
+        // token for somebody else is ignored.
+        termination.on_token(
+            1,
+            mad_astar::Token {
+                sum: -1,
+                black: false,
+                best: f64::INFINITY,
+                owner: 0,
+            },
+        );
+        assert_eq!(termination.token, None);
+
+        // message got received by the other agent - but it was black.
+        termination.on_token(
+            0,
+            mad_astar::Token {
+                sum: -1,
+                black: true,
+                best: f64::INFINITY,
+                owner: 0,
+            },
+        );
+        assert_eq!(termination.passive(&agent, &ps, &0), None);
+
+        // all quiet - other agent found a goal.
+        termination.on_token(
+            0,
+            mad_astar::Token {
+                sum: -1,
+                black: false,
+                best: 3.0,
+                owner: 1,
+            },
+        );
+        assert_eq!(termination.passive(&agent, &ps, &0), Some((3.0, 1)));
+
+        // other agents pass on the token & become white again.
+        let mut termination = mad_astar::Termination::new(1, 2);
+        termination.received();
+        assert!(termination.black);
+        termination.on_token(
+            1,
+            mad_astar::Token {
+                sum: 0,
+                black: false,
+                best: f64::INFINITY,
+                owner: 0,
+            },
+        );
+        assert_eq!(termination.passive(&agent, &ps, &0), None);
+        assert!(!termination.black);
+        assert_eq!(termination.token, None);
+    }
 }
@@ -1,45 +1,91 @@
 use std::collections;
+use std::fmt;
+use std::hash;
 
 use crate::planner;
 
-/// Expand the given state.
+///
+/// Configuration of the search.
+///
+pub struct Config {
+    /// Number of iterations to run per step.
+    pub iterations: u16,
+    /// Exploration constant of the UCT formula.
+    pub exploration: f64,
+    /// Progressive widening (k, alpha) - a state visited n times has at most ceil(k * n^alpha)
+    /// children; cheapest transitions get expanded first.
+    pub widening: Option<(f64, f64)>,
+    /// RAVE/AMAF equivalence parameter k - values get blended using beta = sqrt(k / (3n + k)).
+    pub rave: Option<f64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            iterations: 100,
+            exploration: 1.0,
+            widening: None,
+            rave: None,
+        }
+    }
+}
+
+/// All-moves-as-first statistics - keyed by state, collected over all states of a simulation.
+struct Amaf<S> {
+    k: f64,
+    n_vals: collections::HashMap<S, u64>,
+    q_vals: collections::HashMap<S, f64>,
+}
+
+/// Expand the given state - picks the first transition not yet in the tree, or the cheapest one if
+/// requested (for progressive widening). Transitions back to the state itself are skipped.
 fn expand<PS: planner::ProblemSpace>(
     ps: &PS,
     v: PS::State,
     children: &mut collections::HashMap<PS::State, Vec<PS::State>>,
+    cheapest: bool,
 ) -> Option<PS::State> {
-    let mut res: PS::State = v;
+    let mut res: Option<(PS::State, f64)> = None;
     for item in ps.succ(&v) {
-        if !children.contains_key(&v) || !children[&v].contains(&item.0) {
-            children.entry(v).or_default().push(item.0);
-            res = item.0;
+        if item.0 == v || children.get(&v).is_some_and(|c| c.contains(&item.0)) {
+            continue;
+        }
+        if res.is_none_or(|(_, cost)| item.1 < cost) {
+            res = Some(item);
+        }
+        if !cheapest {
             break;
         }
     }
-    if res != v {
-        Some(res)
-    } else {
-        None
-    }
+    let (res, _) = res?;
+    children.entry(v).or_default().push(res);
+    Some(res)
 }
 
 /// Determine the best possible next step.
-fn best_child<PS: planner::ProblemSpace + planner::Anytime>(
+fn best_child<PS: planner::ProblemSpace>(
     _: &PS,
     v: PS::State,
     children: &mut collections::HashMap<PS::State, Vec<PS::State>>,
     n_vals: &collections::HashMap<PS::State, u64>,
     q_vals: &collections::HashMap<PS::State, f64>,
     c_val: f64,
+    amaf: Option<&Amaf<PS::State>>,
 ) -> Option<PS::State> {
     let mut max_val: f64 = 0.0;
     let mut res = v;
     for child in children.get(&v).unwrap() {
+        let mut q_val = q_vals[child] / n_vals[child] as f64;
+        if let Some(amaf) = amaf {
+            if let Some(n_amaf) = amaf.n_vals.get(child) {
+                let beta = (amaf.k / (3.0 * n_vals[child] as f64 + amaf.k)).sqrt();
+                q_val = (1.0 - beta) * q_val + beta * (amaf.q_vals[child] / *n_amaf as f64);
+            }
+        }
         let tmp: f64 = if c_val > 0.0 {
-            (q_vals[child] / n_vals[child] as f64)
-                + c_val * (((2.0 * (n_vals[&v] as f64).ln()) / n_vals[child] as f64).sqrt())
+            q_val + c_val * (((2.0 * (n_vals[&v] as f64).ln()) / n_vals[child] as f64).sqrt())
         } else {
-            q_vals[child] / n_vals[child] as f64
+            q_val
         };
         if tmp >= max_val {
             max_val = tmp;
@@ -53,21 +99,49 @@ fn best_child<PS: planner::ProblemSpace + planner::Anytime>(
     }
 }
 
-/// Select of expand a state.
-fn tree_policy<PS: planner::ProblemSpace + planner::Anytime>(
+/// Select of expand a state - records the path taken in the parents map.
+#[allow(clippy::too_many_arguments)]
+fn tree_policy<PS: planner::ProblemSpace>(
     ps: &PS,
     state: PS::State,
     children: &mut collections::HashMap<PS::State, Vec<PS::State>>,
     n_vals: &collections::HashMap<PS::State, u64>,
     q_vals: &collections::HashMap<PS::State, f64>,
+    parents: &mut collections::HashMap<PS::State, PS::State>,
+    config: &Config,
+    amaf: Option<&Amaf<PS::State>>,
 ) -> PS::State {
     let mut v = state;
-    while ps.succ(&v).count() != 0 {
-        if !children.contains_key(&v) || ps.succ(&v).count() != (children.get(&v).unwrap()).len() {
-            v = expand(ps, v, children).unwrap();
+    loop {
+        let n_succ = ps.succ(&v).count();
+        if n_succ == 0 {
             break;
-        } else {
-            v = best_child(ps, v, children, n_vals, q_vals, 1.0).unwrap();
+        }
+        let limit = match config.widening {
+            Some((k, alpha)) => {
+                let n_v = *n_vals.get(&v).unwrap_or(&0) as f64;
+                ((k * (n_v + 1.0).powf(alpha)).ceil() as usize).clamp(1, n_succ)
+            }
+            None => n_succ,
+        };
+        let prev = v;
+        if children.get(&v).is_none_or(|c| c.len() < limit) {
+            if let Some(next) = expand(ps, v, children, config.widening.is_some()) {
+                parents.insert(next, prev);
+                v = next;
+                break;
+            }
+        }
+        // fully expanded - or only transitions back to itself are left.
+        if !children.contains_key(&v) {
+            break;
+        }
+        match best_child(ps, v, children, n_vals, q_vals, config.exploration, amaf) {
+            Some(next) => {
+                parents.insert(next, prev);
+                v = next;
+            }
+            None => break,
         }
     }
     v
@@ -75,7 +149,7 @@ fn tree_policy<PS: planner::ProblemSpace + planner::Anytime>(
 
 /// Simulate what would happen if you play from this state to the end.
 // TODO: check if to make this part of the trait - would allow for multi-player games etc.
-fn default_policy<PS: planner::ProblemSpace + planner::Anytime>(
+fn default_policy<PS: planner::ProblemSpace>(
     ps: &PS,
     v: PS::State,
     goal: PS::State,
@@ -109,7 +183,7 @@ fn default_policy<PS: planner::ProblemSpace + planner::Anytime>(
 }
 
 /// Backpropagate the reward up the tree.
-fn backup<PS: planner::ProblemSpace + planner::Anytime>(
+fn backup<PS: planner::ProblemSpace>(
     _: &PS,
     v: PS::State,
     delta: f64,
@@ -129,6 +203,310 @@ fn backup<PS: planner::ProblemSpace + planner::Anytime>(
     }
 }
 
+/// Share the reward with all states seen during the simulation.
+fn backup_amaf<S: Copy + Eq + hash::Hash>(
+    amaf: &mut Amaf<S>,
+    delta: f64,
+    parents: &collections::HashMap<S, S>,
+) {
+    let mut seen: collections::HashSet<S> = collections::HashSet::new();
+    for (child, parent) in parents.iter() {
+        seen.insert(*child);
+        seen.insert(*parent);
+    }
+    for s in seen {
+        *amaf.n_vals.entry(s).or_default() += 1;
+        *amaf.q_vals.entry(s).or_default() += delta;
+    }
+}
+
+///
+/// Statistics of a child of the root state.
+///
+pub struct ChildStats<S> {
+    /// State the child represents.
+    pub state: S,
+    /// Number of times the child was visited.
+    pub visits: u64,
+    /// Average reward of the child.
+    pub value: f64,
+}
+
+///
+/// Summary of the current search tree - for inspecting and debugging the search.
+///
+pub struct SearchReport<S> {
+    /// State the tree is rooted in.
+    pub root: S,
+    /// Visits & average reward of the root's children.
+    pub children: Vec<ChildStats<S>>,
+    /// Expected line of play - following the most visited children.
+    pub principal_variation: Vec<S>,
+    /// Number of states in the tree.
+    pub size: usize,
+    /// Largest distance of a state in the tree from the root.
+    pub depth: usize,
+}
+
+///
+/// Monte-Carlo search tree which is kept between moves - after advancing, the subtree of the
+/// chosen state becomes the new root and all unrelated branches are discarded.
+///
+/// # Example
+/// ```
+/// use std::vec;
+///
+/// use rusty_planner::mcts;
+/// use rusty_planner::planner;
+///
+/// struct Line {}
+///
+/// impl planner::ProblemSpace for Line {
+///     type State = i32;
+///     type Iter = vec::IntoIter<(Self::State, f64)>;
+///     fn heuristic(&self, _: &Self::State, _: &Self::State) -> f64 {
+///         0.0
+///     }
+///     fn succ(&self, s: &Self::State) -> Self::Iter {
+///         if *s < 3 {
+///             vec![(s + 1, 1.0)].into_iter()
+///         } else {
+///             vec![].into_iter()
+///         }
+///     }
+///     fn pred(&self, s: &Self::State) -> Self::Iter {
+///         vec![(s - 1, 1.0)].into_iter()
+///     }
+/// }
+///
+/// let ps = Line {};
+/// let mut tree: mcts::Mcts<Line> = mcts::Mcts::new(0, 3, 5);
+/// let mut curr = 0;
+/// while let Some(next) = tree.best_action(&ps, curr) {
+///     // TODO: execute the step...
+///     tree.advance(next);
+///     curr = next;
+/// }
+/// assert_eq!(curr, 3);
+/// ```
+///
+pub struct Mcts<PS: planner::ProblemSpace> {
+    root: PS::State,
+    goal: PS::State,
+    config: Config,
+    n_vals: collections::HashMap<PS::State, u64>,
+    q_vals: collections::HashMap<PS::State, f64>,
+    children: collections::HashMap<PS::State, Vec<PS::State>>,
+    amaf: Option<Amaf<PS::State>>,
+}
+
+impl<PS: planner::ProblemSpace> Mcts<PS> {
+    /// Create a new, empty search tree rooted in the start state.
+    pub fn new(start: PS::State, goal: PS::State, iterations: u16) -> Mcts<PS> {
+        Mcts::with_config(
+            start,
+            goal,
+            Config {
+                iterations,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Create a new, empty search tree using the given configuration.
+    pub fn with_config(start: PS::State, goal: PS::State, config: Config) -> Mcts<PS> {
+        let amaf = config.rave.map(|k| Amaf {
+            k,
+            n_vals: collections::HashMap::new(),
+            q_vals: collections::HashMap::new(),
+        });
+        Mcts {
+            root: start,
+            goal,
+            config,
+            n_vals: collections::HashMap::new(),
+            q_vals: collections::HashMap::new(),
+            children: collections::HashMap::new(),
+            amaf,
+        }
+    }
+
+    /// Returns the state the tree is currently rooted in.
+    pub fn root(&self) -> PS::State {
+        self.root
+    }
+
+    /// Run the search from the given state and return the best next step; `None` if the goal
+    /// has been reached or no step is possible. The tree is re-rooted if the state differs from
+    /// the current root.
+    pub fn best_action(&mut self, ps: &PS, state: PS::State) -> Option<PS::State> {
+        if state != self.root {
+            self.advance(state);
+        }
+        if state == self.goal || ps.succ(&state).count() == 0 {
+            return None;
+        }
+        for _ in 0..self.config.iterations {
+            let mut parents: collections::HashMap<PS::State, PS::State> =
+                collections::HashMap::new();
+            let v_i = tree_policy(
+                ps,
+                self.root,
+                &mut self.children,
+                &self.n_vals,
+                &self.q_vals,
+                &mut parents,
+                &self.config,
+                self.amaf.as_ref(),
+            );
+            let delta = default_policy(ps, v_i, self.goal, &mut parents);
+            backup(ps, v_i, delta, &mut self.n_vals, &mut self.q_vals, &parents);
+            if let Some(amaf) = self.amaf.as_mut() {
+                backup_amaf(amaf, delta, &parents);
+            }
+        }
+        best_child(
+            ps,
+            self.root,
+            &mut self.children,
+            &self.n_vals,
+            &self.q_vals,
+            0.0,
+            None,
+        )
+    }
+
+    /// Make the given state the new root of the tree; branches not reachable from it are
+    /// discarded.
+    pub fn advance(&mut self, action: PS::State) {
+        self.root = action;
+
+        let mut keep: collections::HashSet<PS::State> = collections::HashSet::new();
+        let mut todo: Vec<PS::State> = vec![action];
+        while let Some(s) = todo.pop() {
+            if keep.insert(s) {
+                if let Some(succs) = self.children.get(&s) {
+                    todo.extend(succs.iter().filter(|c| !keep.contains(c)));
+                }
+            }
+        }
+        self.n_vals.retain(|s, _| keep.contains(s));
+        self.q_vals.retain(|s, _| keep.contains(s));
+        self.children.retain(|s, _| keep.contains(s));
+        if let Some(amaf) = self.amaf.as_mut() {
+            amaf.n_vals.retain(|s, _| keep.contains(s));
+            amaf.q_vals.retain(|s, _| keep.contains(s));
+        }
+    }
+
+    /// Average reward of a state in the tree.
+    fn value(&self, s: &PS::State) -> f64 {
+        match self.n_vals.get(s) {
+            Some(n) if *n > 0 => self.q_vals[s] / *n as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns statistics on the current search tree.
+    pub fn report(&self) -> SearchReport<PS::State> {
+        let mut children = Vec::new();
+        if let Some(succs) = self.children.get(&self.root) {
+            for child in succs {
+                children.push(ChildStats {
+                    state: *child,
+                    visits: *self.n_vals.get(child).unwrap_or(&0),
+                    value: self.value(child),
+                });
+            }
+        }
+
+        // follow the most visited children - avoiding loops.
+        let mut principal_variation = Vec::new();
+        let mut seen: collections::HashSet<PS::State> = collections::HashSet::new();
+        let mut curr = self.root;
+        seen.insert(curr);
+        while let Some(succs) = self.children.get(&curr) {
+            let next = succs
+                .iter()
+                .filter(|c| !seen.contains(c))
+                .max_by_key(|c| self.n_vals.get(c).unwrap_or(&0));
+            match next {
+                Some(next) => {
+                    principal_variation.push(*next);
+                    seen.insert(*next);
+                    curr = *next;
+                }
+                None => break,
+            }
+        }
+
+        // breadth first walk to determine size & depth.
+        let mut depths: collections::HashMap<PS::State, usize> = collections::HashMap::new();
+        let mut todo: collections::VecDeque<PS::State> = collections::VecDeque::new();
+        depths.insert(self.root, 0);
+        todo.push_back(self.root);
+        while let Some(s) = todo.pop_front() {
+            if let Some(succs) = self.children.get(&s) {
+                for child in succs {
+                    if !depths.contains_key(child) {
+                        depths.insert(*child, depths[&s] + 1);
+                        todo.push_back(*child);
+                    }
+                }
+            }
+        }
+
+        SearchReport {
+            root: self.root,
+            children,
+            principal_variation,
+            size: depths.len(),
+            depth: *depths.values().max().unwrap_or(&0),
+        }
+    }
+
+    /// Export the top of the tree - up to the given depth - in DOT format.
+    pub fn to_dot(&self, max_depth: usize) -> String
+    where
+        PS::State: fmt::Debug,
+    {
+        use std::fmt::Write;
+        let mut res = String::new();
+
+        writeln!(res, "digraph mcts {{").unwrap();
+        let mut seen: collections::HashSet<PS::State> = collections::HashSet::new();
+        let mut todo: collections::VecDeque<(PS::State, usize)> = collections::VecDeque::new();
+        seen.insert(self.root);
+        todo.push_back((self.root, 0));
+        while let Some((s, depth)) = todo.pop_front() {
+            let name = format!("{:?}", s).replace('"', "\\\"");
+            writeln!(
+                res,
+                "  \"{}\" [label=\"{}\\nn={} q={:.3}\"];",
+                name,
+                name,
+                self.n_vals.get(&s).unwrap_or(&0),
+                self.value(&s)
+            )
+            .unwrap();
+            if depth >= max_depth {
+                continue;
+            }
+            if let Some(succs) = self.children.get(&s) {
+                for child in succs {
+                    let child_name = format!("{:?}", child).replace('"', "\\\"");
+                    writeln!(res, "  \"{}\" -> \"{}\";", name, child_name).unwrap();
+                    if seen.insert(*child) {
+                        todo.push_back((*child, depth + 1));
+                    }
+                }
+            }
+        }
+        writeln!(res, "}}").unwrap();
+        res
+    }
+}
+
 ///
 /// Given an problem space will try to figure our what the best next step/action is.
 ///
@@ -139,25 +517,13 @@ pub fn solve<PS: planner::ProblemSpace + planner::Anytime>(
     goal: PS::State,
     iterations: u16,
 ) -> PS::State {
-    let mut n_vals: collections::HashMap<PS::State, u64> = collections::HashMap::new();
-    let mut q_vals: collections::HashMap<PS::State, f64> = collections::HashMap::new();
-    let mut children: collections::HashMap<PS::State, Vec<PS::State>> = collections::HashMap::new();
-
+    let mut tree: Mcts<PS> = Mcts::new(start, goal, iterations);
     let mut curr = start;
 
     while curr != goal {
-        let mut i: u16 = 0;
-        while i < iterations {
-            let mut parents: collections::HashMap<PS::State, PS::State> =
-                collections::HashMap::new();
-            let v_i = tree_policy(ps, curr, &mut children, &n_vals, &q_vals);
-            parents.insert(v_i, curr);
-            let delta = default_policy(ps, v_i, goal, &mut parents);
-            backup(ps, v_i, delta, &mut n_vals, &mut q_vals, &parents);
-            i += 1;
-        }
-        curr = best_child(ps, curr, &mut children, &n_vals, &q_vals, 0.0).unwrap();
+        curr = tree.best_action(ps, curr).unwrap();
         ps.callback(&curr);
+        tree.advance(curr);
     }
     curr
 }
@@ -183,6 +549,7 @@ mod tests {
 
         fn succ(&self, s_0: &Self::State) -> Self::Iter {
             match *s_0 {
+                0 => vec![(1, 2.0), (2, 1.0)].into_iter(),
                 1 => vec![(2, 0.8), (3, 1.0)].into_iter(),
                 2 => vec![(4, 1.0)].into_iter(),
                 3 => vec![(4, 0.5), (5, 1.0)].into_iter(),
@@ -201,6 +568,32 @@ mod tests {
         fn callback(&mut self, _: &Self::State) {}
     }
 
+    /// State 0 can stay where it is - or move to 1 at infinite cost.
+    struct Loop {
+        exit: bool,
+    }
+
+    impl ProblemSpace for Loop {
+        type State = i32;
+        type Iter = vec::IntoIter<(Self::State, f64)>;
+
+        fn heuristic(&self, _: &Self::State, _: &Self::State) -> f64 {
+            0.0
+        }
+
+        fn succ(&self, s_0: &Self::State) -> Self::Iter {
+            match (*s_0, self.exit) {
+                (0, true) => vec![(0, 1.0), (1, f64::INFINITY)].into_iter(),
+                (0, false) => vec![(0, 1.0)].into_iter(),
+                _ => vec![].into_iter(),
+            }
+        }
+
+        fn pred(&self, _: &Self::State) -> Self::Iter {
+            vec![].into_iter()
+        }
+    }
+
     // Test for success.
 
     #[test]
@@ -208,7 +601,7 @@ mod tests {
         let ps = StateGraph {};
         let mut children: collections::HashMap<i32, Vec<i32>> = collections::HashMap::new();
 
-        mcts::expand(&ps, 1, &mut children);
+        mcts::expand(&ps, 1, &mut children, false);
     }
 
     #[test]
@@ -226,7 +619,7 @@ mod tests {
         q_vals.insert(2, 0.1);
         q_vals.insert(3, 0.1);
 
-        mcts::best_child(&ps, 1, &mut children, &visits, &q_vals, 1.0);
+        mcts::best_child(&ps, 1, &mut children, &visits, &q_vals, 1.0, None);
     }
 
     #[test]
@@ -236,8 +629,19 @@ mod tests {
         let visits: collections::HashMap<i32, u64> = collections::HashMap::new();
         let q_vals: collections::HashMap<i32, f64> = collections::HashMap::new();
         let mut children: collections::HashMap<i32, Vec<i32>> = collections::HashMap::new();
+        let mut parents: collections::HashMap<i32, i32> = collections::HashMap::new();
+        let config = mcts::Config::default();
 
-        mcts::tree_policy(&ps, 1, &mut children, &visits, &q_vals);
+        mcts::tree_policy(
+            &ps,
+            1,
+            &mut children,
+            &visits,
+            &q_vals,
+            &mut parents,
+            &config,
+            None,
+        );
     }
 
     #[test]
@@ -257,12 +661,53 @@ mod tests {
 
         let mut visits: collections::HashMap<i32, u64> = collections::HashMap::new();
         let mut q_vals: collections::HashMap<i32, f64> = collections::HashMap::new();
-        let mut parents: collections::HashMap<
+        let parents: collections::HashMap<
             <StateGraph as ProblemSpace>::State,
             <StateGraph as ProblemSpace>::State,
         > = collections::HashMap::new();
 
-        mcts::backup(&ps, 1, 1.0, &mut visits, &mut q_vals, &mut parents);
+        mcts::backup(&ps, 1, 1.0, &mut visits, &mut q_vals, &parents);
+    }
+
+    #[test]
+    fn test_backup_amaf_for_success() {
+        let mut amaf = mcts::Amaf {
+            k: 1.0,
+            n_vals: collections::HashMap::new(),
+            q_vals: collections::HashMap::new(),
+        };
+        let parents: collections::HashMap<i32, i32> = collections::HashMap::new();
+        mcts::backup_amaf(&mut amaf, 1.0, &parents);
+    }
+
+    #[test]
+    fn test_best_action_for_success() {
+        let ps = StateGraph {};
+        let mut tree: mcts::Mcts<StateGraph> = mcts::Mcts::new(1, 6, 3);
+        tree.best_action(&ps, 1);
+    }
+
+    #[test]
+    fn test_advance_for_success() {
+        let mut tree: mcts::Mcts<StateGraph> = mcts::Mcts::new(1, 6, 3);
+        tree.advance(2);
+    }
+
+    #[test]
+    fn test_report_for_success() {
+        let ps = StateGraph {};
+        let mut tree: mcts::Mcts<StateGraph> = mcts::Mcts::new(1, 6, 3);
+        tree.report();
+        tree.best_action(&ps, 1);
+        tree.report();
+    }
+
+    #[test]
+    fn test_to_dot_for_success() {
+        let ps = StateGraph {};
+        let mut tree: mcts::Mcts<StateGraph> = mcts::Mcts::new(1, 6, 3);
+        tree.best_action(&ps, 1);
+        tree.to_dot(2);
     }
 
     #[test]
@@ -289,6 +734,64 @@ mod tests {
         ps.pred(&0);
     }
 
+    #[test]
+    fn test_tree_policy_for_failure() {
+        let visits: collections::HashMap<i32, u64> = collections::HashMap::new();
+        let q_vals: collections::HashMap<i32, f64> = collections::HashMap::new();
+        let mut parents: collections::HashMap<i32, i32> = collections::HashMap::new();
+        let mut children: collections::HashMap<i32, Vec<i32>> = collections::HashMap::new();
+        let widening = mcts::Config {
+            widening: Some((2.0, 0.0)),
+            ..Default::default()
+        };
+
+        // only a transition back to itself --> nothing to expand.
+        for config in [mcts::Config::default(), widening] {
+            let ps = Loop { exit: false };
+            let res = mcts::tree_policy(
+                &ps,
+                0,
+                &mut children,
+                &visits,
+                &q_vals,
+                &mut parents,
+                &config,
+                None,
+            );
+            assert_eq!(res, 0);
+            assert!(children.is_empty());
+
+            // infinite costs are expanded; afterwards the existing child gets selected.
+            let ps = Loop { exit: true };
+            let res = mcts::tree_policy(
+                &ps,
+                0,
+                &mut children,
+                &visits,
+                &q_vals,
+                &mut parents,
+                &config,
+                None,
+            );
+            assert_eq!(res, 1);
+            let visits = collections::HashMap::from([(0, 1), (1, 1)]);
+            let q_vals = collections::HashMap::from([(1, 0.0)]);
+            let res = mcts::tree_policy(
+                &ps,
+                0,
+                &mut children,
+                &visits,
+                &q_vals,
+                &mut parents,
+                &config,
+                None,
+            );
+            assert_eq!(res, 1);
+            assert_eq!(children[&0], vec![1]);
+            children.clear();
+        }
+    }
+
     // Test for sanity.
 
     #[test]
@@ -298,13 +801,24 @@ mod tests {
 
         // should add 3 to the list of children of 1.
         children.insert(1, vec![2]);
-        let res = mcts::expand(&ps, 1, &mut children);
+        let res = mcts::expand(&ps, 1, &mut children, false);
         assert_eq!(children[&1], vec![2, 3]);
         assert_eq!(res.unwrap(), 3);
 
         // now all children have been found --> None.
-        let res = mcts::expand(&ps, 1, &mut children);
+        let res = mcts::expand(&ps, 1, &mut children, false);
         assert_eq!(res, None);
+
+        // first transition gets expanded first.
+        let res = mcts::expand(&ps, 0, &mut children, false);
+        assert_eq!(res.unwrap(), 1);
+
+        // cheapest transition gets expanded first.
+        children.clear();
+        let res = mcts::expand(&ps, 0, &mut children, true);
+        assert_eq!(res.unwrap(), 2);
+        let res = mcts::expand(&ps, 0, &mut children, true);
+        assert_eq!(res.unwrap(), 1);
     }
 
     #[test]
@@ -324,16 +838,29 @@ mod tests {
         q_vals.insert(3, 2.5);
 
         // (Exploration) 3 has a high q_val --> best child.
-        let res = mcts::best_child(&ps, 1, &mut children, &visits, &q_vals, 1.0);
+        let res = mcts::best_child(&ps, 1, &mut children, &visits, &q_vals, 1.0, None);
         assert_eq!(res.unwrap(), 3);
 
         // (next step) c val --> 0.0
-        let res = mcts::best_child(&ps, 1, &mut children, &visits, &q_vals, 0.0);
+        let res = mcts::best_child(&ps, 1, &mut children, &visits, &q_vals, 0.0, None);
         assert_eq!(res.unwrap(), 3);
 
         // no child states.
-        let res = mcts::best_child(&ps, 6, &mut children, &visits, &q_vals, 0.0);
+        let res = mcts::best_child(&ps, 6, &mut children, &visits, &q_vals, 0.0, None);
         assert_eq!(res, None);
+
+        // (RAVE) simulations through 2 did well elsewhere in the tree --> 2 is favoured.
+        let mut amaf = mcts::Amaf {
+            k: 1000.0,
+            n_vals: collections::HashMap::new(),
+            q_vals: collections::HashMap::new(),
+        };
+        amaf.n_vals.insert(2, 1);
+        amaf.q_vals.insert(2, 10.0);
+        amaf.n_vals.insert(3, 1);
+        amaf.q_vals.insert(3, 0.0);
+        let res = mcts::best_child(&ps, 1, &mut children, &visits, &q_vals, 0.0, Some(&amaf));
+        assert_eq!(res.unwrap(), 2);
     }
 
     #[test]
@@ -343,15 +870,37 @@ mod tests {
         let mut visits: collections::HashMap<i32, u64> = collections::HashMap::new();
         let mut q_vals: collections::HashMap<i32, f64> = collections::HashMap::new();
         let mut children: collections::HashMap<i32, Vec<i32>> = collections::HashMap::new();
+        let mut parents: collections::HashMap<i32, i32> = collections::HashMap::new();
+        let config = mcts::Config::default();
 
         // no children - return itself.
-        let res = mcts::tree_policy(&ps, 6, &mut children, &visits, &q_vals);
+        let res = mcts::tree_policy(
+            &ps,
+            6,
+            &mut children,
+            &visits,
+            &q_vals,
+            &mut parents,
+            &config,
+            None,
+        );
         assert_eq!(res, 6);
+        assert_eq!(parents.len(), 0);
 
         // in case we expand - need to look into 3...walk all the way to the end...
         children.insert(1, vec![2]);
-        let res2 = mcts::tree_policy(&ps, 1, &mut children, &visits, &q_vals);
+        let res2 = mcts::tree_policy(
+            &ps,
+            1,
+            &mut children,
+            &visits,
+            &q_vals,
+            &mut parents,
+            &config,
+            None,
+        );
         assert_eq!(res2, 3);
+        assert_eq!(parents[&3], 1);
 
         // in case we try to find best child
         children.insert(3, vec![4, 5]);
@@ -360,8 +909,73 @@ mod tests {
         q_vals.insert(4, 1.0);
         visits.insert(5, 2);
         q_vals.insert(5, 0.8);
-        let res3 = mcts::tree_policy(&ps, 3, &mut children, &visits, &q_vals);
+        let res3 = mcts::tree_policy(
+            &ps,
+            3,
+            &mut children,
+            &visits,
+            &q_vals,
+            &mut parents,
+            &config,
+            None,
+        );
         assert_eq!(res3, 5);
+
+        // path through the tree is recorded - 1 -> 3 -> 4 -> 5 -> 6.
+        visits.insert(1, 2);
+        q_vals.insert(3, 1.0);
+        visits.insert(2, 1);
+        q_vals.insert(2, 0.1);
+        let mut parents: collections::HashMap<i32, i32> = collections::HashMap::new();
+        children.insert(1, vec![2, 3]);
+        let res4 = mcts::tree_policy(
+            &ps,
+            1,
+            &mut children,
+            &visits,
+            &q_vals,
+            &mut parents,
+            &config,
+            None,
+        );
+        assert_eq!(res4, 6);
+        assert_eq!(parents[&6], 5);
+        assert_eq!(parents[&5], 4);
+        assert_eq!(parents[&4], 3);
+        assert_eq!(parents[&3], 1);
+
+        // (progressive widening) at most one child allowed --> no further expansion of 0.
+        let config = mcts::Config {
+            widening: Some((1.0, 0.0)),
+            ..Default::default()
+        };
+        let mut parents: collections::HashMap<i32, i32> = collections::HashMap::new();
+        let res5 = mcts::tree_policy(
+            &ps,
+            0,
+            &mut children,
+            &visits,
+            &q_vals,
+            &mut parents,
+            &config,
+            None,
+        );
+        assert_eq!(res5, 2);
+        assert_eq!(children[&0], vec![2]);
+        visits.insert(0, 1);
+        let mut parents: collections::HashMap<i32, i32> = collections::HashMap::new();
+        mcts::tree_policy(
+            &ps,
+            0,
+            &mut children,
+            &visits,
+            &q_vals,
+            &mut parents,
+            &config,
+            None,
+        );
+        assert_eq!(children[&0], vec![2]);
+        assert_eq!(parents[&2], 0);
     }
 
     #[test]
@@ -391,7 +1005,7 @@ mod tests {
         parents.insert(4, 3);
         parents.insert(3, 2);
         parents.insert(2, 1);
-        mcts::backup(&ps, 4, 1.2, &mut visits, &mut q_vals, &mut parents);
+        mcts::backup(&ps, 4, 1.2, &mut visits, &mut q_vals, &parents);
 
         assert_eq!(visits[&3], 1);
         assert_eq!(visits[&1], 1);
@@ -399,6 +1013,131 @@ mod tests {
         assert_eq!(q_vals[&1], 1.2);
     }
 
+    #[test]
+    fn test_backup_amaf_for_sanity() {
+        let mut amaf = mcts::Amaf {
+            k: 1.0,
+            n_vals: collections::HashMap::new(),
+            q_vals: collections::HashMap::new(),
+        };
+        let mut parents: collections::HashMap<i32, i32> = collections::HashMap::new();
+        parents.insert(5, 4);
+        parents.insert(4, 3);
+        parents.insert(3, 1);
+
+        // all states of the simulation get the reward - once.
+        mcts::backup_amaf(&mut amaf, 1.5, &parents);
+        assert_eq!(amaf.n_vals.len(), 4);
+        assert_eq!(amaf.n_vals[&4], 1);
+        assert_eq!(amaf.q_vals[&1], 1.5);
+        assert_eq!(amaf.q_vals[&5], 1.5);
+    }
+
+    #[test]
+    fn test_best_action_for_sanity() {
+        let ps = StateGraph {};
+        let mut tree: mcts::Mcts<StateGraph> = mcts::Mcts::new(1, 6, 10);
+
+        // 3 leads to cheaper transitions --> best next step.
+        let res = tree.best_action(&ps, 1);
+        assert_eq!(res, Some(3));
+        assert_eq!(tree.n_vals[&1], 10);
+
+        // state differs from root --> tree gets re-rooted before searching.
+        let res = tree.best_action(&ps, 4);
+        assert_eq!(tree.root(), 4);
+        assert_eq!(res, Some(5));
+        assert!(!tree.n_vals.contains_key(&1));
+
+        // goal & dead ends have no next step.
+        assert_eq!(tree.best_action(&ps, 6), None);
+        let mut tree: mcts::Mcts<StateGraph> = mcts::Mcts::new(7, 6, 10);
+        assert_eq!(tree.best_action(&ps, 7), None);
+
+        // widening & RAVE still find the cheaper route.
+        let config = mcts::Config {
+            iterations: 10,
+            widening: Some((1.0, 0.5)),
+            rave: Some(10.0),
+            ..Default::default()
+        };
+        let mut tree: mcts::Mcts<StateGraph> = mcts::Mcts::with_config(1, 6, config);
+        assert_eq!(tree.best_action(&ps, 1), Some(3));
+        assert!(tree.amaf.as_ref().unwrap().n_vals.contains_key(&6));
+    }
+
+    #[test]
+    fn test_advance_for_sanity() {
+        let ps = StateGraph {};
+        let mut tree: mcts::Mcts<StateGraph> = mcts::Mcts::new(1, 6, 10);
+        tree.best_action(&ps, 1);
+        assert!(tree.n_vals.contains_key(&2));
+        let visits_3 = tree.n_vals[&3];
+
+        // sibling 2 is discarded - subtree of 3 is kept incl. its statistics.
+        tree.advance(3);
+        assert_eq!(tree.root(), 3);
+        assert!(!tree.n_vals.contains_key(&1));
+        assert!(!tree.n_vals.contains_key(&2));
+        assert!(!tree.children.contains_key(&2));
+        assert_eq!(tree.n_vals[&3], visits_3);
+        assert_eq!(tree.children[&3], vec![4, 5]);
+
+        // unknown state --> nothing to reuse.
+        tree.advance(42);
+        assert_eq!(tree.n_vals.len(), 0);
+        assert_eq!(tree.children.len(), 0);
+    }
+
+    #[test]
+    fn test_report_for_sanity() {
+        let ps = StateGraph {};
+        let mut tree: mcts::Mcts<StateGraph> = mcts::Mcts::new(1, 6, 10);
+
+        // empty tree.
+        let report = tree.report();
+        assert_eq!(report.root, 1);
+        assert_eq!(report.children.len(), 0);
+        assert_eq!(report.principal_variation.len(), 0);
+        assert_eq!(report.size, 1);
+        assert_eq!(report.depth, 0);
+
+        // after searching - all states are in the tree; 6 is 3 steps away from the root.
+        tree.best_action(&ps, 1);
+        let report = tree.report();
+        assert_eq!(report.children.len(), 2);
+        let visits: u64 = report.children.iter().map(|c| c.visits).sum();
+        assert_eq!(visits, 10);
+        assert_eq!(report.children[1].state, 3);
+        assert_eq!(
+            report.children[1].value,
+            tree.q_vals[&3] / tree.n_vals[&3] as f64
+        );
+        assert_eq!(report.principal_variation[0], 3);
+        assert_eq!(*report.principal_variation.last().unwrap(), 6);
+        assert_eq!(report.size, 6);
+        assert_eq!(report.depth, 3);
+    }
+
+    #[test]
+    fn test_to_dot_for_sanity() {
+        let ps = StateGraph {};
+        let mut tree: mcts::Mcts<StateGraph> = mcts::Mcts::new(1, 6, 10);
+        tree.best_action(&ps, 1);
+
+        // only the root & its children.
+        let dot = tree.to_dot(1);
+        assert!(dot.starts_with("digraph mcts {"));
+        assert!(dot.contains("\"1\" -> \"3\";"));
+        assert!(dot.contains(&format!("\"3\" [label=\"3\\nn={}", tree.n_vals[&3])));
+        assert!(!dot.contains("\"3\" -> \"4\";"));
+        assert!(dot.trim_end().ends_with('}'));
+
+        // deeper.
+        let dot = tree.to_dot(2);
+        assert!(dot.contains("\"3\" -> \"4\";"));
+    }
+
     #[test]
     fn test_solve_for_sanity() {
         let mut ps = StateGraph {};
@@ -0,0 +1,1619 @@
+use std::cell;
+use std::collections;
+use std::error;
+use std::fmt;
+use std::vec;
+
+use crate::planner;
+use crate::strips;
+use crate::util;
+
+/// Requirements which can be parsed & grounded.
+const REQUIREMENTS: [&str; 6] = [
+    ":strips",
+    ":typing",
+    ":negative-preconditions",
+    ":conditional-effects",
+    ":equality",
+    ":action-costs",
+];
+
+/// Id of the artificial state all goal states lead to.
+const GOAL: usize = 0;
+/// Id of the initial state.
+const INIT: usize = 1;
+
+///
+/// Reasons a domain or problem can not be parsed or grounded.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The text is not well-formed - details on what was expected.
+    Syntax(String),
+    /// A PDDL feature which is rejected - `forall`, `exists` & disjunctive preconditions or
+    /// numeric effects other than `increase total-cost`.
+    Unsupported(String),
+    /// Reference to a type, predicate, object, variable or function which is not declared.
+    Undefined(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Syntax(what) => write!(f, "syntax error: {}", what),
+            Error::Unsupported(what) => write!(f, "unsupported feature: {}", what),
+            Error::Undefined(what) => write!(f, "undefined: {}", what),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// S-expression - PDDL's syntax.
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Atom(String),
+    List(Vec<Expr>),
+}
+
+impl Expr {
+    fn atom(&self) -> Result<&str, Error> {
+        match self {
+            Expr::Atom(atom) => Ok(atom),
+            Expr::List(_) => Err(Error::Syntax(format!("expected a name, found {}", self))),
+        }
+    }
+
+    fn list(&self) -> Result<&[Expr], Error> {
+        match self {
+            Expr::List(items) => Ok(items),
+            Expr::Atom(atom) => Err(Error::Syntax(format!("expected a list, found {}", atom))),
+        }
+    }
+
+    /// Name at the beginning of a list - if any.
+    fn head(&self) -> Option<&str> {
+        match self {
+            Expr::List(items) => match items.first() {
+                Some(Expr::Atom(atom)) => Some(atom),
+                _ => None,
+            },
+            Expr::Atom(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Atom(atom) => write!(f, "{}", atom),
+            Expr::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "({})", items.join(" "))
+            }
+        }
+    }
+}
+
+/// Split the text into parentheses & names - PDDL is case-insensitive & ';' starts a comment.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut res = Vec::new();
+    for line in text.lines() {
+        let line = line.split(';').next().unwrap_or("");
+        let line = line.replace('(', " ( ").replace(')', " ) ");
+        res.extend(line.split_whitespace().map(|token| token.to_lowercase()));
+    }
+    res
+}
+
+fn read(tokens: &[String], pos: &mut usize) -> Result<Expr, Error> {
+    let token = tokens
+        .get(*pos)
+        .ok_or_else(|| Error::Syntax(String::from("unexpected end of input")))?;
+    *pos += 1;
+    match token.as_str() {
+        "(" => {
+            let mut items = Vec::new();
+            while tokens.get(*pos).is_some_and(|token| token != ")") {
+                items.push(read(tokens, pos)?);
+            }
+            if *pos == tokens.len() {
+                return Err(Error::Syntax(String::from("missing ')'")));
+            }
+            *pos += 1;
+            Ok(Expr::List(items))
+        }
+        ")" => Err(Error::Syntax(String::from("unexpected ')'"))),
+        _ => Ok(Expr::Atom(token.clone())),
+    }
+}
+
+fn parse(text: &str) -> Result<Expr, Error> {
+    let tokens = tokenize(text);
+    let mut pos = 0;
+    let res = read(&tokens, &mut pos)?;
+    if pos != tokens.len() {
+        return Err(Error::Syntax(format!("unexpected '{}'", tokens[pos])));
+    }
+    Ok(res)
+}
+
+/// Split `(define (<kind> <name>) <sections>...)` into the name & the sections.
+fn define<'a>(expr: &'a Expr, kind: &str) -> Result<(String, &'a [Expr]), Error> {
+    let items = expr.list()?;
+    if items.len() < 2 || items[0] != Expr::Atom(String::from("define")) {
+        return Err(Error::Syntax(String::from("expected (define ...)")));
+    }
+    match items[1].list()? {
+        [Expr::Atom(head), Expr::Atom(name)] if head == kind => Ok((name.clone(), &items[2..])),
+        _ => Err(Error::Syntax(format!("expected ({} <name>)", kind))),
+    }
+}
+
+/// Names with their types - `a b - t c` gives `[(a, t), (b, t), (c, object)]`.
+fn typed_list(items: &[Expr]) -> Result<Vec<(String, String)>, Error> {
+    let mut res = Vec::new();
+    let mut pending: Vec<String> = Vec::new();
+    let mut iter = items.iter();
+    while let Some(item) = iter.next() {
+        let name = item.atom()?;
+        if name == "-" {
+            let ty = match iter.next() {
+                Some(Expr::Atom(ty)) => ty.clone(),
+                Some(expr) if expr.head() == Some("either") => {
+                    return Err(Error::Unsupported(String::from("either")))
+                }
+                _ => return Err(Error::Syntax(String::from("expected a type after '-'"))),
+            };
+            res.extend(pending.drain(..).map(|name| (name, ty.clone())));
+        } else {
+            pending.push(name.to_string());
+        }
+    }
+    res.extend(
+        pending
+            .into_iter()
+            .map(|name| (name, String::from("object"))),
+    );
+    Ok(res)
+}
+
+///
+/// Predicate applied to arguments - objects, or variables starting with '?'.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Atom {
+    pub predicate: String,
+    pub args: Vec<String>,
+}
+
+impl Atom {
+    fn parse(expr: &Expr) -> Result<Atom, Error> {
+        let items = expr.list()?;
+        let predicate = match items.first() {
+            Some(item) => item.atom()?.to_string(),
+            None => return Err(Error::Syntax(String::from("empty atom"))),
+        };
+        let args = items[1..]
+            .iter()
+            .map(|arg| arg.atom().map(|arg| arg.to_string()))
+            .collect::<Result<Vec<String>, Error>>()?;
+        Ok(Atom { predicate, args })
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}", self.predicate)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        write!(f, ")")
+    }
+}
+
+///
+/// Atom which needs to hold (positive) or must not hold.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Literal {
+    pub positive: bool,
+    pub atom: Atom,
+}
+
+/// Conjunction of literals - `and` gets flattened.
+fn condition(expr: &Expr) -> Result<Vec<Literal>, Error> {
+    let items = expr.list()?;
+    match expr.head() {
+        None if items.is_empty() => Ok(vec![]),
+        Some("and") => {
+            let mut res = Vec::new();
+            for item in &items[1..] {
+                res.extend(condition(item)?);
+            }
+            Ok(res)
+        }
+        Some("not") if items.len() == 2 => match items[1].head() {
+            Some("and" | "or" | "not" | "imply" | "exists" | "forall") => Err(Error::Unsupported(
+                format!("negated {}", items[1].head().unwrap()),
+            )),
+            _ => Ok(vec![Literal {
+                positive: false,
+                atom: Atom::parse(&items[1])?,
+            }]),
+        },
+        Some(head @ ("or" | "imply" | "exists" | "forall" | "not")) => {
+            Err(Error::Unsupported(head.to_string()))
+        }
+        _ => Ok(vec![Literal {
+            positive: true,
+            atom: Atom::parse(expr)?,
+        }]),
+    }
+}
+
+///
+/// Literal made true by an action - if all literals of the condition hold before it is applied.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Effect {
+    /// Variables of the enclosing `forall`s & their types - the effect applies for all objects.
+    pub variables: Vec<(String, String)>,
+    pub condition: Vec<Literal>,
+    pub literal: Literal,
+}
+
+///
+/// Amount the total cost gets increased by.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cost {
+    Constant(f64),
+    /// Value of a function given in the initial state of the problem.
+    Function(Atom),
+}
+
+/// Collect the effects & costs - `and` gets flattened, `when` turns into conditions & `forall`
+/// into variables.
+fn effects(
+    expr: &Expr,
+    vars: &[(String, String)],
+    cond: &[Literal],
+    res: &mut Vec<Effect>,
+    costs: &mut Vec<Cost>,
+) -> Result<(), Error> {
+    let items = expr.list()?;
+    match expr.head() {
+        None if items.is_empty() => {}
+        Some("and") => {
+            for item in &items[1..] {
+                effects(item, vars, cond, res, costs)?;
+            }
+        }
+        Some("when") if items.len() == 3 => {
+            if !cond.is_empty() {
+                return Err(Error::Unsupported(String::from("nested when")));
+            }
+            effects(&items[2], vars, &condition(&items[1])?, res, costs)?;
+        }
+        Some("forall") if items.len() == 3 => {
+            let mut vars = vars.to_vec();
+            vars.extend(typed_list(items[1].list()?)?);
+            effects(&items[2], &vars, cond, res, costs)?;
+        }
+        Some("increase") if items.len() == 3 => {
+            if items[1] != Expr::List(vec![Expr::Atom(String::from("total-cost"))]) {
+                return Err(Error::Unsupported(format!("increase of {}", items[1])));
+            }
+            if !cond.is_empty() {
+                return Err(Error::Unsupported(String::from("conditional costs")));
+            }
+            if !vars.is_empty() {
+                return Err(Error::Unsupported(String::from("quantified costs")));
+            }
+            costs.push(match &items[2] {
+                Expr::Atom(value) => {
+                    Cost::Constant(value.parse().map_err(|_| {
+                        Error::Syntax(format!("expected a number, found {}", value))
+                    })?)
+                }
+                function => Cost::Function(Atom::parse(function)?),
+            });
+        }
+        Some(head @ ("forall" | "decrease" | "assign" | "scale-up" | "scale-down" | "when")) => {
+            return Err(Error::Unsupported(head.to_string()))
+        }
+        _ => {
+            for literal in condition(expr)? {
+                res.push(Effect {
+                    variables: vars.to_vec(),
+                    condition: cond.to_vec(),
+                    literal,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+///
+/// Action schema of a domain.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Action {
+    pub name: String,
+    /// Variables & their types.
+    pub parameters: Vec<(String, String)>,
+    pub precondition: Vec<Literal>,
+    pub effects: Vec<Effect>,
+    /// Increases of the total cost - requires `:action-costs`.
+    pub cost: Vec<Cost>,
+}
+
+impl Action {
+    fn parse(items: &[Expr]) -> Result<Action, Error> {
+        let mut action = Action {
+            name: match items.first() {
+                Some(name) => name.atom()?.to_string(),
+                None => return Err(Error::Syntax(String::from("action without a name"))),
+            },
+            parameters: vec![],
+            precondition: vec![],
+            effects: vec![],
+            cost: vec![],
+        };
+        for pair in items[1..].chunks(2) {
+            let value = match pair {
+                [_, value] => value,
+                _ => return Err(Error::Syntax(format!("missing value in {}", action.name))),
+            };
+            match pair[0].atom()? {
+                ":parameters" => action.parameters = typed_list(value.list()?)?,
+                ":precondition" => action.precondition = condition(value)?,
+                ":effect" => effects(value, &[], &[], &mut action.effects, &mut action.cost)?,
+                other => return Err(Error::Unsupported(other.to_string())),
+            }
+        }
+        Ok(action)
+    }
+}
+
+///
+/// Domain with its types, predicates & actions.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Domain {
+    pub name: String,
+    pub requirements: Vec<String>,
+    /// Types & their parent types.
+    pub types: Vec<(String, String)>,
+    pub constants: Vec<(String, String)>,
+    /// Predicates & the types of their parameters.
+    pub predicates: Vec<(String, Vec<String>)>,
+    pub actions: Vec<Action>,
+}
+
+impl Domain {
+    ///
+    /// Parse a PDDL domain - actions are checked to only refer to declared predicates & variables.
+    ///
+    pub fn parse(text: &str) -> Result<Domain, Error> {
+        let expr = parse(text)?;
+        let (name, sections) = define(&expr, "domain")?;
+        let mut domain = Domain {
+            name,
+            requirements: vec![String::from(":strips")],
+            types: vec![],
+            constants: vec![],
+            predicates: vec![],
+            actions: vec![],
+        };
+        for section in sections {
+            let items = section.list()?;
+            let rest = &items[1.min(items.len())..];
+            match section.head() {
+                Some(":requirements") => {
+                    for requirement in rest {
+                        let requirement = requirement.atom()?;
+                        if !REQUIREMENTS.contains(&requirement) {
+                            return Err(Error::Unsupported(requirement.to_string()));
+                        }
+                        domain.requirements.push(requirement.to_string());
+                    }
+                }
+                Some(":types") => domain.types = typed_list(rest)?,
+                Some(":constants") => domain.constants = typed_list(rest)?,
+                Some(":predicates") => {
+                    for predicate in rest {
+                        let items = predicate.list()?;
+                        let name = match items.first() {
+                            Some(name) => name.atom()?.to_string(),
+                            None => return Err(Error::Syntax(String::from("empty predicate"))),
+                        };
+                        let types = typed_list(&items[1..])?.into_iter().map(|p| p.1);
+                        domain.predicates.push((name, types.collect()));
+                    }
+                }
+                // only the total cost & static functions are supported - checked when grounding.
+                Some(":functions") => {}
+                Some(":action") => domain.actions.push(Action::parse(rest)?),
+                Some(other) => return Err(Error::Unsupported(other.to_string())),
+                None => return Err(Error::Syntax(format!("unexpected {}", section))),
+            }
+        }
+        for action in &domain.actions {
+            let variables: Vec<&str> = action.parameters.iter().map(|p| p.0.as_str()).collect();
+            for literal in &action.precondition {
+                domain.check(&literal.atom, &variables)?;
+            }
+            for effect in &action.effects {
+                let mut variables = variables.clone();
+                variables.extend(effect.variables.iter().map(|v| v.0.as_str()));
+                for literal in effect.condition.iter().chain([&effect.literal]) {
+                    domain.check(&literal.atom, &variables)?;
+                }
+            }
+        }
+        Ok(domain)
+    }
+
+    /// Check the atom refers to a declared predicate with the right arity & declared variables.
+    fn check(&self, atom: &Atom, variables: &[&str]) -> Result<(), Error> {
+        let arity = match self.predicates.iter().find(|p| p.0 == atom.predicate) {
+            Some(predicate) => predicate.1.len(),
+            None if atom.predicate == "=" => 2,
+            None => return Err(Error::Undefined(format!("predicate {}", atom.predicate))),
+        };
+        if atom.args.len() != arity {
+            return Err(Error::Syntax(format!(
+                "{} expects {} arguments",
+                atom, arity
+            )));
+        }
+        for arg in &atom.args {
+            if arg.starts_with('?') && !variables.contains(&arg.as_str()) {
+                return Err(Error::Undefined(format!("variable {} in {}", arg, atom)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if the type is the given one or one of its subtypes.
+    pub fn is_a(&self, ty: &str, parent: &str) -> bool {
+        let mut curr = ty;
+        // the bound guards against cyclic type hierarchies.
+        for _ in 0..=self.types.len() {
+            if curr == parent {
+                return true;
+            }
+            match self.types.iter().find(|t| t.0 == curr) {
+                Some(t) => curr = &t.1,
+                None => return false,
+            }
+        }
+        false
+    }
+}
+
+///
+/// Problem with its objects, initial state & goal.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    pub name: String,
+    pub domain: String,
+    pub objects: Vec<(String, String)>,
+    pub init: Vec<Atom>,
+    /// Values of the functions - e.g. the costs of the actions.
+    pub values: Vec<(Atom, f64)>,
+    pub goal: Vec<Literal>,
+}
+
+impl Problem {
+    ///
+    /// Parse a PDDL problem.
+    ///
+    pub fn parse(text: &str) -> Result<Problem, Error> {
+        let expr = parse(text)?;
+        let (name, sections) = define(&expr, "problem")?;
+        let mut problem = Problem {
+            name,
+            domain: String::new(),
+            objects: vec![],
+            init: vec![],
+            values: vec![],
+            goal: vec![],
+        };
+        for section in sections {
+            let items = section.list()?;
+            let rest = &items[1.min(items.len())..];
+            match section.head() {
+                Some(":domain") if rest.len() == 1 => problem.domain = rest[0].atom()?.to_string(),
+                Some(":objects") => problem.objects = typed_list(rest)?,
+                Some(":init") => {
+                    for item in rest {
+                        match item.list()? {
+                            [Expr::Atom(eq), function, Expr::Atom(value)] if eq == "=" => {
+                                let value = value.parse().map_err(|_| {
+                                    Error::Syntax(format!("expected a number, found {}", value))
+                                })?;
+                                problem.values.push((Atom::parse(function)?, value));
+                            }
+                            _ => problem.init.push(Atom::parse(item)?),
+                        }
+                    }
+                }
+                Some(":goal") if rest.len() == 1 => problem.goal = condition(&rest[0])?,
+                // plans are always of minimal cost.
+                Some(":requirements" | ":metric") => {}
+                Some(other) => return Err(Error::Unsupported(other.to_string())),
+                None => return Err(Error::Syntax(format!("unexpected {}", section))),
+            }
+        }
+        Ok(problem)
+    }
+}
+
+///
+/// Conditional effect of a ground operator.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conditional {
+    pub pre: Vec<usize>,
+    pub pre_neg: Vec<usize>,
+    pub add: Vec<usize>,
+    pub del: Vec<usize>,
+}
+
+///
+/// Ground action - facts are given by their index.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operator {
+    /// Name incl. the arguments - e.g. `(drive truck a b)`.
+    pub name: String,
+    pub pre: Vec<usize>,
+    pub pre_neg: Vec<usize>,
+    pub add: Vec<usize>,
+    pub del: Vec<usize>,
+    pub conditional: Vec<Conditional>,
+    pub cost: f64,
+}
+
+fn satisfied(facts: &strips::FactSet, pre: &[usize], pre_neg: &[usize]) -> bool {
+    facts.contains_all(pre) && pre_neg.iter().all(|f| !facts.contains(*f))
+}
+
+/// Facts an instance needs & the facts it adds (incl. the facts their conditions need) - ignoring
+/// negative preconditions & delete effects.
+type Relaxed = (Vec<String>, Vec<(Vec<String>, String)>);
+
+/// Ground action schema before the facts got indexed.
+#[derive(Clone)]
+struct Instance<'a> {
+    action: &'a Action,
+    args: Vec<&'a str>,
+    /// Objects bound to the variables of `forall` effects.
+    quantified: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Instance<'a> {
+    /// Object bound to the variable - constants stay as they are.
+    fn arg<'b>(&'b self, arg: &'b str) -> &'b str {
+        if let Some((_, object)) = self.quantified.iter().rev().find(|q| q.0 == arg) {
+            return object;
+        }
+        match self.action.parameters.iter().position(|p| p.0 == arg) {
+            Some(i) => self.args[i],
+            None => arg,
+        }
+    }
+
+    /// Instances of an effect - one for every assignment of objects to its `forall` variables.
+    fn quantify(
+        &self,
+        effect: &'a Effect,
+        objects: &'a [(String, String)],
+        domain: &Domain,
+    ) -> Vec<Instance<'a>> {
+        let mut res = vec![self.clone()];
+        for (var, ty) in &effect.variables {
+            let mut next = Vec::new();
+            for inst in &res {
+                for (object, _) in objects.iter().filter(|o| domain.is_a(&o.1, ty)) {
+                    let mut inst = inst.clone();
+                    inst.quantified.push((var, object));
+                    next.push(inst);
+                }
+            }
+            res = next;
+        }
+        res
+    }
+
+    /// Name of the ground atom.
+    fn bind(&self, atom: &Atom) -> String {
+        Atom {
+            predicate: atom.predicate.clone(),
+            args: atom
+                .args
+                .iter()
+                .map(|arg| self.arg(arg).to_string())
+                .collect(),
+        }
+        .to_string()
+    }
+}
+
+///
+/// Grounded planning task - implements `planner::ProblemSpace` over the states reachable from
+/// the initial one. States are ids of fact sets; as goals are partial all goal states lead to an
+/// artificial goal state (at no cost), hence solvers are given `initial_state` & `goal_state`.
+///
+/// *Note*: predecessors are found by regression - only those in which no pair of facts is mutually
+/// exclusive are kept, hence backward searches skip most states which can't be reached.
+///
+/// # Example
+/// ```
+/// use rusty_planner::cooperative;
+/// use rusty_planner::pddl;
+///
+/// let domain = pddl::Domain::parse(
+///     "(define (domain switch)
+///        (:predicates (on))
+///        (:action flip :parameters () :precondition (not (on)) :effect (on)))",
+/// )
+/// .unwrap();
+/// let problem = pddl::Problem::parse(
+///     "(define (problem turn-on) (:domain switch) (:init) (:goal (on)))",
+/// )
+/// .unwrap();
+/// let task = pddl::Task::ground(&domain, &problem).unwrap();
+///
+/// let mut dist = cooperative::TrueDistance::new(task.goal_state());
+/// assert_eq!(dist.distance(&task, &task.initial_state()), 1.0);
+/// ```
+///
+pub struct Task {
+    /// Names of the facts - e.g. `(at truck a)`.
+    pub facts: Vec<String>,
+    pub operators: Vec<Operator>,
+    pub init: Vec<usize>,
+    pub goal: Vec<usize>,
+    pub goal_neg: Vec<usize>,
+    states: cell::RefCell<util::Interner<strips::FactSet>>,
+    /// Pairs of facts which can't hold together - found on first use.
+    mutexes: cell::OnceCell<strips::Mutexes>,
+}
+
+impl Task {
+    ///
+    /// Ground the problem - only operators & facts reachable when ignoring negative preconditions
+    /// & delete effects are kept; facts which no operator changes get compiled away.
+    ///
+    pub fn ground(domain: &Domain, problem: &Problem) -> Result<Task, Error> {
+        if problem.domain != domain.name {
+            return Err(Error::Undefined(format!("domain {}", problem.domain)));
+        }
+        let mut objects = domain.constants.clone();
+        objects.extend(problem.objects.iter().cloned());
+        for (object, ty) in &objects {
+            if ty != "object" && !domain.types.iter().any(|t| t.0 == *ty) {
+                return Err(Error::Undefined(format!("type {} of {}", ty, object)));
+            }
+        }
+        let names: Vec<&str> = objects.iter().map(|o| o.0.as_str()).collect();
+        for atom in problem
+            .init
+            .iter()
+            .chain(problem.goal.iter().map(|l| &l.atom))
+        {
+            domain.check(atom, &[])?;
+        }
+        let atoms = problem
+            .init
+            .iter()
+            .chain(problem.goal.iter().map(|l| &l.atom));
+        for atom in atoms.chain(problem.values.iter().map(|v| &v.0)) {
+            if let Some(arg) = atom.args.iter().find(|a| !names.contains(&a.as_str())) {
+                return Err(Error::Undefined(format!("object {} in {}", arg, atom)));
+            }
+        }
+
+        // predicates changed by some action - all others are static.
+        let fluents: collections::HashSet<&str> = domain
+            .actions
+            .iter()
+            .flat_map(|a| a.effects.iter().map(|e| e.literal.atom.predicate.as_str()))
+            .collect();
+        let init: collections::HashSet<String> =
+            problem.init.iter().map(|a| a.to_string()).collect();
+
+        // ground the actions - static preconditions get checked right away.
+        let mut instances = Vec::new();
+        for action in &domain.actions {
+            let candidates = action
+                .parameters
+                .iter()
+                .map(|(_, ty)| {
+                    let res: Vec<&str> = objects
+                        .iter()
+                        .filter(|o| domain.is_a(&o.1, ty))
+                        .map(|o| o.0.as_str())
+                        .collect();
+                    res
+                })
+                .collect::<Vec<_>>();
+            let mut instance = Instance {
+                action,
+                args: Vec::new(),
+                quantified: Vec::new(),
+            };
+            let statics: Vec<&Literal> = action
+                .precondition
+                .iter()
+                .filter(|l| !fluents.contains(l.atom.predicate.as_str()))
+                .collect();
+            enumerate(&mut instance, &candidates, &statics, &init, &mut instances);
+        }
+
+        // relaxed reachability.
+        let mut reached: collections::HashSet<String> = problem
+            .init
+            .iter()
+            .filter(|a| fluents.contains(a.predicate.as_str()))
+            .map(|a| a.to_string())
+            .collect();
+        let relaxed: Vec<Relaxed> = instances
+            .iter()
+            .map(|inst| {
+                let pre = inst
+                    .action
+                    .precondition
+                    .iter()
+                    .filter(|l| l.positive && fluents.contains(l.atom.predicate.as_str()))
+                    .map(|l| inst.bind(&l.atom))
+                    .collect();
+                let mut add = Vec::new();
+                for effect in inst.action.effects.iter().filter(|e| e.literal.positive) {
+                    for inst in inst.quantify(effect, &objects, domain) {
+                        let cond = effect
+                            .condition
+                            .iter()
+                            .filter(|l| l.positive && fluents.contains(l.atom.predicate.as_str()))
+                            .map(|l| inst.bind(&l.atom))
+                            .collect();
+                        add.push((cond, inst.bind(&effect.literal.atom)));
+                    }
+                }
+                (pre, add)
+            })
+            .collect();
+        let mut applicable = vec![false; instances.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, (pre, add)) in relaxed.iter().enumerate() {
+                if !applicable[i] && pre.iter().all(|f| reached.contains(f)) {
+                    applicable[i] = true;
+                }
+                if !applicable[i] {
+                    continue;
+                }
+                for (cond, fact) in add {
+                    if cond.iter().all(|f| reached.contains(f)) && !reached.contains(fact) {
+                        reached.insert(fact.clone());
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        // facts - goals are kept even if they can't be reached.
+        let mut facts: Vec<String> = reached.into_iter().collect();
+        for literal in &problem.goal {
+            let name = literal.atom.to_string();
+            if !facts.contains(&name) {
+                facts.push(name);
+            }
+        }
+        facts.sort();
+        let index: collections::HashMap<&str, usize> = facts
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (f.as_str(), i))
+            .collect();
+
+        let mut operators = Vec::new();
+        for (inst, _) in instances.iter().zip(applicable).filter(|(_, a)| *a) {
+            if let Some(op) = operator(inst, &objects, &fluents, &init, &index, domain, problem)? {
+                operators.push(op);
+            }
+        }
+        let (pos, neg): (Vec<&Literal>, Vec<&Literal>) =
+            problem.goal.iter().partition(|l| l.positive);
+        let task = Task {
+            init: (0..facts.len())
+                .filter(|f| init.contains(&facts[*f]))
+                .collect(),
+            goal: pos
+                .iter()
+                .map(|l| index[l.atom.to_string().as_str()])
+                .collect(),
+            goal_neg: neg
+                .iter()
+                .map(|l| index[l.atom.to_string().as_str()])
+                .collect(),
+            facts,
+            operators,
+            states: cell::RefCell::new(util::Interner::new()),
+            mutexes: cell::OnceCell::new(),
+        };
+
+        // the artificial goal state only has the marker set.
+        let n_facts = task.facts.len();
+        task.intern(strips::FactSet::from_facts(n_facts, &[n_facts]));
+        task.intern(strips::FactSet::from_facts(n_facts, &task.init));
+        Ok(task)
+    }
+
+    fn fact_set(&self, state: usize) -> strips::FactSet {
+        self.states.borrow().get(state).clone()
+    }
+
+    fn intern(&self, facts: strips::FactSet) -> usize {
+        self.states.borrow_mut().intern(facts)
+    }
+
+    /// The initial state.
+    pub fn initial_state(&self) -> usize {
+        INIT
+    }
+
+    /// The artificial state reached from all states satisfying the goal.
+    pub fn goal_state(&self) -> usize {
+        GOAL
+    }
+
+    /// Check if the state satisfies the goal.
+    pub fn is_goal(&self, state: usize) -> bool {
+        state != GOAL && satisfied(&self.fact_set(state), &self.goal, &self.goal_neg)
+    }
+
+    /// Names of the facts holding in the state.
+    pub fn facts_of(&self, state: usize) -> Vec<&str> {
+        self.fact_set(state)
+            .iter()
+            .filter(|f| *f < self.facts.len())
+            .map(|f| self.facts[f].as_str())
+            .collect()
+    }
+
+    /// Apply the operator - `None` if its preconditions are not satisfied.
+    pub fn apply(&self, state: usize, op: usize) -> Option<usize> {
+        if state == GOAL {
+            return None;
+        }
+        self.successor(&self.fact_set(state), &self.operators[op])
+            .map(|facts| self.intern(facts))
+    }
+
+    /// Pairs of facts which can't hold together - ignoring negative preconditions.
+    fn mutexes(&self) -> &strips::Mutexes {
+        self.mutexes.get_or_init(|| {
+            let operators: Vec<strips::Operator> = self
+                .operators
+                .iter()
+                .map(|op| strips::Operator {
+                    name: op.name.clone(),
+                    pre: op.pre.clone(),
+                    add: op.add.clone(),
+                    del: op.del.clone(),
+                    conditional: op
+                        .conditional
+                        .iter()
+                        .map(|c| strips::Conditional {
+                            pre: c.pre.clone(),
+                            add: c.add.clone(),
+                            del: c.del.clone(),
+                        })
+                        .collect(),
+                    cost: op.cost,
+                })
+                .collect();
+            strips::Mutexes::new(self.facts.len(), &self.init, &operators)
+        })
+    }
+
+    /// Conditions of effects are evaluated before any effect gets applied; adds win over deletes.
+    fn successor(&self, facts: &strips::FactSet, op: &Operator) -> Option<strips::FactSet> {
+        if !satisfied(facts, &op.pre, &op.pre_neg) {
+            return None;
+        }
+        let triggered: Vec<&Conditional> = op
+            .conditional
+            .iter()
+            .filter(|c| satisfied(facts, &c.pre, &c.pre_neg))
+            .collect();
+        let mut res = facts.clone();
+        let dels = op.del.iter().chain(triggered.iter().flat_map(|c| &c.del));
+        for fact in dels {
+            res.remove(*fact);
+        }
+        let adds = op.add.iter().chain(triggered.iter().flat_map(|c| &c.add));
+        for fact in adds {
+            res.insert(*fact);
+        }
+        Some(res)
+    }
+}
+
+/// Assign objects to the parameters one by one - skipping those violating static preconditions.
+fn enumerate<'a>(
+    instance: &mut Instance<'a>,
+    candidates: &[Vec<&'a str>],
+    statics: &[&Literal],
+    init: &collections::HashSet<String>,
+    res: &mut Vec<Instance<'a>>,
+) {
+    let params = &instance.action.parameters[..instance.args.len()];
+    for literal in statics {
+        // only check literals once all their variables got bound.
+        let bound = literal
+            .atom
+            .args
+            .iter()
+            .all(|arg| !arg.starts_with('?') || params.iter().any(|p| p.0 == *arg));
+        if bound && holds_static(instance, literal, init) != literal.positive {
+            return;
+        }
+    }
+    if instance.args.len() == candidates.len() {
+        res.push(instance.clone());
+        return;
+    }
+    for object in &candidates[instance.args.len()] {
+        instance.args.push(object);
+        enumerate(instance, candidates, statics, init, res);
+        instance.args.pop();
+    }
+}
+
+/// Check if a static atom holds - equality or given in the initial state.
+fn holds_static(
+    instance: &Instance,
+    literal: &Literal,
+    init: &collections::HashSet<String>,
+) -> bool {
+    let atom = &literal.atom;
+    if atom.predicate == "=" {
+        return instance.arg(&atom.args[0]) == instance.arg(&atom.args[1]);
+    }
+    init.contains(&instance.bind(atom))
+}
+
+/// Index the facts of an instance - `None` if it can never be applied.
+fn operator(
+    inst: &Instance,
+    objects: &[(String, String)],
+    fluents: &collections::HashSet<&str>,
+    init: &collections::HashSet<String>,
+    index: &collections::HashMap<&str, usize>,
+    domain: &Domain,
+    problem: &Problem,
+) -> Result<Option<Operator>, Error> {
+    // splits literals into facts which need to hold or not - `None` if it can't be satisfied.
+    let split = |inst: &Instance, literals: &[Literal]| -> Option<(Vec<usize>, Vec<usize>)> {
+        let mut res = (vec![], vec![]);
+        for literal in literals {
+            if !fluents.contains(literal.atom.predicate.as_str()) {
+                if holds_static(inst, literal, init) != literal.positive {
+                    return None;
+                }
+                continue;
+            }
+            match (
+                index.get(inst.bind(&literal.atom).as_str()),
+                literal.positive,
+            ) {
+                (Some(fact), true) => res.0.push(*fact),
+                (Some(fact), false) => res.1.push(*fact),
+                // unreachable facts never hold.
+                (None, true) => return None,
+                (None, false) => {}
+            }
+        }
+        Some(res)
+    };
+    let (pre, pre_neg) = match split(inst, &inst.action.precondition) {
+        Some(res) => res,
+        None => return Ok(None),
+    };
+    let mut op = Operator {
+        name: Atom {
+            predicate: inst.action.name.clone(),
+            args: inst.args.iter().map(|a| a.to_string()).collect(),
+        }
+        .to_string(),
+        pre,
+        pre_neg,
+        add: vec![],
+        del: vec![],
+        conditional: vec![],
+        cost: 1.0,
+    };
+    for effect in &inst.action.effects {
+        for inst in inst.quantify(effect, objects, domain) {
+            let fact = match index.get(inst.bind(&effect.literal.atom).as_str()) {
+                Some(fact) => *fact,
+                // deleting an unreachable fact changes nothing.
+                None => continue,
+            };
+            let (pre, pre_neg) = match split(&inst, &effect.condition) {
+                Some(res) => res,
+                None => continue,
+            };
+            let (add, del) = if pre.is_empty() && pre_neg.is_empty() {
+                (&mut op.add, &mut op.del)
+            } else {
+                let pos = match op
+                    .conditional
+                    .iter()
+                    .position(|c| c.pre == pre && c.pre_neg == pre_neg)
+                {
+                    Some(pos) => pos,
+                    None => {
+                        op.conditional.push(Conditional {
+                            pre,
+                            pre_neg,
+                            add: vec![],
+                            del: vec![],
+                        });
+                        op.conditional.len() - 1
+                    }
+                };
+                let cond = &mut op.conditional[pos];
+                (&mut cond.add, &mut cond.del)
+            };
+            if effect.literal.positive {
+                add.push(fact);
+            } else {
+                del.push(fact);
+            }
+        }
+    }
+    if domain.requirements.iter().any(|r| r == ":action-costs") {
+        op.cost = 0.0;
+        for cost in &inst.action.cost {
+            op.cost += match cost {
+                Cost::Constant(value) => *value,
+                Cost::Function(function) => {
+                    let name = inst.bind(function);
+                    match problem.values.iter().find(|v| v.0.to_string() == name) {
+                        Some(value) => value.1,
+                        None => return Err(Error::Undefined(format!("function {}", name))),
+                    }
+                }
+            };
+        }
+    }
+    Ok(Some(op))
+}
+
+impl planner::ProblemSpace for Task {
+    type State = usize;
+    type Iter = vec::IntoIter<(usize, f64)>;
+
+    fn heuristic(&self, _: &Self::State, _: &Self::State) -> f64 {
+        0.0
+    }
+
+    fn succ(&self, state: &Self::State) -> Self::Iter {
+        let mut res = Vec::new();
+        if *state == GOAL {
+            return res.into_iter();
+        }
+        let facts = self.fact_set(*state);
+        for op in &self.operators {
+            if let Some(next) = self.successor(&facts, op) {
+                res.push((self.intern(next), op.cost));
+            }
+        }
+        if satisfied(&facts, &self.goal, &self.goal_neg) {
+            res.push((GOAL, 0.0));
+        }
+        res.into_iter()
+    }
+
+    fn pred(&self, state: &Self::State) -> Self::Iter {
+        let mut res = Vec::new();
+        let n_facts = self.facts.len();
+        if *state == GOAL {
+            // all completions of the goal.
+            let free: Vec<usize> = (0..n_facts)
+                .filter(|f| !self.goal.contains(f) && !self.goal_neg.contains(f))
+                .collect();
+            let goal = strips::FactSet::from_facts(n_facts, &self.goal);
+            for facts in self.mutexes().completions(&goal, &free) {
+                res.push((self.intern(facts), 0.0));
+            }
+            return res.into_iter();
+        }
+        // regression - predecessors only differ in the facts the operator touches.
+        let facts = self.fact_set(*state);
+        for op in &self.operators {
+            if !facts.contains_all(&op.add) {
+                continue;
+            }
+            let mut touched: Vec<usize> = op.add.iter().chain(&op.del).copied().collect();
+            for cond in &op.conditional {
+                touched.extend(cond.add.iter().chain(&cond.del));
+            }
+            touched.sort_unstable();
+            touched.dedup();
+            // preconditions the operator does not touch need to hold in the state already.
+            let untouched = |f: &&usize| !touched.contains(f);
+            if !satisfied(
+                &facts,
+                &op.pre
+                    .iter()
+                    .filter(untouched)
+                    .copied()
+                    .collect::<Vec<usize>>(),
+                &op.pre_neg
+                    .iter()
+                    .filter(untouched)
+                    .copied()
+                    .collect::<Vec<usize>>(),
+            ) {
+                continue;
+            }
+            let mut candidate = facts.clone();
+            for fact in &touched {
+                candidate.remove(*fact);
+            }
+            for fact in &op.pre {
+                candidate.insert(*fact);
+            }
+            let fixed = |f: &usize| op.pre.contains(f) || op.pre_neg.contains(f);
+            let free: Vec<usize> = touched.iter().filter(|f| !fixed(f)).copied().collect();
+            for candidate in self.mutexes().completions(&candidate, &free) {
+                if self.successor(&candidate, op).as_ref() == Some(&facts) {
+                    res.push((self.intern(candidate), op.cost));
+                }
+            }
+        }
+        res.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic;
+
+    use crate::any_dyn_astar;
+    use crate::cooperative;
+    use crate::pddl;
+    use crate::planner::ProblemSpace;
+
+    static PLAN_LENGTH: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+
+    const DOMAIN: &str = "
+        ; a van delivering a parcel.
+        (define (domain delivery)
+          (:requirements :strips :typing :negative-preconditions :conditional-effects
+                         :equality :action-costs)
+          (:types truck location - object package)
+          (:constants parcel - package)
+          (:predicates (at ?t - truck ?l - location) (in ?p - package ?l - location)
+                       (loaded ?p - package) (road ?a ?b - location))
+          (:functions (total-cost) - number (length ?a ?b - location) - number)
+          (:action drive
+            :parameters (?t - truck ?a ?b - location)
+            :precondition (and (at ?t ?a) (road ?a ?b) (not (= ?a ?b)))
+            :effect (and (not (at ?t ?a)) (at ?t ?b)
+                         (when (loaded parcel) (and (not (in parcel ?a)) (in parcel ?b)))
+                         (increase (total-cost) (length ?a ?b))))
+          (:action load
+            :parameters (?t - truck ?l - location)
+            :precondition (and (at ?t ?l) (in parcel ?l) (not (loaded parcel)))
+            :effect (and (loaded parcel) (increase (total-cost) 1)))
+          (:action unload
+            :parameters (?t - truck ?l - location)
+            :precondition (and (at ?t ?l) (loaded parcel))
+            :effect (and (not (loaded parcel)) (increase (total-cost) 1))))";
+
+    const PROBLEM: &str = "
+        (define (problem deliver-parcel)
+          (:domain delivery)
+          (:objects van - truck depot shop home - location)
+          (:init (at van depot) (in parcel shop)
+                 (road depot shop) (road shop depot) (road shop home) (road depot home)
+                 (= (length depot shop) 2) (= (length shop depot) 2)
+                 (= (length shop home) 3) (= (length depot home) 10)
+                 (= (total-cost) 0))
+          (:goal (and (in parcel home) (not (loaded parcel))))
+          (:metric minimize (total-cost)))";
+
+    const LINE: &str = "
+        ; cells in a row - walk to the end or get sent back to the start.
+        (define (domain line)
+          (:requirements :strips :typing :conditional-effects)
+          (:types cell)
+          (:constants start - cell)
+          (:predicates (at ?c - cell) (next ?a ?b - cell))
+          (:action walk
+            :parameters (?a ?b - cell)
+            :precondition (and (at ?a) (next ?a ?b))
+            :effect (and (not (at ?a)) (at ?b)))
+          (:action restart
+            :parameters ()
+            :effect (and (forall (?c - cell) (not (at ?c))) (at start))))";
+
+    /// Walk from the start along the given number of cells.
+    fn line(n: usize) -> pddl::Task {
+        let cells: Vec<String> = (1..=n).map(|i| format!("c{}", i)).collect();
+        let mut init = vec![
+            String::from("(at start)"),
+            format!("(next start {})", cells[0]),
+        ];
+        for pair in cells.windows(2) {
+            init.push(format!("(next {} {})", pair[0], pair[1]));
+        }
+        let problem = format!(
+            "(define (problem walk) (:domain line) (:objects {} - cell) (:init {}) (:goal (at {})))",
+            cells.join(" "),
+            init.join(" "),
+            cells[n - 1]
+        );
+        ground(LINE, &problem).unwrap()
+    }
+
+    fn task() -> pddl::Task {
+        let domain = pddl::Domain::parse(DOMAIN).unwrap();
+        let problem = pddl::Problem::parse(PROBLEM).unwrap();
+        pddl::Task::ground(&domain, &problem).unwrap()
+    }
+
+    /// Apply the operator with the given name.
+    fn apply(task: &pddl::Task, state: usize, name: &str) -> Option<usize> {
+        let op = task
+            .operators
+            .iter()
+            .position(|op| op.name == name)
+            .unwrap();
+        task.apply(state, op)
+    }
+
+    fn callback(plan: Vec<usize>) {
+        PLAN_LENGTH.store(plan.len(), atomic::Ordering::SeqCst);
+    }
+
+    fn ground(domain: &str, problem: &str) -> Result<pddl::Task, pddl::Error> {
+        let domain = pddl::Domain::parse(domain)?;
+        pddl::Task::ground(&domain, &pddl::Problem::parse(problem)?)
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_parse_for_success() {
+        pddl::Domain::parse(DOMAIN).unwrap();
+        pddl::Problem::parse(PROBLEM).unwrap();
+    }
+
+    #[test]
+    fn test_ground_for_success() {
+        task();
+    }
+
+    #[test]
+    fn test_task_for_success() {
+        let task = task();
+        let init = task.initial_state();
+        task.succ(&init);
+        task.pred(&init);
+        task.heuristic(&init, &task.goal_state());
+        task.facts_of(init);
+        task.is_goal(init);
+        task.apply(init, 0);
+    }
+
+    // Test for failure.
+
+    #[test]
+    fn test_parse_for_failure() {
+        let errors = vec![
+            (
+                "(define (domain d)",
+                pddl::Error::Syntax(String::from("missing ')'")),
+            ),
+            (
+                "(define (domain d)))",
+                pddl::Error::Syntax(String::from("unexpected ')'")),
+            ),
+            (
+                "(define (problem d))",
+                pddl::Error::Syntax(String::from("expected (domain <name>)")),
+            ),
+            (
+                "(define (domain d) (:requirements :fluents))",
+                pddl::Error::Unsupported(String::from(":fluents")),
+            ),
+            (
+                "(define (domain d) (:types a - (either b c)))",
+                pddl::Error::Unsupported(String::from("either")),
+            ),
+            (
+                "(define (domain d) (:predicates (p ?x)) (:action a :parameters (?x)
+                  :precondition (or (p ?x) (p ?x)) :effect (p ?x)))",
+                pddl::Error::Unsupported(String::from("or")),
+            ),
+            (
+                "(define (domain d) (:predicates (p ?x)) (:action a :parameters (?x)
+                  :effect (forall (?y) (increase (total-cost) 1))))",
+                pddl::Error::Unsupported(String::from("quantified costs")),
+            ),
+            (
+                "(define (domain d) (:predicates (p ?x)) (:action a :parameters (?x)
+                  :effect (forall (?y) (p ?z))))",
+                pddl::Error::Undefined(String::from("variable ?z in (p ?z)")),
+            ),
+            (
+                "(define (domain d) (:predicates (p ?x)) (:action a :parameters (?x)
+                  :effect (q ?x)))",
+                pddl::Error::Undefined(String::from("predicate q")),
+            ),
+            (
+                "(define (domain d) (:predicates (p ?x)) (:action a :parameters (?x)
+                  :effect (p ?y)))",
+                pddl::Error::Undefined(String::from("variable ?y in (p ?y)")),
+            ),
+            (
+                "(define (domain d) (:predicates (p ?x)) (:action a :parameters (?x)
+                  :effect (p ?x ?x)))",
+                pddl::Error::Syntax(String::from("(p ?x ?x) expects 1 arguments")),
+            ),
+        ];
+        for (text, error) in errors {
+            assert_eq!(pddl::Domain::parse(text), Err(error));
+        }
+        assert_eq!(
+            pddl::Problem::parse("(define (problem p) (:init (= (f) x)))"),
+            Err(pddl::Error::Syntax(String::from(
+                "expected a number, found x"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_ground_for_failure() {
+        let problem = PROBLEM.replace("(:domain delivery)", "(:domain other)");
+        assert_eq!(
+            ground(DOMAIN, &problem).err(),
+            Some(pddl::Error::Undefined(String::from("domain other")))
+        );
+        let problem = PROBLEM.replace("(in parcel shop)", "(in box shop)");
+        assert_eq!(
+            ground(DOMAIN, &problem).err(),
+            Some(pddl::Error::Undefined(String::from(
+                "object box in (in box shop)"
+            )))
+        );
+        let problem = PROBLEM.replace("home - location", "home - place");
+        assert_eq!(
+            ground(DOMAIN, &problem).err(),
+            Some(pddl::Error::Undefined(String::from("type place of depot")))
+        );
+        let problem = PROBLEM.replace("(= (length shop home) 3)", "");
+        assert_eq!(
+            ground(DOMAIN, &problem).err(),
+            Some(pddl::Error::Undefined(String::from(
+                "function (length shop home)"
+            )))
+        );
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_parse_for_sanity() {
+        let domain = pddl::Domain::parse(DOMAIN).unwrap();
+        assert_eq!(domain.name, "delivery");
+        assert_eq!(domain.requirements.len(), 7);
+        assert_eq!(
+            domain.types,
+            vec![
+                (String::from("truck"), String::from("object")),
+                (String::from("location"), String::from("object")),
+                (String::from("package"), String::from("object")),
+            ]
+        );
+        assert_eq!(domain.constants[0].0, "parcel");
+        assert_eq!(domain.predicates[3].1, vec!["location", "location"]);
+
+        let drive = &domain.actions[0];
+        assert_eq!(drive.parameters.len(), 3);
+        assert_eq!(drive.precondition.len(), 3);
+        assert!(!drive.precondition[2].positive);
+        assert_eq!(drive.precondition[2].atom.to_string(), "(= ?a ?b)");
+        // two unconditional & two conditional effects.
+        assert_eq!(drive.effects.len(), 4);
+        assert!(drive.effects[..2].iter().all(|e| e.condition.is_empty()));
+        assert_eq!(
+            drive.effects[2].condition[0].atom.to_string(),
+            "(loaded parcel)"
+        );
+        assert_eq!(drive.effects[3].literal.atom.to_string(), "(in parcel ?b)");
+        assert!(matches!(&drive.cost[0], pddl::Cost::Function(f) if f.predicate == "length"));
+        assert!(drive.effects.iter().all(|e| e.variables.is_empty()));
+        assert_eq!(domain.actions[1].cost, vec![pddl::Cost::Constant(1.0)]);
+
+        let problem = pddl::Problem::parse(PROBLEM).unwrap();
+        assert_eq!(
+            (problem.name.as_str(), problem.domain.as_str()),
+            ("deliver-parcel", "delivery")
+        );
+        assert_eq!(problem.objects.len(), 4);
+        assert_eq!(problem.init.len(), 6);
+        assert_eq!(problem.values.len(), 5);
+        assert_eq!(problem.goal.len(), 2);
+        assert!(!problem.goal[1].positive);
+
+        // case & comments do not matter.
+        let upper =
+            pddl::Problem::parse(&PROBLEM.to_uppercase().replace("(:GOAL", "; foo\n(:GOAL"));
+        assert_eq!(upper, Ok(problem));
+    }
+
+    #[test]
+    fn test_ground_for_sanity() {
+        let task = task();
+        // static roads got compiled away.
+        assert_eq!(
+            task.facts,
+            vec![
+                "(at van depot)",
+                "(at van home)",
+                "(at van shop)",
+                "(in parcel depot)",
+                "(in parcel home)",
+                "(in parcel shop)",
+                "(loaded parcel)",
+            ]
+        );
+        assert_eq!(task.init, vec![0, 5]);
+        assert_eq!(
+            (task.goal.clone(), task.goal_neg.clone()),
+            (vec![4], vec![6])
+        );
+
+        // 4 roads, loading & unloading at all 3 locations.
+        assert_eq!(task.operators.len(), 10);
+        assert_eq!(task.operators[0].name, "(drive van depot shop)");
+        let drive = task
+            .operators
+            .iter()
+            .find(|op| op.name == "(drive van depot home)")
+            .unwrap();
+        assert_eq!(
+            (drive.pre.clone(), drive.add.clone(), drive.del.clone()),
+            (vec![0], vec![1], vec![0])
+        );
+        assert_eq!(
+            drive.conditional,
+            vec![pddl::Conditional {
+                pre: vec![6],
+                pre_neg: vec![],
+                add: vec![4],
+                del: vec![3],
+            }]
+        );
+        assert_eq!(drive.cost, 10.0);
+        let load = task
+            .operators
+            .iter()
+            .find(|op| op.name == "(load van shop)")
+            .unwrap();
+        assert_eq!((load.pre_neg.clone(), load.cost), (vec![6], 1.0));
+
+        // w/o action costs all actions cost the same.
+        let domain = DOMAIN.replace(":action-costs", "");
+        let task = ground(&domain, PROBLEM).unwrap();
+        assert!(task.operators.iter().all(|op| op.cost == 1.0));
+
+        // unreachable goals are kept.
+        let problem = PROBLEM.replace("(road shop home) (road depot home)", "");
+        let task = ground(DOMAIN, &problem).unwrap();
+        assert!(task.facts.contains(&String::from("(in parcel home)")));
+        assert!(!task.facts.contains(&String::from("(at van home)")));
+        assert_eq!(task.operators.len(), 6);
+
+        // forall effects apply to all objects of the type - incl. constants.
+        let task = line(3);
+        let restart = task
+            .operators
+            .iter()
+            .find(|op| op.name == "(restart)")
+            .unwrap();
+        assert_eq!(
+            (restart.add.clone(), restart.del.clone()),
+            (vec![3], vec![3, 0, 1, 2])
+        );
+        let domain = pddl::Domain::parse(LINE).unwrap();
+        let effects = &domain.actions[1].effects;
+        assert_eq!(
+            effects[0].variables,
+            vec![(String::from("?c"), String::from("cell"))]
+        );
+        assert!(effects[1].variables.is_empty());
+    }
+
+    #[test]
+    fn test_task_for_sanity() {
+        let task = task();
+        let init = task.initial_state();
+        assert_eq!(
+            task.facts_of(init),
+            vec!["(at van depot)", "(in parcel shop)"]
+        );
+
+        // negative preconditions.
+        let s_1 = apply(&task, init, "(drive van depot shop)").unwrap();
+        let s_2 = apply(&task, s_1, "(load van shop)").unwrap();
+        assert_eq!(apply(&task, s_2, "(load van shop)"), None);
+
+        // conditional effects move the parcel along.
+        let s_3 = apply(&task, s_2, "(drive van shop home)").unwrap();
+        assert_eq!(
+            task.facts_of(s_3),
+            vec!["(at van home)", "(in parcel home)", "(loaded parcel)"]
+        );
+        assert!(!task.is_goal(s_3));
+        let s_4 = apply(&task, s_3, "(unload van home)").unwrap();
+        assert!(task.is_goal(s_4));
+
+        // successors incl. the artificial goal state.
+        assert_eq!(
+            task.succ(&init).collect::<Vec<_>>(),
+            vec![
+                (s_1, 2.0),
+                (apply(&task, init, "(drive van depot home)").unwrap(), 10.0)
+            ]
+        );
+        assert!(task.succ(&s_4).any(|s| s == (task.goal_state(), 0.0)));
+        assert_eq!(task.succ(&task.goal_state()).count(), 0);
+
+        // regression - incl. through conditional effects.
+        assert!(task.pred(&s_1).any(|s| s == (init, 2.0)));
+        assert!(task.pred(&s_3).any(|s| s == (s_2, 3.0)));
+        assert!(task.pred(&task.goal_state()).any(|s| s == (s_4, 0.0)));
+        assert!(task
+            .pred(&init)
+            .all(|(s, _)| task.succ(&s).any(|n| n.0 == init)));
+
+        // cheapest plan - drive to the shop, load, drive home & unload.
+        let mut dist = cooperative::TrueDistance::new(task.goal_state());
+        assert_eq!(dist.distance(&task, &init), 7.0);
+        assert_eq!(dist.distance(&task, &s_3), 1.0);
+
+        // backward search - 4 actions & the artificial goal state.
+        any_dyn_astar::solve(&task, init, task.goal_state(), callback);
+        assert_eq!(PLAN_LENGTH.load(atomic::Ordering::SeqCst), 5);
+
+        // more than 64 facts - mutually exclusive facts keep the regression small.
+        let task = line(80);
+        assert_eq!(task.facts.len(), 81);
+        let goals: Vec<(usize, f64)> = task.pred(&task.goal_state()).collect();
+        assert_eq!(goals.len(), 1);
+        assert!(task.is_goal(goals[0].0));
+        assert_eq!(task.pred(&goals[0].0).count(), 1);
+        // restarting from any of the cells - incl. the start & being nowhere, which mutually
+        // exclusive pairs of facts can't rule out.
+        assert_eq!(task.pred(&task.initial_state()).count(), 82);
+        let mut dist = cooperative::TrueDistance::new(task.goal_state());
+        assert_eq!(dist.distance(&task, &task.initial_state()), 80.0);
+        any_dyn_astar::solve(&task, task.initial_state(), task.goal_state(), callback);
+        assert_eq!(PLAN_LENGTH.load(atomic::Ordering::SeqCst), 81);
+    }
+}
@@ -1,7 +1,8 @@
 use std::hash;
 
+use crate::protocol;
+
 // TODO: check usage of &str + lifetime vs String.
-// TODO: check if we can have a generic PDDL parser --> ProblemSpace.
 
 ///
 /// Public trait which - once implemented - describes the problem space to solve.
@@ -51,13 +52,61 @@ pub trait SharedStates: ProblemSpace {
     fn deserialize(&self, _: String) -> (u8, Self::State, Vec<f64>);
 }
 
+///
+/// Trait for multi-agent/system planning algorithms exchanging typed messages. Implemented for
+/// all `SharedStates` - for states implementing `protocol::Codec` the encodings of
+/// `protocol::Message` can be used directly instead.
+///
+pub trait SharedMessages: ProblemSpace {
+    /// Determine if a given state is shared with others - i.e. a public state.
+    fn is_shared(&self, _: &Self::State) -> bool;
+    /// Encode a message into a string.
+    fn encode(&self, _: &protocol::Message<Self::State>) -> String;
+    /// Decode a string into a message.
+    fn decode(&self, _: &str) -> Result<protocol::Message<Self::State>, protocol::DecodeError>;
+    /// Projection of a state which gets shared with others - private facts of this agent should
+    /// be hidden, e.g. using a `protocol::Obfuscator`. Must only touch this agent's facts.
+    /// Defaults to sharing the full state.
+    fn project(&self, state: &Self::State) -> Self::State {
+        *state
+    }
+    /// Restore a state received from others into the local view - resolves the hidden facts of
+    /// this agent. Defaults to the state as is.
+    fn restore(&self, state: &Self::State) -> Self::State {
+        *state
+    }
+    /// Heuristic estimate shared alongside a state - must not depend on private facts, e.g. the
+    /// heuristic of the public projection. Defaults to `None`, sharing the local estimate.
+    fn public_heuristic(&self, _: &Self::State, _: &Self::State) -> Option<f64> {
+        None
+    }
+}
+
+impl<T: SharedStates> SharedMessages for T {
+    fn is_shared(&self, state: &Self::State) -> bool {
+        self.is_public(state)
+    }
+
+    fn encode(&self, msg: &protocol::Message<Self::State>) -> String {
+        let (msg_type, state, para) = msg.to_legacy();
+        self.serialize(msg_type, &state, para)
+    }
+
+    fn decode(&self, msg: &str) -> Result<protocol::Message<Self::State>, protocol::DecodeError> {
+        let (msg_type, state, para) = self.deserialize(msg.to_string());
+        protocol::Message::from_legacy(msg_type, state, para)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::vec;
 
     use crate::planner::Lifelong;
     use crate::planner::ProblemSpace;
+    use crate::planner::SharedMessages;
     use crate::planner::SharedStates;
+    use crate::protocol;
 
     struct Environment {}
 
@@ -110,4 +159,31 @@ mod tests {
         env.serialize(0, &0, vec![]);
         env.deserialize(String::from("0"));
     }
+
+    #[test]
+    fn test_shared_messages_for_success() {
+        let env = Environment {};
+        env.is_shared(&0);
+        env.encode(&protocol::Message::Traceback { state: 0, depth: 0 });
+        env.decode("0").unwrap_err();
+        env.project(&0);
+        env.restore(&0);
+        env.public_heuristic(&0, &1);
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_shared_messages_for_sanity() {
+        let env = Environment {};
+
+        // adapter lacks the g & h values.
+        assert_eq!(env.decode("0"), Err(protocol::DecodeError::Truncated));
+        assert_eq!(env.encode(&protocol::Message::Terminate { goal: 1 }), "0");
+
+        // states are shared in full by default.
+        assert_eq!(env.project(&2), 2);
+        assert_eq!(env.restore(&2), 2);
+        assert_eq!(env.public_heuristic(&2, &1), None);
+    }
 }
@@ -0,0 +1,1050 @@
+use std::collections;
+use std::error;
+use std::fmt;
+use std::hash;
+use std::hash::Hasher;
+use std::sync;
+
+///
+/// Version of the encodings - bumped whenever the layout of a message changes.
+///
+pub const VERSION: u8 = 2;
+
+// Message kinds - for compatibility these match the message types of the legacy format.
+const KIND_STATE: u8 = 0;
+const KIND_TRACEBACK: u8 = 1;
+const KIND_TOKEN_WHITE: u8 = 2;
+const KIND_TOKEN_BLACK: u8 = 3;
+const KIND_TERMINATE: u8 = 4;
+const KIND_PROVED: u8 = 5;
+const KIND_GOAL: u8 = 6;
+const KIND_STEP: u8 = 7;
+const KIND_COMPLETE: u8 = 8;
+
+///
+/// Reasons a message can not be decoded.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodeError {
+    /// Message ended before all fields were read.
+    Truncated,
+    /// Message got encoded with an unsupported version.
+    Version(u8),
+    /// Unknown kind of message.
+    Kind(String),
+    /// A field could not be parsed.
+    Malformed(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "message is truncated"),
+            DecodeError::Version(v) => {
+                write!(f, "unsupported version {} (expected {})", v, VERSION)
+            }
+            DecodeError::Kind(k) => write!(f, "unknown kind of message '{}'", k),
+            DecodeError::Malformed(field) => write!(f, "malformed field '{}'", field),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+///
+/// Trait for states which can be encoded into bytes & text.
+///
+pub trait Codec: Sized {
+    /// Append the binary representation to the buffer.
+    fn write(&self, buf: &mut Vec<u8>);
+    /// Read from the front of the buffer - advancing it.
+    fn read(buf: &mut &[u8]) -> Result<Self, DecodeError>;
+    /// Human-readable representation.
+    fn to_text(&self) -> String;
+    /// Parse the human-readable representation.
+    fn from_text(text: &str) -> Result<Self, DecodeError>;
+}
+
+/// Take n bytes from the front of the buffer.
+fn take<'a>(buf: &mut &'a [u8], n: usize) -> Result<&'a [u8], DecodeError> {
+    if buf.len() < n {
+        return Err(DecodeError::Truncated);
+    }
+    let (head, tail) = buf.split_at(n);
+    *buf = tail;
+    Ok(head)
+}
+
+macro_rules! impl_codec {
+    ($($t:ty),*) => {
+        $(
+            impl Codec for $t {
+                fn write(&self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+                    let bytes = take(buf, std::mem::size_of::<$t>())?;
+                    let mut arr = [0; std::mem::size_of::<$t>()];
+                    arr.copy_from_slice(bytes);
+                    Ok(<$t>::from_le_bytes(arr))
+                }
+
+                fn to_text(&self) -> String {
+                    self.to_string()
+                }
+
+                fn from_text(text: &str) -> Result<Self, DecodeError> {
+                    text.trim()
+                        .parse()
+                        .map_err(|_| DecodeError::Malformed(text.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+impl_codec!(i8, i16, i32, i64, u8, u16, u32, u64, f64);
+
+impl Codec for usize {
+    fn write(&self, buf: &mut Vec<u8>) {
+        (*self as u64).write(buf)
+    }
+
+    fn read(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(u64::read(buf)? as usize)
+    }
+
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_text(text: &str) -> Result<Self, DecodeError> {
+        text.trim()
+            .parse()
+            .map_err(|_| DecodeError::Malformed(text.to_string()))
+    }
+}
+
+impl Codec for bool {
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8)
+    }
+
+    fn read(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        match take(buf, 1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(DecodeError::Malformed(b.to_string())),
+        }
+    }
+
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_text(text: &str) -> Result<Self, DecodeError> {
+        text.trim()
+            .parse()
+            .map_err(|_| DecodeError::Malformed(text.to_string()))
+    }
+}
+
+/// Split the text between the brackets at the top-level commas.
+fn split_nested(text: &str, open: char, close: char) -> Result<Vec<&str>, DecodeError> {
+    let inner = text
+        .trim()
+        .strip_prefix(open)
+        .and_then(|t| t.strip_suffix(close))
+        .ok_or_else(|| DecodeError::Malformed(text.to_string()))?;
+    let mut res = Vec::new();
+    let mut depth = 0;
+    let mut begin = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                res.push(&inner[begin..i]);
+                begin = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !inner.trim().is_empty() {
+        res.push(&inner[begin..]);
+    }
+    Ok(res)
+}
+
+macro_rules! impl_codec_tuple {
+    ($n:expr; $($t:ident $i:tt),*) => {
+        impl<$($t: Codec),*> Codec for ($($t,)*) {
+            fn write(&self, buf: &mut Vec<u8>) {
+                $(self.$i.write(buf);)*
+            }
+
+            fn read(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+                Ok(($($t::read(buf)?,)*))
+            }
+
+            fn to_text(&self) -> String {
+                let items: Vec<String> = vec![$(self.$i.to_text()),*];
+                format!("({})", items.join(","))
+            }
+
+            fn from_text(text: &str) -> Result<Self, DecodeError> {
+                let items = split_nested(text, '(', ')')?;
+                if items.len() != $n {
+                    return Err(DecodeError::Malformed(text.to_string()));
+                }
+                Ok(($($t::from_text(items[$i])?,)*))
+            }
+        }
+    };
+}
+
+impl_codec_tuple!(2; A 0, B 1);
+impl_codec_tuple!(3; A 0, B 1, C 2);
+impl_codec_tuple!(4; A 0, B 1, C 2, D 3);
+
+impl<T: Codec + Copy + Default, const N: usize> Codec for [T; N] {
+    fn write(&self, buf: &mut Vec<u8>) {
+        for item in self {
+            item.write(buf);
+        }
+    }
+
+    fn read(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let mut res = [T::default(); N];
+        for item in res.iter_mut() {
+            *item = T::read(buf)?;
+        }
+        Ok(res)
+    }
+
+    fn to_text(&self) -> String {
+        let items: Vec<String> = self.iter().map(|item| item.to_text()).collect();
+        format!("[{}]", items.join(","))
+    }
+
+    fn from_text(text: &str) -> Result<Self, DecodeError> {
+        let items = split_nested(text, '[', ']')?;
+        if items.len() != N {
+            return Err(DecodeError::Malformed(text.to_string()));
+        }
+        let mut res = [T::default(); N];
+        for (item, txt) in res.iter_mut().zip(items) {
+            *item = T::from_text(txt)?;
+        }
+        Ok(res)
+    }
+}
+
+///
+/// Messages exchanged by the agents of a distributed search.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message<S> {
+    /// Announcement of a (public) state incl. its g & h values.
+    State { state: S, g: f64, h: f64 },
+    /// Announcement of a goal state incl. its g & h values.
+    Goal { state: S, g: f64, h: f64 },
+    /// Request to continue the traceback from the given state - `depth` steps are already known.
+    Traceback { state: S, depth: usize },
+    /// Step of the plan taken from the given state by the agent - `depth` steps before the goal.
+    Step {
+        state: S,
+        agent: usize,
+        depth: usize,
+    },
+    /// Traceback reached the start - the plan for the goal has the given number of steps.
+    Complete { goal: S, length: usize },
+    /// Token of the termination detection.
+    Token {
+        goal: S,
+        target: usize,
+        sum: i64,
+        black: bool,
+        best: f64,
+        owner: usize,
+    },
+    /// No plan exists for the given goal.
+    Terminate { goal: S },
+    /// Goal has been proven to be reached with minimal cost by the owner.
+    Proved { goal: S, cost: f64, owner: usize },
+}
+
+/// Returns the para or an error if there are not enough of them.
+fn para_at(para: &[f64], i: usize) -> Result<f64, DecodeError> {
+    para.get(i).copied().ok_or(DecodeError::Truncated)
+}
+
+/// Returns the para as a count - an error if it is not a whole number within the range of `usize`.
+fn count_at(para: &[f64], i: usize) -> Result<usize, DecodeError> {
+    let value = para_at(para, i)?;
+    if value.fract() != 0.0 || value < 0.0 || value >= usize::MAX as f64 {
+        return Err(DecodeError::Malformed(value.to_string()));
+    }
+    Ok(value as usize)
+}
+
+/// Returns the para as an integer - an error if it is not a whole number within the range of `i64`.
+fn integer_at(para: &[f64], i: usize) -> Result<i64, DecodeError> {
+    let value = para_at(para, i)?;
+    if value.fract() != 0.0 || value < i64::MIN as f64 || value >= i64::MAX as f64 {
+        return Err(DecodeError::Malformed(value.to_string()));
+    }
+    Ok(value as i64)
+}
+
+/// Parse a field of the human-readable representation.
+fn field<T: std::str::FromStr>(fields: &[&str], i: usize) -> Result<T, DecodeError> {
+    let text = fields.get(i).ok_or(DecodeError::Truncated)?;
+    text.parse()
+        .map_err(|_| DecodeError::Malformed(text.to_string()))
+}
+
+impl<S: Copy> Message<S> {
+    ///
+    /// Convert into the message type, state & parameters of `planner::SharedStates`. Goals are
+    /// announced like any other state there.
+    ///
+    pub fn to_legacy(&self) -> (u8, S, Vec<f64>) {
+        match *self {
+            Message::State { state, g, h } | Message::Goal { state, g, h } => {
+                (KIND_STATE, state, vec![g, h])
+            }
+            Message::Traceback { state, depth } => (KIND_TRACEBACK, state, vec![depth as f64]),
+            Message::Step {
+                state,
+                agent,
+                depth,
+            } => (KIND_STEP, state, vec![agent as f64, depth as f64]),
+            Message::Complete { goal, length } => (KIND_COMPLETE, goal, vec![length as f64]),
+            Message::Token {
+                goal,
+                target,
+                sum,
+                black,
+                best,
+                owner,
+            } => {
+                let kind = if black {
+                    KIND_TOKEN_BLACK
+                } else {
+                    KIND_TOKEN_WHITE
+                };
+                (
+                    kind,
+                    goal,
+                    vec![target as f64, sum as f64, best, owner as f64],
+                )
+            }
+            Message::Terminate { goal } => (KIND_TERMINATE, goal, vec![]),
+            Message::Proved { goal, cost, owner } => (KIND_PROVED, goal, vec![cost, owner as f64]),
+        }
+    }
+
+    ///
+    /// Convert from the message type, state & parameters of `planner::SharedStates`.
+    ///
+    pub fn from_legacy(msg_type: u8, state: S, para: Vec<f64>) -> Result<Self, DecodeError> {
+        match msg_type {
+            KIND_STATE => Ok(Message::State {
+                state,
+                g: para_at(&para, 0)?,
+                h: para_at(&para, 1)?,
+            }),
+            // depth defaults to 0 for compatibility.
+            KIND_TRACEBACK => Ok(Message::Traceback {
+                state,
+                depth: if para.is_empty() {
+                    0
+                } else {
+                    count_at(&para, 0)?
+                },
+            }),
+            KIND_STEP => Ok(Message::Step {
+                state,
+                agent: count_at(&para, 0)?,
+                depth: count_at(&para, 1)?,
+            }),
+            KIND_COMPLETE => Ok(Message::Complete {
+                goal: state,
+                length: count_at(&para, 0)?,
+            }),
+            KIND_TOKEN_WHITE | KIND_TOKEN_BLACK => Ok(Message::Token {
+                goal: state,
+                target: count_at(&para, 0)?,
+                sum: integer_at(&para, 1)?,
+                black: msg_type == KIND_TOKEN_BLACK,
+                best: para_at(&para, 2)?,
+                owner: count_at(&para, 3)?,
+            }),
+            KIND_TERMINATE => Ok(Message::Terminate { goal: state }),
+            KIND_PROVED => Ok(Message::Proved {
+                goal: state,
+                cost: para_at(&para, 0)?,
+                owner: count_at(&para, 1)?,
+            }),
+            _ => Err(DecodeError::Kind(msg_type.to_string())),
+        }
+    }
+}
+
+impl<S: Codec + Copy> Message<S> {
+    ///
+    /// Compact binary representation: version, kind & the fields in little endian.
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![VERSION];
+        match self {
+            Message::State { state, g, h } | Message::Goal { state, g, h } => {
+                let kind = match self {
+                    Message::Goal { .. } => KIND_GOAL,
+                    _ => KIND_STATE,
+                };
+                buf.push(kind);
+                g.write(&mut buf);
+                h.write(&mut buf);
+                state.write(&mut buf);
+            }
+            Message::Traceback { state, depth } => {
+                buf.push(KIND_TRACEBACK);
+                depth.write(&mut buf);
+                state.write(&mut buf);
+            }
+            Message::Step {
+                state,
+                agent,
+                depth,
+            } => {
+                buf.push(KIND_STEP);
+                agent.write(&mut buf);
+                depth.write(&mut buf);
+                state.write(&mut buf);
+            }
+            Message::Complete { goal, length } => {
+                buf.push(KIND_COMPLETE);
+                length.write(&mut buf);
+                goal.write(&mut buf);
+            }
+            Message::Token {
+                goal,
+                target,
+                sum,
+                black,
+                best,
+                owner,
+            } => {
+                buf.push(if *black {
+                    KIND_TOKEN_BLACK
+                } else {
+                    KIND_TOKEN_WHITE
+                });
+                target.write(&mut buf);
+                sum.write(&mut buf);
+                best.write(&mut buf);
+                owner.write(&mut buf);
+                goal.write(&mut buf);
+            }
+            Message::Terminate { goal } => {
+                buf.push(KIND_TERMINATE);
+                goal.write(&mut buf);
+            }
+            Message::Proved { goal, cost, owner } => {
+                buf.push(KIND_PROVED);
+                cost.write(&mut buf);
+                owner.write(&mut buf);
+                goal.write(&mut buf);
+            }
+        }
+        buf
+    }
+
+    ///
+    /// Decode the compact binary representation.
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = bytes;
+        let version = u8::read(&mut buf)?;
+        if version != VERSION {
+            return Err(DecodeError::Version(version));
+        }
+        let kind = u8::read(&mut buf)?;
+        let msg = match kind {
+            KIND_STATE | KIND_GOAL => {
+                let g = f64::read(&mut buf)?;
+                let h = f64::read(&mut buf)?;
+                let state = S::read(&mut buf)?;
+                if kind == KIND_GOAL {
+                    Message::Goal { state, g, h }
+                } else {
+                    Message::State { state, g, h }
+                }
+            }
+            KIND_TRACEBACK => Message::Traceback {
+                depth: usize::read(&mut buf)?,
+                state: S::read(&mut buf)?,
+            },
+            KIND_STEP => Message::Step {
+                agent: usize::read(&mut buf)?,
+                depth: usize::read(&mut buf)?,
+                state: S::read(&mut buf)?,
+            },
+            KIND_COMPLETE => Message::Complete {
+                length: usize::read(&mut buf)?,
+                goal: S::read(&mut buf)?,
+            },
+            KIND_TOKEN_WHITE | KIND_TOKEN_BLACK => Message::Token {
+                target: usize::read(&mut buf)?,
+                sum: i64::read(&mut buf)?,
+                best: f64::read(&mut buf)?,
+                owner: usize::read(&mut buf)?,
+                goal: S::read(&mut buf)?,
+                black: kind == KIND_TOKEN_BLACK,
+            },
+            KIND_TERMINATE => Message::Terminate {
+                goal: S::read(&mut buf)?,
+            },
+            KIND_PROVED => Message::Proved {
+                cost: f64::read(&mut buf)?,
+                owner: usize::read(&mut buf)?,
+                goal: S::read(&mut buf)?,
+            },
+            _ => return Err(DecodeError::Kind(kind.to_string())),
+        };
+        if !buf.is_empty() {
+            return Err(DecodeError::Malformed(format!(
+                "{} trailing bytes",
+                buf.len()
+            )));
+        }
+        Ok(msg)
+    }
+
+    ///
+    /// Human-readable representation: version, kind & the fields separated by ';' - the state
+    /// comes last, e.g. `2;state;2.5;1;(1,2)`.
+    ///
+    pub fn to_text(&self) -> String {
+        let fields: Vec<String> = match self {
+            Message::State { state, g, h } => {
+                vec![
+                    "state".into(),
+                    g.to_string(),
+                    h.to_string(),
+                    state.to_text(),
+                ]
+            }
+            Message::Goal { state, g, h } => {
+                vec!["goal".into(), g.to_string(), h.to_string(), state.to_text()]
+            }
+            Message::Traceback { state, depth } => {
+                vec!["traceback".into(), depth.to_string(), state.to_text()]
+            }
+            Message::Step {
+                state,
+                agent,
+                depth,
+            } => vec![
+                "step".into(),
+                agent.to_string(),
+                depth.to_string(),
+                state.to_text(),
+            ],
+            Message::Complete { goal, length } => {
+                vec!["complete".into(), length.to_string(), goal.to_text()]
+            }
+            Message::Token {
+                goal,
+                target,
+                sum,
+                black,
+                best,
+                owner,
+            } => vec![
+                "token".into(),
+                target.to_string(),
+                sum.to_string(),
+                if *black { "black" } else { "white" }.into(),
+                best.to_string(),
+                owner.to_string(),
+                goal.to_text(),
+            ],
+            Message::Terminate { goal } => vec!["terminate".into(), goal.to_text()],
+            Message::Proved { goal, cost, owner } => vec![
+                "proved".into(),
+                cost.to_string(),
+                owner.to_string(),
+                goal.to_text(),
+            ],
+        };
+        format!("{};{}", VERSION, fields.join(";"))
+    }
+
+    ///
+    /// Parse the human-readable representation.
+    ///
+    pub fn from_text(text: &str) -> Result<Self, DecodeError> {
+        let (version, rest) = text.split_once(';').ok_or(DecodeError::Truncated)?;
+        let version: u8 = version
+            .parse()
+            .map_err(|_| DecodeError::Malformed(version.to_string()))?;
+        if version != VERSION {
+            return Err(DecodeError::Version(version));
+        }
+        let (kind, rest) = rest.split_once(';').unwrap_or((rest, ""));
+        let n_fields = match kind {
+            "state" | "goal" => 2,
+            "token" => 5,
+            "proved" | "step" => 2,
+            "traceback" | "complete" => 1,
+            "terminate" => 0,
+            _ => return Err(DecodeError::Kind(kind.to_string())),
+        };
+        // state comes last & may contain ';' itself.
+        let fields: Vec<&str> = rest.splitn(n_fields + 1, ';').collect();
+        if fields.len() != n_fields + 1 {
+            return Err(DecodeError::Truncated);
+        }
+        let state = S::from_text(fields[n_fields])?;
+        Ok(match kind {
+            "state" => Message::State {
+                state,
+                g: field(&fields, 0)?,
+                h: field(&fields, 1)?,
+            },
+            "goal" => Message::Goal {
+                state,
+                g: field(&fields, 0)?,
+                h: field(&fields, 1)?,
+            },
+            "token" => Message::Token {
+                goal: state,
+                target: field(&fields, 0)?,
+                sum: field(&fields, 1)?,
+                black: match fields[2] {
+                    "black" => true,
+                    "white" => false,
+                    other => return Err(DecodeError::Malformed(other.to_string())),
+                },
+                best: field(&fields, 3)?,
+                owner: field(&fields, 4)?,
+            },
+            "proved" => Message::Proved {
+                goal: state,
+                cost: field(&fields, 0)?,
+                owner: field(&fields, 1)?,
+            },
+            "traceback" => Message::Traceback {
+                state,
+                depth: field(&fields, 0)?,
+            },
+            "step" => Message::Step {
+                state,
+                agent: field(&fields, 0)?,
+                depth: field(&fields, 1)?,
+            },
+            "complete" => Message::Complete {
+                goal: state,
+                length: field(&fields, 0)?,
+            },
+            _ => Message::Terminate { goal: state },
+        })
+    }
+}
+
+/// Prefix of a batch of messages.
+const BATCH: &str = "batch;";
+
+///
+/// Combine encoded messages into a single one - each message is prefixed by its length, so
+/// messages may contain anything. A single message is passed on as is.
+///
+pub fn batch(msgs: &[String]) -> String {
+    if msgs.len() == 1 {
+        return msgs[0].clone();
+    }
+    let mut res = String::from(BATCH);
+    for msg in msgs {
+        res.push_str(&msg.len().to_string());
+        res.push(';');
+        res.push_str(msg);
+    }
+    res
+}
+
+///
+/// Split a batch into the encoded messages - anything which is not a batch is a single message.
+///
+pub fn unbatch(msg: &str) -> Result<Vec<String>, DecodeError> {
+    let mut rest = match msg.strip_prefix(BATCH) {
+        Some(rest) => rest,
+        None => return Ok(vec![msg.to_string()]),
+    };
+    let mut res = Vec::new();
+    while !rest.is_empty() {
+        let (len, tail) = rest.split_once(';').ok_or(DecodeError::Truncated)?;
+        let len: usize = len
+            .parse()
+            .map_err(|_| DecodeError::Malformed(len.to_string()))?;
+        if tail.len() < len {
+            return Err(DecodeError::Truncated);
+        }
+        let item = tail
+            .get(..len)
+            .ok_or_else(|| DecodeError::Malformed(tail.to_string()))?;
+        res.push(item.to_string());
+        rest = &tail[len..];
+    }
+    Ok(res)
+}
+
+///
+/// Replaces private facts by opaque handles before states get shared - only the agent holding the
+/// obfuscator can resolve them again. The same private facts always map to the same handle, so
+/// states coming back from other agents match the local ones.
+///
+pub struct Obfuscator<P> {
+    key: u64,
+    handles: sync::Mutex<collections::HashMap<u64, P>>,
+}
+
+impl<P: Copy + Eq + hash::Hash> Obfuscator<P> {
+    ///
+    /// Create a new obfuscator - the key should be kept secret by the agent.
+    ///
+    pub fn new(key: u64) -> Obfuscator<P> {
+        Obfuscator {
+            key,
+            handles: sync::Mutex::new(collections::HashMap::new()),
+        }
+    }
+
+    ///
+    /// Opaque handle for the given private facts.
+    ///
+    pub fn hide(&self, private: &P) -> u64 {
+        let mut hasher = collections::hash_map::DefaultHasher::new();
+        hasher.write_u64(self.key);
+        private.hash(&mut hasher);
+        let handle = hasher.finish();
+        self.handles.lock().unwrap().insert(handle, *private);
+        handle
+    }
+
+    ///
+    /// Resolve a handle - `None` if it was not created by this obfuscator.
+    ///
+    pub fn reveal(&self, handle: u64) -> Option<P> {
+        self.handles.lock().unwrap().get(&handle).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol;
+    use crate::protocol::Codec;
+
+    type Msg = protocol::Message<(i32, i32)>;
+
+    fn messages() -> Vec<Msg> {
+        vec![
+            protocol::Message::State {
+                state: (1, -2),
+                g: 2.5,
+                h: 1.0,
+            },
+            protocol::Message::Goal {
+                state: (3, 4),
+                g: 7.0,
+                h: 0.0,
+            },
+            protocol::Message::Traceback {
+                state: (0, 0),
+                depth: 3,
+            },
+            protocol::Message::Step {
+                state: (0, 1),
+                agent: 1,
+                depth: 2,
+            },
+            protocol::Message::Complete {
+                goal: (3, 4),
+                length: 5,
+            },
+            protocol::Message::Token {
+                goal: (3, 4),
+                target: 2,
+                sum: -3,
+                black: true,
+                best: f64::INFINITY,
+                owner: 0,
+            },
+            protocol::Message::Terminate { goal: (3, 4) },
+            protocol::Message::Proved {
+                goal: (3, 4),
+                cost: 7.0,
+                owner: 1,
+            },
+        ]
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_codec_for_success() {
+        let mut buf = vec![];
+        (1u8, [2i64, 3], true).write(&mut buf);
+        (1u8, [2i64, 3], true).to_text();
+        <(u8, [i64; 2], bool)>::read(&mut buf.as_slice()).unwrap();
+        <(u8, [i64; 2], bool)>::from_text("(1,[2,3],true)").unwrap();
+    }
+
+    #[test]
+    fn test_batch_for_success() {
+        let msgs: Vec<String> = messages().iter().map(|msg| msg.to_text()).collect();
+        protocol::unbatch(&protocol::batch(&msgs)).unwrap();
+    }
+
+    #[test]
+    fn test_obfuscator_for_success() {
+        let obfuscator = protocol::Obfuscator::new(42);
+        let handle = obfuscator.hide(&(1, 2));
+        obfuscator.reveal(handle).unwrap();
+    }
+
+    #[test]
+    fn test_bytes_for_success() {
+        for msg in messages() {
+            Msg::from_bytes(&msg.to_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_text_for_success() {
+        for msg in messages() {
+            Msg::from_text(&msg.to_text()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_legacy_for_success() {
+        for msg in messages() {
+            let (msg_type, state, para) = msg.to_legacy();
+            Msg::from_legacy(msg_type, state, para).unwrap();
+        }
+    }
+
+    // Test for failure.
+
+    #[test]
+    fn test_bytes_for_failure() {
+        let bytes = messages()[0].to_bytes();
+
+        // empty, truncated & too long.
+        assert_eq!(Msg::from_bytes(&[]), Err(protocol::DecodeError::Truncated));
+        assert_eq!(
+            Msg::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(protocol::DecodeError::Truncated)
+        );
+        let mut long = bytes.clone();
+        long.push(0);
+        assert!(matches!(
+            Msg::from_bytes(&long),
+            Err(protocol::DecodeError::Malformed(_))
+        ));
+
+        // wrong version & kind.
+        let mut other = bytes.clone();
+        other[0] = 1;
+        assert_eq!(
+            Msg::from_bytes(&other),
+            Err(protocol::DecodeError::Version(1))
+        );
+        let mut other = bytes;
+        other[1] = 42;
+        assert_eq!(
+            Msg::from_bytes(&other),
+            Err(protocol::DecodeError::Kind("42".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_text_for_failure() {
+        assert_eq!(Msg::from_text(""), Err(protocol::DecodeError::Truncated));
+        assert_eq!(
+            Msg::from_text("1;state;1;1;(1,2)"),
+            Err(protocol::DecodeError::Version(1))
+        );
+        assert_eq!(
+            Msg::from_text("2;foo;(1,2)"),
+            Err(protocol::DecodeError::Kind("foo".to_string()))
+        );
+        assert_eq!(
+            Msg::from_text("2;state;1;(1,2)"),
+            Err(protocol::DecodeError::Truncated)
+        );
+        assert_eq!(
+            Msg::from_text("2;state;x;1;(1,2)"),
+            Err(protocol::DecodeError::Malformed("x".to_string()))
+        );
+        assert_eq!(
+            Msg::from_text("2;state;1;1;(1,2,3)"),
+            Err(protocol::DecodeError::Malformed("(1,2,3)".to_string()))
+        );
+        assert_eq!(
+            Msg::from_text("2;token;0;0;grey;1;0;(1,2)"),
+            Err(protocol::DecodeError::Malformed("grey".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_legacy_for_failure() {
+        // missing g & h values.
+        assert_eq!(
+            Msg::from_legacy(0, (1, 2), vec![1.0]),
+            Err(protocol::DecodeError::Truncated)
+        );
+        assert_eq!(
+            Msg::from_legacy(9, (1, 2), vec![]),
+            Err(protocol::DecodeError::Kind("9".to_string()))
+        );
+
+        // counts & sums need to be whole numbers - counts can't be negative.
+        assert_eq!(
+            Msg::from_legacy(1, (1, 2), vec![-1.0]),
+            Err(protocol::DecodeError::Malformed("-1".to_string()))
+        );
+        assert_eq!(
+            Msg::from_legacy(2, (1, 2), vec![0.5, 1.0]),
+            Err(protocol::DecodeError::Malformed("0.5".to_string()))
+        );
+        assert_eq!(
+            Msg::from_legacy(8, (1, 2), vec![f64::INFINITY]),
+            Err(protocol::DecodeError::Malformed("inf".to_string()))
+        );
+        assert_eq!(
+            Msg::from_legacy(2, (1, 2), vec![0.0, f64::NAN, 1.0, 0.0]),
+            Err(protocol::DecodeError::Malformed("NaN".to_string()))
+        );
+        assert_eq!(
+            Msg::from_legacy(3, (1, 2), vec![0.0, 1e20, 1.0, 0.0]),
+            Err(protocol::DecodeError::Malformed(
+                "100000000000000000000".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_batch_for_failure() {
+        assert_eq!(
+            protocol::unbatch("batch;5;foo"),
+            Err(protocol::DecodeError::Truncated)
+        );
+        assert_eq!(
+            protocol::unbatch("batch;3"),
+            Err(protocol::DecodeError::Truncated)
+        );
+        assert_eq!(
+            protocol::unbatch("batch;x;foo"),
+            Err(protocol::DecodeError::Malformed("x".to_string()))
+        );
+        // length ends within a character.
+        assert_eq!(
+            protocol::unbatch("batch;1;ä"),
+            Err(protocol::DecodeError::Malformed("ä".to_string()))
+        );
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_codec_for_sanity() {
+        let state = ((1i32, -2i64), [3u8, 4, 5], 6usize);
+        let mut buf = vec![];
+        state.write(&mut buf);
+        assert_eq!(buf.len(), 4 + 8 + 3 + 8);
+        assert_eq!(Codec::read(&mut buf.as_slice()), Ok(state));
+        assert_eq!(state.to_text(), "((1,-2),[3,4,5],6)");
+        assert_eq!(Codec::from_text(" ((1, -2), [3,4,5], 6) "), Ok(state));
+    }
+
+    #[test]
+    fn test_bytes_for_sanity() {
+        for msg in messages() {
+            assert_eq!(Msg::from_bytes(&msg.to_bytes()), Ok(msg));
+        }
+        // version, kind, g, h & state.
+        assert_eq!(messages()[0].to_bytes().len(), 1 + 1 + 8 + 8 + 4 + 4);
+    }
+
+    #[test]
+    fn test_text_for_sanity() {
+        for msg in messages() {
+            assert_eq!(Msg::from_text(&msg.to_text()), Ok(msg));
+        }
+        assert_eq!(messages()[0].to_text(), "2;state;2.5;1;(1,-2)");
+        assert_eq!(messages()[3].to_text(), "2;step;1;2;(0,1)");
+        assert_eq!(messages()[5].to_text(), "2;token;2;-3;black;inf;0;(3,4)");
+        assert_eq!(messages()[6].to_text(), "2;terminate;(3,4)");
+    }
+
+    #[test]
+    fn test_batch_for_sanity() {
+        let msgs: Vec<String> = messages().iter().map(|msg| msg.to_text()).collect();
+        assert_eq!(protocol::unbatch(&protocol::batch(&msgs)), Ok(msgs));
+
+        // single messages are not wrapped.
+        let single = vec![String::from("0;1;2")];
+        assert_eq!(protocol::batch(&single), "0;1;2");
+        assert_eq!(protocol::unbatch("0;1;2"), Ok(single));
+
+        // messages may contain the separators.
+        let msgs = vec![String::from("a;3;b"), String::new(), String::from("batch;")];
+        let batch = protocol::batch(&msgs);
+        assert_eq!(batch, "batch;5;a;3;b0;6;batch;");
+        assert_eq!(protocol::unbatch(&batch), Ok(msgs));
+    }
+
+    #[test]
+    fn test_obfuscator_for_sanity() {
+        let obfuscator = protocol::Obfuscator::new(42);
+        let handle = obfuscator.hide(&7u8);
+        assert_eq!(obfuscator.hide(&7u8), handle);
+        assert_ne!(obfuscator.hide(&8u8), handle);
+        assert_eq!(obfuscator.reveal(handle), Some(7));
+
+        // others can neither resolve nor reproduce the handles.
+        let other = protocol::Obfuscator::new(43);
+        assert_eq!(other.reveal(handle), None);
+        assert_ne!(other.hide(&7u8), handle);
+    }
+
+    #[test]
+    fn test_legacy_for_sanity() {
+        for msg in messages() {
+            let (msg_type, state, para) = msg.to_legacy();
+            let res = Msg::from_legacy(msg_type, state, para).unwrap();
+            match msg {
+                // goals are announced as plain states.
+                protocol::Message::Goal { state, g, h } => {
+                    assert_eq!(res, protocol::Message::State { state, g, h })
+                }
+                _ => assert_eq!(res, msg),
+            }
+        }
+        assert_eq!(messages()[0].to_legacy(), (0, (1, -2), vec![2.5, 1.0]));
+
+        // traceback requests w/o depth are accepted.
+        assert_eq!(
+            Msg::from_legacy(1, (1, 2), vec![]),
+            Ok(protocol::Message::Traceback {
+                state: (1, 2),
+                depth: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,870 @@
+use std::cmp;
+use std::collections;
+use std::vec;
+
+use crate::iterative_repair;
+use crate::util;
+
+///
+/// A task which needs to be scheduled.
+///
+pub struct Task {
+    /// Time the task takes.
+    pub duration: i64,
+    /// Earliest possible start time.
+    pub release: i64,
+    /// Time by which the task needs to be done.
+    pub deadline: i64,
+    /// Resources (id & amount) used while the task runs.
+    pub demands: Vec<(usize, u32)>,
+}
+
+///
+/// A resource with a limited capacity - e.g. a machine or a runway.
+///
+pub struct Resource {
+    /// Amount available at any point in time.
+    pub capacity: u32,
+}
+
+/// Usage of a resource over time.
+#[derive(Default)]
+struct Profile {
+    /// Usage from the given time on - until the next time given.
+    usage: collections::BTreeMap<i64, u32>,
+    /// Start times of the tasks using the resource.
+    starts: collections::BTreeSet<(i64, usize)>,
+}
+
+impl Profile {
+    /// Usage at the given time.
+    fn at(&self, time: i64) -> u32 {
+        self.usage.range(..=time).next_back().map_or(0, |(_, u)| *u)
+    }
+
+    /// Add (or remove) the demand of a task running from start until end.
+    fn change(&mut self, start: i64, end: i64, demand: u32, add: bool) {
+        for time in [start, end] {
+            let usage = self.at(time);
+            self.usage.entry(time).or_insert(usage);
+        }
+        for (_, usage) in self.usage.range_mut(start..end) {
+            if add {
+                *usage += demand;
+            } else {
+                *usage -= demand;
+            }
+        }
+    }
+}
+
+/// Precedences & resource usage by task - kept up to date while tasks get moved.
+#[derive(Default)]
+struct Index {
+    /// Precedences (by position) in which the task comes 2nd.
+    preds: Vec<Vec<usize>>,
+    /// Precedences (by position) in which the task comes 1st.
+    succs: Vec<Vec<usize>>,
+    /// Usage profile of each resource.
+    profiles: Vec<Profile>,
+    /// Start times the profiles are based on.
+    starts: Vec<i64>,
+    /// Number of precedences indexed.
+    n_precedences: usize,
+}
+
+/// Conflicts found in a schedule.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Conflict {
+    /// Task does not run within its time window.
+    Window(usize),
+    /// Task (2nd) starts before its predecessor (1st) has finished.
+    Precedence(usize, usize),
+    /// Resource is overloaded at the given time - task is the one to move. Tasks without a duration
+    /// never use a resource.
+    Capacity(usize, i64, usize),
+}
+
+///
+/// A schedule made up of tasks, resources and precedence constraints - the start times of the
+/// tasks get repaired using the iterative repair algorithm.
+///
+/// # Example
+/// ```
+/// use rusty_planner::iterative_repair;
+/// use rusty_planner::scheduling;
+///
+/// let mut schedule = scheduling::Schedule::new(10);
+/// let machine = schedule.add_resource(1);
+/// let t_0 = schedule.add_task(scheduling::Task {
+///     duration: 2,
+///     release: 0,
+///     deadline: 10,
+///     demands: vec![(machine, 1)],
+/// });
+/// let t_1 = schedule.add_task(scheduling::Task {
+///     duration: 3,
+///     release: 0,
+///     deadline: 10,
+///     demands: vec![(machine, 1)],
+/// });
+/// schedule.add_precedence(t_1, t_0);
+///
+/// let res = iterative_repair::solve(&mut schedule, 10);
+/// assert!(res.0);
+/// assert_eq!(schedule.starts, vec![3, 0]);
+/// ```
+///
+pub struct Schedule {
+    /// Tasks to schedule - referred to by their index.
+    tasks: Vec<Task>,
+    /// Resources used by the tasks - referred to by their index.
+    resources: Vec<Resource>,
+    /// Pairs of tasks - first one needs to finish before the second one starts.
+    precedences: Vec<(usize, usize)>,
+    /// Start times of the tasks.
+    pub starts: Vec<i64>,
+    /// Tasks are placed within [0, horizon).
+    pub horizon: i64,
+    index: Index,
+    /// Number of conflicts checked while tracking the changes made by repairs.
+    #[cfg(test)]
+    evaluations: usize,
+}
+
+impl Schedule {
+    /// Create a new, empty schedule.
+    pub fn new(horizon: i64) -> Schedule {
+        Schedule {
+            tasks: Vec::new(),
+            resources: Vec::new(),
+            precedences: Vec::new(),
+            starts: Vec::new(),
+            horizon,
+            index: Index::default(),
+            #[cfg(test)]
+            evaluations: 0,
+        }
+    }
+
+    /// Add a resource with the given capacity - returns its id.
+    pub fn add_resource(&mut self, capacity: u32) -> usize {
+        self.resources.push(Resource { capacity });
+        self.resources.len() - 1
+    }
+
+    /// Add a task - it initially starts at its release time; returns its id.
+    pub fn add_task(&mut self, task: Task) -> usize {
+        self.starts.push(task.release);
+        self.tasks.push(task);
+        self.tasks.len() - 1
+    }
+
+    /// Task before needs to be done before task after can start.
+    pub fn add_precedence(&mut self, before: usize, after: usize) {
+        self.precedences.push((before, after));
+    }
+
+    /// Tasks to schedule - referred to by their index.
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    /// Change a task - the index gets rebuilt.
+    pub fn task_mut(&mut self, task: usize) -> &mut Task {
+        self.index = Index::default();
+        &mut self.tasks[task]
+    }
+
+    /// Resources used by the tasks - referred to by their index.
+    pub fn resources(&self) -> &[Resource] {
+        &self.resources
+    }
+
+    /// Change a resource - the index gets rebuilt.
+    pub fn resource_mut(&mut self, resource: usize) -> &mut Resource {
+        self.index = Index::default();
+        &mut self.resources[resource]
+    }
+
+    /// Pairs of tasks - first one needs to finish before the second one starts.
+    pub fn precedences(&self) -> &[(usize, usize)] {
+        &self.precedences
+    }
+
+    /// Returns the time the given task is done.
+    pub fn end(&self, task: usize) -> i64 {
+        self.starts[task] + self.tasks[task].duration
+    }
+
+    /// Returns the time all tasks are done.
+    pub fn makespan(&self) -> i64 {
+        (0..self.tasks.len())
+            .map(|i| self.end(i))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Latest possible start time of a task.
+    fn latest_start(&self, task: usize) -> i64 {
+        cmp::min(self.tasks[task].deadline, self.horizon) - self.tasks[task].duration
+    }
+
+    /// Amount of a resource the given task uses.
+    fn demand(&self, task: usize, resource: usize) -> u32 {
+        self.tasks[task]
+            .demands
+            .iter()
+            .filter(|d| d.0 == resource)
+            .map(|d| d.1)
+            .sum()
+    }
+
+    /// Find all times at which a resource is overloaded.
+    fn capacity_conflicts(&self, resource: usize, res: &mut Vec<(Conflict, f64)>) {
+        let users: Vec<usize> = (0..self.tasks.len())
+            .filter(|i| self.tasks[*i].duration > 0 && self.demand(*i, resource) > 0)
+            .collect();
+        let mut moved: Vec<usize> = Vec::new();
+        for i in users.iter() {
+            // usage can only increase when a task starts...
+            let time = self.starts[*i];
+            let active: Vec<usize> = users
+                .iter()
+                .filter(|j| self.starts[**j] <= time && time < self.end(**j))
+                .copied()
+                .collect();
+            let usage: u32 = active.iter().map(|j| self.demand(*j, resource)).sum();
+            if usage > self.resources[resource].capacity {
+                // ...move the one which started last.
+                let task = *active
+                    .iter()
+                    .max_by_key(|j| (self.starts[**j], **j))
+                    .unwrap();
+                if !moved.contains(&task) {
+                    moved.push(task);
+                    res.push((Conflict::Capacity(resource, time, task), 2.0));
+                }
+            }
+        }
+    }
+
+    /// Candidate start times for a task - its release time, latest start & right before or after
+    /// other tasks.
+    fn start_times(&self, task: usize) -> Vec<i64> {
+        let earliest = cmp::max(self.tasks[task].release, 0);
+        let latest = self.latest_start(task);
+        let mut res = vec![earliest];
+        for i in 0..self.tasks.len() {
+            if i == task {
+                continue;
+            }
+            for time in [self.end(i), self.starts[i] - self.tasks[task].duration] {
+                if time > earliest && time <= latest && !res.contains(&time) {
+                    res.push(time);
+                }
+            }
+        }
+        if latest > earliest && !res.contains(&latest) {
+            res.push(latest);
+        }
+        res
+    }
+
+    /// Bring the index up to date - it gets rebuilt once tasks, resources or precedences were
+    /// added or changed; tasks moved since get shifted in the profiles.
+    fn sync(&mut self) {
+        if self.index.starts.len() != self.tasks.len()
+            || self.index.profiles.len() != self.resources.len()
+            || self.index.n_precedences != self.precedences.len()
+        {
+            let mut index = Index {
+                preds: vec![Vec::new(); self.tasks.len()],
+                succs: vec![Vec::new(); self.tasks.len()],
+                profiles: self.resources.iter().map(|_| Profile::default()).collect(),
+                starts: self.starts.clone(),
+                n_precedences: self.precedences.len(),
+            };
+            for (i, (before, after)) in self.precedences.iter().enumerate() {
+                index.succs[*before].push(i);
+                index.preds[*after].push(i);
+            }
+            self.index = index;
+            for task in 0..self.tasks.len() {
+                self.place(task, true);
+            }
+        }
+        for task in 0..self.tasks.len() {
+            if self.index.starts[task] != self.starts[task] {
+                self.shift(task);
+            }
+        }
+    }
+
+    /// Add (or remove) a task to the profiles of the resources it uses - at its indexed start.
+    fn place(&mut self, task: usize, add: bool) {
+        let start = self.index.starts[task];
+        let duration = self.tasks[task].duration;
+        if duration <= 0 {
+            return;
+        }
+        for (resource, demand) in &self.tasks[task].demands {
+            if let Some(profile) = self.index.profiles.get_mut(*resource) {
+                profile.change(start, start + duration, *demand, add);
+                if add {
+                    profile.starts.insert((start, task));
+                } else {
+                    profile.starts.remove(&(start, task));
+                }
+            }
+        }
+    }
+
+    /// Move a task in the profiles to its current start time.
+    fn shift(&mut self, task: usize) {
+        self.place(task, false);
+        self.index.starts[task] = self.starts[task];
+        self.place(task, true);
+    }
+
+    /// Conflicts which can change when the given task is moved - its window, its precedences & the
+    /// capacity of the resources it uses while running from any of the given start times. Needs
+    /// an up to date index.
+    fn local_conflicts(&mut self, task: usize, starts: &[i64]) -> Vec<(Conflict, f64)> {
+        let mut res = Vec::new();
+        #[cfg(test)]
+        {
+            self.evaluations += 1;
+        }
+        if self.starts[task] < cmp::max(self.tasks[task].release, 0)
+            || self.starts[task] > self.latest_start(task)
+        {
+            res.push((Conflict::Window(task), 0.0));
+        }
+        let mut precedences: Vec<usize> = self.index.preds[task]
+            .iter()
+            .chain(&self.index.succs[task])
+            .copied()
+            .collect();
+        precedences.sort_unstable();
+        precedences.dedup();
+        for i in precedences {
+            #[cfg(test)]
+            {
+                self.evaluations += 1;
+            }
+            let (before, after) = self.precedences[i];
+            if self.starts[after] < self.end(before) {
+                res.push((Conflict::Precedence(before, after), 1.0));
+            }
+        }
+        let duration = self.tasks[task].duration;
+        for resource in 0..self.resources.len() {
+            if duration <= 0 || self.demand(task, resource) == 0 {
+                continue;
+            }
+            // usage only increases when a task starts - check those within the intervals.
+            let profile = &self.index.profiles[resource];
+            let mut times: Vec<i64> = starts
+                .iter()
+                .flat_map(|start| {
+                    profile
+                        .starts
+                        .range((*start, 0)..(start + duration, 0))
+                        .map(|(time, _)| *time)
+                })
+                .collect();
+            times.sort_unstable();
+            times.dedup();
+            for time in times {
+                #[cfg(test)]
+                {
+                    self.evaluations += 1;
+                }
+                if profile.at(time) > self.resources[resource].capacity {
+                    // ...the task which started last gets moved.
+                    let (_, last) = profile
+                        .starts
+                        .range((time, 0)..=(time, usize::MAX))
+                        .next_back()
+                        .unwrap();
+                    res.push((Conflict::Capacity(resource, time, *last), 2.0));
+                }
+            }
+        }
+        res
+    }
+
+    /// Possible moves (task & new start time) to resolve a conflict.
+    fn moves(&self, conflict: &Conflict) -> Vec<(usize, i64)> {
+        let tasks: Vec<usize> = match *conflict {
+            Conflict::Window(task) => vec![task],
+            Conflict::Precedence(before, after) => vec![after, before],
+            Conflict::Capacity(resource, time, _) => (0..self.tasks.len())
+                .filter(|j| {
+                    self.demand(*j, resource) > 0 && self.starts[*j] <= time && time < self.end(*j)
+                })
+                .collect(),
+        };
+        let mut res = Vec::new();
+        for task in tasks {
+            for time in self.start_times(task) {
+                if time != self.starts[task] {
+                    res.push((task, time));
+                }
+            }
+        }
+        res
+    }
+}
+
+impl iterative_repair::Problem for Schedule {
+    type Conflict = Conflict;
+    type Iter = vec::IntoIter<(Self::Conflict, f64)>;
+
+    fn find_conflicts(&self) -> Self::Iter {
+        let mut res = Vec::new();
+        for i in 0..self.tasks.len() {
+            if self.starts[i] < cmp::max(self.tasks[i].release, 0)
+                || self.starts[i] > self.latest_start(i)
+            {
+                res.push((Conflict::Window(i), 0.0));
+            }
+        }
+        for (before, after) in self.precedences.iter() {
+            if self.starts[*after] < self.end(*before) {
+                res.push((Conflict::Precedence(*before, *after), 1.0));
+            }
+        }
+        for resource in 0..self.resources.len() {
+            self.capacity_conflicts(resource, &mut res);
+        }
+        res.into_iter()
+    }
+
+    fn fix_conflict(&mut self, conflict: &Self::Conflict) {
+        match *conflict {
+            Conflict::Window(task) => {
+                let earliest = cmp::max(self.tasks[task].release, 0);
+                self.starts[task] = cmp::max(
+                    cmp::min(self.starts[task], self.latest_start(task)),
+                    earliest,
+                );
+            }
+            Conflict::Precedence(before, after) => {
+                self.starts[after] = self.end(before);
+            }
+            Conflict::Capacity(resource, time, task) => {
+                // move after the first other task which is done.
+                let next = (0..self.tasks.len())
+                    .filter(|j| {
+                        *j != task
+                            && self.demand(*j, resource) > 0
+                            && self.starts[*j] <= time
+                            && time < self.end(*j)
+                    })
+                    .map(|j| self.end(j))
+                    .min();
+                if let Some(next) = next {
+                    self.starts[task] = next;
+                }
+            }
+        }
+    }
+
+    fn fix_conflict_delta(
+        &mut self,
+        conflict: &Self::Conflict,
+    ) -> Option<iterative_repair::Delta<Self::Conflict>> {
+        let task = match *conflict {
+            Conflict::Window(task) => task,
+            Conflict::Precedence(_, after) => after,
+            Conflict::Capacity(_, _, task) => task,
+        };
+        self.sync();
+        let old = self.starts[task];
+        self.fix_conflict(conflict);
+        let new = self.starts[task];
+
+        // conflicts around the old & the new interval of the task - before & after the move.
+        self.starts[task] = old;
+        let before = self.local_conflicts(task, &[old, new]);
+        self.starts[task] = new;
+        self.shift(task);
+        let after = self.local_conflicts(task, &[old, new]);
+        Some(iterative_repair::Delta {
+            added: after
+                .iter()
+                .filter(|(c, _)| !before.iter().any(|(b, _)| b == c))
+                .copied()
+                .collect(),
+            removed: before
+                .into_iter()
+                .filter(|(c, _)| !after.iter().any(|(a, _)| a == c))
+                .map(|(c, _)| c)
+                .collect(),
+        })
+    }
+}
+
+impl iterative_repair::Repairable for Schedule {
+    type Value = (usize, i64);
+    type Assignment = Vec<i64>;
+
+    fn values(&self, conflict: &Self::Conflict) -> Vec<Self::Value> {
+        self.moves(conflict)
+    }
+
+    fn assign(&mut self, _: &Self::Conflict, value: &Self::Value) {
+        self.starts[value.0] = value.1;
+    }
+
+    fn assignment(&self) -> Self::Assignment {
+        self.starts.clone()
+    }
+
+    fn restore(&mut self, assignment: &Self::Assignment) {
+        self.starts = assignment.clone();
+    }
+
+    fn randomize(&mut self, seed: u64) {
+        let mut rng = util::Rng::new(seed);
+        for i in 0..self.tasks.len() {
+            let earliest = cmp::max(self.tasks[i].release, 0);
+            let span = cmp::max(self.latest_start(i) - earliest, 0) as usize + 1;
+            self.starts[i] = earliest + rng.gen_range(span) as i64;
+        }
+    }
+}
+
+impl iterative_repair::Evaluate for Schedule {
+    type Repair = Option<(usize, i64)>;
+
+    /// Number of conflicts - with the makespan as tie-breaker for compact schedules.
+    fn cost(&self) -> f64 {
+        let n_conflicts = iterative_repair::Problem::find_conflicts(self).count();
+        n_conflicts as f64 + self.makespan() as f64 / (self.horizon.abs() + 1) as f64
+    }
+
+    fn propose_repair(&mut self, conflict: &Self::Conflict, sample: f64) -> Self::Repair {
+        let moves = self.moves(conflict);
+        if moves.is_empty() {
+            return None;
+        }
+        let (task, time) = moves[(sample * moves.len() as f64) as usize];
+        let old = self.starts[task];
+        self.starts[task] = time;
+        Some((task, old))
+    }
+
+    fn undo(&mut self, repair: Self::Repair) {
+        if let Some((task, old)) = repair {
+            self.starts[task] = old;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::iterative_repair;
+    use crate::iterative_repair::Problem;
+    use crate::scheduling;
+
+    /// Two machines, three jobs with two operations each - operations of a job are ordered.
+    fn job_shop() -> scheduling::Schedule {
+        let mut schedule = scheduling::Schedule::new(20);
+        let m_0 = schedule.add_resource(1);
+        let m_1 = schedule.add_resource(1);
+        for (d_0, d_1) in [(3, 2), (2, 4), (4, 1)] {
+            let op_0 = schedule.add_task(task(d_0, 0, 20, m_0));
+            let op_1 = schedule.add_task(task(d_1, 0, 20, m_1));
+            schedule.add_precedence(op_0, op_1);
+        }
+        schedule
+    }
+
+    fn task(duration: i64, release: i64, deadline: i64, resource: usize) -> scheduling::Task {
+        scheduling::Task {
+            duration,
+            release,
+            deadline,
+            demands: vec![(resource, 1)],
+        }
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_add_for_success() {
+        let mut schedule = scheduling::Schedule::new(10);
+        let res = schedule.add_resource(1);
+        let t_0 = schedule.add_task(task(1, 0, 10, res));
+        let t_1 = schedule.add_task(task(1, 0, 10, res));
+        schedule.add_precedence(t_0, t_1);
+    }
+
+    #[test]
+    fn test_makespan_for_success() {
+        let schedule = job_shop();
+        schedule.makespan();
+    }
+
+    #[test]
+    fn test_find_conflicts_for_success() {
+        let schedule = job_shop();
+        schedule.find_conflicts();
+    }
+
+    #[test]
+    fn test_fix_conflict_for_success() {
+        let mut schedule = job_shop();
+        schedule.fix_conflict(&scheduling::Conflict::Precedence(0, 1));
+    }
+
+    #[test]
+    fn test_fix_conflict_delta_for_success() {
+        let mut schedule = job_shop();
+        schedule.fix_conflict_delta(&scheduling::Conflict::Precedence(0, 1));
+    }
+
+    #[test]
+    fn test_moves_for_success() {
+        let schedule = job_shop();
+        schedule.moves(&scheduling::Conflict::Capacity(0, 0, 2));
+    }
+
+    #[test]
+    fn test_solve_for_success() {
+        let mut schedule = job_shop();
+        iterative_repair::solve(&mut schedule, 50);
+    }
+
+    // Test for failure.
+
+    #[test]
+    fn test_solve_for_failure() {
+        // two tasks need the single machine at the same time - cannot be fit in the horizon.
+        let mut schedule = scheduling::Schedule::new(5);
+        let res = schedule.add_resource(1);
+        schedule.add_task(task(3, 0, 5, res));
+        schedule.add_task(task(3, 0, 5, res));
+        let res = iterative_repair::solve(&mut schedule, 20);
+        assert!(!res.0);
+
+        let config = iterative_repair::Config {
+            strategy: iterative_repair::Strategy::MinConflicts,
+            steps: 10,
+            restarts: 2,
+            ..Default::default()
+        };
+        let res = iterative_repair::solve_with_config(&mut schedule, &config);
+        assert!(!res.found);
+        assert_eq!(res.conflicts, 1);
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_add_for_sanity() {
+        let mut schedule = scheduling::Schedule::new(10);
+        assert_eq!(schedule.add_resource(1), 0);
+        assert_eq!(schedule.add_resource(2), 1);
+        assert_eq!(schedule.add_task(task(1, 3, 10, 0)), 0);
+        assert_eq!(schedule.add_task(task(1, 0, 10, 1)), 1);
+        schedule.add_precedence(0, 1);
+        // tasks start @ release time.
+        assert_eq!(schedule.starts, vec![3, 0]);
+        assert_eq!(schedule.precedences(), &[(0, 1)]);
+        assert_eq!(schedule.end(0), 4);
+    }
+
+    #[test]
+    fn test_makespan_for_sanity() {
+        let mut schedule = job_shop();
+        assert_eq!(schedule.makespan(), 4);
+        schedule.starts[5] = 10;
+        assert_eq!(schedule.makespan(), 11);
+        assert_eq!(scheduling::Schedule::new(1).makespan(), 0);
+    }
+
+    #[test]
+    fn test_find_conflicts_for_sanity() {
+        let mut schedule = scheduling::Schedule::new(10);
+        let res = schedule.add_resource(2);
+        schedule.add_task(task(2, 1, 5, res));
+        schedule.add_task(task(2, 0, 10, res));
+        schedule.add_task(task(2, 0, 10, res));
+
+        // capacity of 2 --> task starting last overloads the resource.
+        let conflicts: Vec<scheduling::Conflict> = schedule.find_conflicts().map(|c| c.0).collect();
+        assert_eq!(conflicts, vec![scheduling::Conflict::Capacity(0, 1, 0)]);
+
+        // outside of time window & precedence violated.
+        schedule.starts = vec![4, 0, 2];
+        schedule.add_precedence(2, 1);
+        let conflicts: Vec<(scheduling::Conflict, f64)> = schedule.find_conflicts().collect();
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0], (scheduling::Conflict::Window(0), 0.0));
+        assert_eq!(conflicts[1], (scheduling::Conflict::Precedence(2, 1), 1.0));
+    }
+
+    #[test]
+    fn test_fix_conflict_for_sanity() {
+        let mut schedule = scheduling::Schedule::new(10);
+        let res = schedule.add_resource(1);
+        schedule.add_task(task(2, 1, 5, res));
+        schedule.add_task(task(3, 0, 10, res));
+
+        // move into time window.
+        schedule.starts[0] = 4;
+        schedule.fix_conflict(&scheduling::Conflict::Window(0));
+        assert_eq!(schedule.starts[0], 3);
+        schedule.starts[0] = 0;
+        schedule.fix_conflict(&scheduling::Conflict::Window(0));
+        assert_eq!(schedule.starts[0], 1);
+
+        // start after predecessor is done.
+        schedule.fix_conflict(&scheduling::Conflict::Precedence(0, 1));
+        assert_eq!(schedule.starts[1], 3);
+
+        // move after the other task using the resource.
+        schedule.starts[1] = 2;
+        schedule.fix_conflict(&scheduling::Conflict::Capacity(0, 2, 1));
+        assert_eq!(schedule.starts[1], 3);
+    }
+
+    #[test]
+    fn test_fix_conflict_delta_for_sanity() {
+        let mut schedule = scheduling::Schedule::new(10);
+        let res = schedule.add_resource(1);
+        schedule.add_task(task(2, 0, 10, res));
+        schedule.add_task(task(3, 0, 10, res));
+        schedule.add_task(task(1, 0, 10, res));
+        schedule.add_precedence(0, 1);
+        schedule.starts = vec![0, 1, 9];
+
+        // moving task 1 resolves both the precedence & the capacity conflict.
+        let delta = schedule
+            .fix_conflict_delta(&scheduling::Conflict::Precedence(0, 1))
+            .unwrap();
+        assert_eq!(schedule.starts[1], 2);
+        assert_eq!(
+            delta.removed,
+            vec![
+                scheduling::Conflict::Precedence(0, 1),
+                scheduling::Conflict::Capacity(0, 1, 1)
+            ]
+        );
+        assert_eq!(delta.added.len(), 0);
+
+        // only the conflicts of the moved task change.
+        schedule.starts[2] = 3;
+        let delta = schedule
+            .fix_conflict_delta(&scheduling::Conflict::Capacity(0, 3, 2))
+            .unwrap();
+        assert_eq!(schedule.starts[2], 5);
+        assert_eq!(delta.removed, vec![scheduling::Conflict::Capacity(0, 3, 2)]);
+        assert_eq!(delta.added, vec![]);
+
+        // changing a task rebuilds the index - task 2 overloads the resource on its own now, which
+        // no move fixes.
+        schedule.task_mut(2).demands = vec![(res, 2)];
+        let conflict = scheduling::Conflict::Capacity(0, 5, 2);
+        assert_eq!(schedule.find_conflicts().next(), Some((conflict, 2.0)));
+        let delta = schedule.fix_conflict_delta(&conflict).unwrap();
+        assert!(delta.removed.is_empty() && delta.added.is_empty());
+
+        // tracking the deltas gives the same conflicts as a full scan.
+        let mut schedule = job_shop();
+        schedule.starts = vec![0; 6];
+        let mut conflicts: Vec<scheduling::Conflict> =
+            schedule.find_conflicts().map(|(c, _)| c).collect();
+        while let Some(conflict) = conflicts.first().copied() {
+            let delta = schedule.fix_conflict_delta(&conflict).unwrap();
+            conflicts.retain(|c| !delta.removed.contains(c));
+            conflicts.extend(delta.added.iter().map(|(c, _)| *c));
+            let mut expected: Vec<scheduling::Conflict> =
+                schedule.find_conflicts().map(|(c, _)| c).collect();
+            conflicts.sort_by_key(|c| format!("{:?}", c));
+            expected.sort_by_key(|c| format!("{:?}", c));
+            assert_eq!(conflicts, expected);
+        }
+
+        // a repair only looks at the neighbours of the moved task - however long the chain is.
+        let evaluations: Vec<usize> = [10usize, 1000]
+            .iter()
+            .map(|n| {
+                let horizon = 2 * *n as i64;
+                let mut schedule = scheduling::Schedule::new(horizon);
+                for i in 0..*n {
+                    let res = schedule.add_resource(1);
+                    schedule.add_task(task(1, 0, horizon, res));
+                    if i > 0 {
+                        schedule.add_precedence(i - 1, i);
+                    }
+                }
+                schedule.fix_conflict_delta(&scheduling::Conflict::Precedence(4, 5));
+                schedule.evaluations
+            })
+            .collect();
+        assert_eq!(evaluations, vec![8, 8]);
+    }
+
+    #[test]
+    fn test_moves_for_sanity() {
+        let mut schedule = scheduling::Schedule::new(10);
+        let res = schedule.add_resource(1);
+        schedule.add_task(task(2, 1, 6, res));
+        schedule.add_task(task(3, 0, 10, res));
+        schedule.add_precedence(0, 1);
+
+        // task 0 could move after task 1 or to its latest start.
+        let moves = schedule.moves(&scheduling::Conflict::Window(0));
+        assert_eq!(moves, vec![(0, 3), (0, 4)]);
+        // task 1 could move after task 0 or to its latest start; or task 0 gets moved.
+        let moves = schedule.moves(&scheduling::Conflict::Precedence(0, 1));
+        assert_eq!(moves, vec![(1, 3), (1, 7), (0, 3), (0, 4)]);
+
+        // all tasks using the resource at that time can be moved.
+        schedule.starts = vec![1, 1];
+        let moves = schedule.moves(&scheduling::Conflict::Capacity(0, 1, 1));
+        assert!(moves.contains(&(0, 4)));
+        assert!(moves.contains(&(1, 3)));
+    }
+
+    #[test]
+    fn test_solve_for_sanity() {
+        // greedy repairs.
+        let mut schedule = job_shop();
+        let res = iterative_repair::solve(&mut schedule, 50);
+        assert!(res.0);
+        assert_eq!(schedule.find_conflicts().len(), 0);
+
+        // greedy repairs with incremental conflict tracking.
+        let mut incremental = job_shop();
+        let res = iterative_repair::solve_incremental(&mut incremental, 50);
+        assert!(res.0);
+        assert_eq!(incremental.find_conflicts().len(), 0);
+
+        // min-conflicts.
+        let mut schedule = job_shop();
+        let config = iterative_repair::Config {
+            strategy: iterative_repair::Strategy::MinConflicts,
+            tabu: 2,
+            restarts: 3,
+            ..Default::default()
+        };
+        let res = iterative_repair::solve_with_config(&mut schedule, &config);
+        assert!(res.found);
+        assert_eq!(res.best, schedule.starts);
+        assert_eq!(schedule.find_conflicts().len(), 0);
+
+        // simulated annealing.
+        let mut schedule = job_shop();
+        let config = iterative_repair::AnnealingConfig::default();
+        let res = iterative_repair::anneal(&mut schedule, &config);
+        assert!(res.found);
+        assert_eq!(schedule.find_conflicts().len(), 0);
+        assert!(schedule.makespan() <= 20);
+    }
+}
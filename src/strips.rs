@@ -0,0 +1,1043 @@
+use std::cell;
+use std::collections;
+use std::vec;
+
+use crate::heuristics;
+use crate::pddl;
+use crate::planner;
+use crate::util;
+
+/// Id of the artificial state all goal states lead to.
+const GOAL: usize = 0;
+/// Id of the initial state.
+const INIT: usize = 1;
+
+///
+/// Set of facts stored as bits - facts are given by their index.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FactSet {
+    words: Vec<u64>,
+}
+
+impl FactSet {
+    /// Create an empty set with room for the given number of facts.
+    pub fn new(n_facts: usize) -> FactSet {
+        FactSet {
+            words: vec![0; n_facts / 64 + 1],
+        }
+    }
+
+    /// Create a set from the given facts.
+    pub fn from_facts(n_facts: usize, facts: &[usize]) -> FactSet {
+        let mut res = FactSet::new(n_facts);
+        for fact in facts {
+            res.insert(*fact);
+        }
+        res
+    }
+
+    /// Check if the fact is in the set.
+    pub fn contains(&self, fact: usize) -> bool {
+        self.words[fact / 64] & (1 << (fact % 64)) != 0
+    }
+
+    /// Check if all the given facts are in the set.
+    pub fn contains_all(&self, facts: &[usize]) -> bool {
+        facts.iter().all(|f| self.contains(*f))
+    }
+
+    /// Add the fact - needs to be within the room the set was created with.
+    pub fn insert(&mut self, fact: usize) {
+        self.words[fact / 64] |= 1 << (fact % 64);
+    }
+
+    /// Remove the fact - if in the set.
+    pub fn remove(&mut self, fact: usize) {
+        self.words[fact / 64] &= !(1 << (fact % 64));
+    }
+
+    /// Number of facts in the set.
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Check if there are no facts in the set.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// Add all facts of the other set.
+    pub fn union_with(&mut self, other: &FactSet) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= other;
+        }
+    }
+
+    /// Keep only the facts also in the other set.
+    pub fn intersect_with(&mut self, other: &FactSet) {
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= other;
+        }
+    }
+
+    /// Facts in the set - in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.words.len() * 64).filter(move |f| self.contains(*f))
+    }
+}
+
+///
+/// Effect of an operator which only happens if its condition holds before the operator is applied.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conditional {
+    /// Facts which need to hold for the effect to happen.
+    pub pre: Vec<usize>,
+    /// Facts made true.
+    pub add: Vec<usize>,
+    /// Facts made false - unless also added.
+    pub del: Vec<usize>,
+}
+
+///
+/// Ground action - facts are given by their index.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operator {
+    /// Name incl. the arguments - e.g. `(drive truck a b)`.
+    pub name: String,
+    /// Facts which need to hold.
+    pub pre: Vec<usize>,
+    /// Facts made true.
+    pub add: Vec<usize>,
+    /// Facts made false - unless also added.
+    pub del: Vec<usize>,
+    /// Effects depending on the state the operator is applied in.
+    pub conditional: Vec<Conditional>,
+    /// Cost of applying the operator.
+    pub cost: f64,
+}
+
+impl Operator {
+    /// Facts the operator may add - incl. those of its conditional effects.
+    pub fn adds(&self) -> impl Iterator<Item = &usize> {
+        self.add
+            .iter()
+            .chain(self.conditional.iter().flat_map(|c| &c.add))
+    }
+}
+
+///
+/// Pairs of facts which can't hold together in any state reachable from the initial one - found
+/// by h² reachability, ignoring negative preconditions & the deletes of other effects. Used to
+/// regress to complete states without enumerating all assignments of the facts an operator touches.
+///
+#[derive(Clone, Debug)]
+pub struct Mutexes {
+    /// Facts which may hold.
+    reachable: FactSet,
+    /// Facts which may hold together with the fact - incl. the fact itself if it may hold.
+    pairs: Vec<FactSet>,
+}
+
+impl Mutexes {
+    /// Find the pairs of facts which may hold together - starting from the given facts.
+    pub fn new(n_facts: usize, init: &[usize], operators: &[Operator]) -> Mutexes {
+        let mut res = Mutexes {
+            reachable: FactSet::new(n_facts),
+            pairs: vec![FactSet::new(n_facts); n_facts],
+        };
+        for p in init {
+            for q in init {
+                res.mark(*p, *q);
+            }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for op in operators {
+                // effects whose conditions may hold together - with the facts which may hold along.
+                let mut effects: Vec<(&[usize], Vec<usize>, FactSet)> = Vec::new();
+                let mut conditions = vec![(&op.add, &op.del, op.pre.clone())];
+                for cond in &op.conditional {
+                    let mut pre = op.pre.clone();
+                    pre.extend(&cond.pre);
+                    conditions.push((&cond.add, &cond.del, pre));
+                }
+                for (add, del, pre) in conditions {
+                    let along = res.along(&pre);
+                    if !pre.iter().all(|f| along.contains(*f)) {
+                        continue;
+                    }
+                    // facts holding before which neither the operator nor the effect delete.
+                    let mut kept = along.clone();
+                    for fact in op.del.iter().chain(del) {
+                        kept.remove(*fact);
+                    }
+                    for p in add {
+                        changed |= res.mark(*p, *p);
+                        for q in kept.iter() {
+                            changed |= res.mark(*p, q);
+                        }
+                    }
+                    effects.push((add, pre, along));
+                }
+                for (i, (add, _, along)) in effects.iter().enumerate() {
+                    // effects which may happen together.
+                    for (other, pre, _) in &effects[i..] {
+                        if pre.iter().all(|f| along.contains(*f)) {
+                            for p in add.iter() {
+                                for q in other.iter() {
+                                    changed |= res.mark(*p, *q);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    /// Mark the facts as holding together - `true` if they were not marked yet.
+    fn mark(&mut self, p: usize, q: usize) -> bool {
+        if self.pairs[p].contains(q) {
+            return false;
+        }
+        self.reachable.insert(p);
+        self.reachable.insert(q);
+        self.pairs[p].insert(q);
+        self.pairs[q].insert(p);
+        true
+    }
+
+    /// Facts which may hold together with all the given facts.
+    fn along(&self, facts: &[usize]) -> FactSet {
+        let mut res = self.reachable.clone();
+        for fact in facts {
+            res.intersect_with(&self.pairs[*fact]);
+        }
+        res
+    }
+
+    /// Check if the facts may hold together.
+    pub fn compatible(&self, facts: &FactSet) -> bool {
+        let facts: Vec<usize> = facts.iter().collect();
+        let along = self.along(&facts);
+        facts.iter().all(|f| along.contains(*f))
+    }
+
+    ///
+    /// States extending the given facts by any of the free facts - only those in which all pairs
+    /// of facts may hold together.
+    ///
+    pub fn completions(&self, facts: &FactSet, free: &[usize]) -> Vec<FactSet> {
+        let mut res = Vec::new();
+        if self.compatible(facts) {
+            let along = self.along(&facts.iter().collect::<Vec<usize>>());
+            self.complete(&mut facts.clone(), along, free, &mut res);
+        }
+        res
+    }
+
+    fn complete(
+        &self,
+        facts: &mut FactSet,
+        along: FactSet,
+        free: &[usize],
+        res: &mut Vec<FactSet>,
+    ) {
+        let (fact, rest) = match free.split_first() {
+            Some(split) => split,
+            None => {
+                res.push(facts.clone());
+                return;
+            }
+        };
+        if along.contains(*fact) {
+            let mut next = along.clone();
+            next.intersect_with(&self.pairs[*fact]);
+            facts.insert(*fact);
+            self.complete(facts, next, rest, res);
+            facts.remove(*fact);
+        }
+        self.complete(facts, along, rest, res);
+    }
+}
+
+///
+/// Decision tree over the facts of the preconditions - finds the applicable operators without
+/// checking every operator.
+///
+#[derive(Debug, Default)]
+struct MatchTree {
+    /// Operators whose preconditions are all checked on the path to this node.
+    ops: Vec<usize>,
+    fact: usize,
+    /// Operators needing the fact.
+    on: Option<Box<MatchTree>>,
+    /// Operators not needing the fact.
+    any: Option<Box<MatchTree>>,
+}
+
+impl MatchTree {
+    /// Build the tree - each operator comes with its remaining preconditions in increasing order.
+    fn build(ops: Vec<(usize, &[usize])>) -> Option<Box<MatchTree>> {
+        if ops.is_empty() {
+            return None;
+        }
+        let mut node = MatchTree::default();
+        let mut rest = Vec::new();
+        for (op, pre) in ops {
+            match pre.first() {
+                Some(_) => rest.push((op, pre)),
+                None => node.ops.push(op),
+            }
+        }
+        if let Some(fact) = rest.iter().map(|(_, pre)| pre[0]).min() {
+            node.fact = fact;
+            let (on, any): (Vec<_>, Vec<_>) = rest.into_iter().partition(|(_, pre)| pre[0] == fact);
+            let on = on.into_iter().map(|(op, pre)| (op, &pre[1..])).collect();
+            node.on = MatchTree::build(on);
+            node.any = MatchTree::build(any);
+        }
+        Some(Box::new(node))
+    }
+
+    fn applicable(&self, state: &FactSet, res: &mut Vec<usize>) {
+        res.extend(&self.ops);
+        if let Some(on) = &self.on {
+            if state.contains(self.fact) {
+                on.applicable(state, res);
+            }
+        }
+        if let Some(any) = &self.any {
+            any.applicable(state, res);
+        }
+    }
+}
+
+///
+/// STRIPS planning task - implements `planner::ProblemSpace` like `pddl::Task` does, incl. the
+/// artificial goal state & the regression pruned by mutually exclusive facts. The operators
+/// leading from state to state can be recovered using `actions`; the heuristic is selected using
+/// `with_heuristic`.
+///
+/// # Example
+/// ```
+/// use rusty_planner::cooperative;
+/// use rusty_planner::strips;
+///
+/// let flip = strips::Operator {
+///     name: String::from("(flip)"),
+///     pre: vec![],
+///     add: vec![0],
+///     del: vec![],
+///     conditional: vec![],
+///     cost: 1.0,
+/// };
+/// let task = strips::Task::new(vec![String::from("(on)")], vec![flip], &[], vec![0]);
+///
+/// let mut dist = cooperative::TrueDistance::new(task.goal_state());
+/// assert_eq!(dist.distance(&task, &task.initial_state()), 1.0);
+/// let next = task.apply(task.initial_state(), 0).unwrap();
+/// assert_eq!(task.actions(&[task.initial_state(), next]), Some(vec!["(flip)"]));
+/// ```
+///
+pub struct Task {
+    facts: Vec<String>,
+    operators: Vec<Operator>,
+    goal: Vec<usize>,
+    tree: Option<Box<MatchTree>>,
+    heuristic: heuristics::Heuristic,
+    /// Facts & their complements - kept consistent when applying operators.
+    complements: Vec<(usize, usize)>,
+    states: cell::RefCell<util::Interner<FactSet>>,
+    /// Pairs of facts which can't hold together - found on first use.
+    mutexes: cell::OnceCell<Mutexes>,
+}
+
+impl Task {
+    ///
+    /// Create a new task given the names of the facts, the operators, the facts holding initially
+    /// & the facts which need to hold in the end.
+    ///
+    pub fn new(
+        facts: Vec<String>,
+        mut operators: Vec<Operator>,
+        init: &[usize],
+        mut goal: Vec<usize>,
+    ) -> Task {
+        let n_facts = facts.len();
+        goal.sort_unstable();
+        goal.dedup();
+        for op in &mut operators {
+            let conditional = op
+                .conditional
+                .iter_mut()
+                .flat_map(|c| [&mut c.pre, &mut c.add, &mut c.del]);
+            for facts in vec![&mut op.pre, &mut op.add, &mut op.del]
+                .into_iter()
+                .chain(conditional)
+            {
+                facts.sort_unstable();
+                facts.dedup();
+                assert!(facts.iter().all(|f| *f < n_facts), "Unknown fact.");
+            }
+        }
+        let tree = MatchTree::build(
+            operators
+                .iter()
+                .enumerate()
+                .map(|(i, op)| (i, op.pre.as_slice()))
+                .collect(),
+        );
+        let mut states = util::Interner::new();
+        // the artificial goal state only has the marker set.
+        let mut marker = FactSet::new(n_facts);
+        marker.insert(n_facts);
+        states.intern(marker);
+        states.intern(FactSet::from_facts(n_facts, init));
+        Task {
+            facts,
+            operators,
+            goal,
+            tree,
+            heuristic: heuristics::Heuristic::Blind,
+            complements: Vec::new(),
+            states: cell::RefCell::new(states),
+            mutexes: cell::OnceCell::new(),
+        }
+    }
+
+    ///
+    /// Compile a grounded PDDL task to STRIPS - negative preconditions become facts of their own
+    /// (named `(not <fact>)`) which are kept complementary to the facts; conditional effects stay
+    /// conditional.
+    ///
+    pub fn from_pddl(task: &pddl::Task) -> Task {
+        let mut facts = task.facts.clone();
+        // complements of the facts needed to be false somewhere.
+        let mut negated: Vec<usize> = task.goal_neg.clone();
+        for op in &task.operators {
+            negated.extend(&op.pre_neg);
+            for cond in &op.conditional {
+                negated.extend(&cond.pre_neg);
+            }
+        }
+        negated.sort_unstable();
+        negated.dedup();
+        let mut complement = collections::HashMap::new();
+        for fact in &negated {
+            complement.insert(*fact, facts.len());
+            facts.push(format!("(not {})", task.facts[*fact]));
+        }
+
+        // preconditions incl. the complements & effects updating the complements - adds of the
+        // operator win over deletes.
+        let pre = |pre: &[usize], pre_neg: &[usize]| -> Vec<usize> {
+            let mut res = pre.to_vec();
+            res.extend(pre_neg.iter().map(|f| complement[f]));
+            res
+        };
+        let effects = |add: &[usize], del: &[usize], op: &pddl::Operator| {
+            let del: Vec<usize> = del
+                .iter()
+                .filter(|f| !op.add.contains(f))
+                .copied()
+                .collect();
+            let mut add_all = add.to_vec();
+            add_all.extend(del.iter().filter_map(|f| complement.get(f)));
+            let mut del_all = del.clone();
+            del_all.extend(add.iter().filter_map(|f| complement.get(f)));
+            (add_all, del_all)
+        };
+        let mut operators = Vec::new();
+        for op in &task.operators {
+            let (add, del) = effects(&op.add, &op.del, op);
+            let conditional = op
+                .conditional
+                .iter()
+                .map(|cond| {
+                    let (add, del) = effects(&cond.add, &cond.del, op);
+                    Conditional {
+                        pre: pre(&cond.pre, &cond.pre_neg),
+                        add,
+                        del,
+                    }
+                })
+                .collect();
+            operators.push(Operator {
+                name: op.name.clone(),
+                pre: pre(&op.pre, &op.pre_neg),
+                add,
+                del,
+                conditional,
+                cost: op.cost,
+            });
+        }
+        let mut init = task.init.clone();
+        for (fact, c) in &complement {
+            if !task.init.contains(fact) {
+                init.push(*c);
+            }
+        }
+        let mut goal = task.goal.clone();
+        goal.extend(task.goal_neg.iter().map(|f| complement[f]));
+        let mut res = Task::new(facts, operators, &init, goal);
+        res.complements = complement.into_iter().collect();
+        res.complements.sort_unstable();
+        res
+    }
+
+    ///
+    /// Use the given heuristic as `ProblemSpace::heuristic` - defaults to `Heuristic::Blind`.
+    ///
+    pub fn with_heuristic(mut self, heuristic: heuristics::Heuristic) -> Task {
+        self.heuristic = heuristic;
+        self
+    }
+
+    /// Estimate the cost of reaching the facts of the target from the state.
+    fn estimate(&self, state: usize, target: usize) -> f64 {
+        if state == GOAL {
+            return f64::INFINITY;
+        }
+        let goal = match target {
+            GOAL => self.goal.clone(),
+            _ => self.state(target).iter().collect(),
+        };
+        heuristics::evaluate(self.heuristic, self, &self.state(state), &goal)
+    }
+
+    /// Names of the facts.
+    pub fn facts(&self) -> &[String] {
+        &self.facts
+    }
+
+    /// Operators - in the order given.
+    pub fn operators(&self) -> &[Operator] {
+        &self.operators
+    }
+
+    /// Facts which need to hold in the end.
+    pub fn goal(&self) -> &[usize] {
+        &self.goal
+    }
+
+    /// The initial state.
+    pub fn initial_state(&self) -> usize {
+        INIT
+    }
+
+    /// The artificial state reached from all states satisfying the goal.
+    pub fn goal_state(&self) -> usize {
+        GOAL
+    }
+
+    /// Facts holding in the state.
+    pub fn state(&self, state: usize) -> FactSet {
+        self.states.borrow().get(state).clone()
+    }
+
+    /// Id of the state with the given facts.
+    pub fn state_of(&self, facts: FactSet) -> usize {
+        self.states.borrow_mut().intern(facts)
+    }
+
+    /// Check if the state satisfies the goal.
+    pub fn is_goal(&self, state: usize) -> bool {
+        state != GOAL && self.state(state).contains_all(&self.goal)
+    }
+
+    /// Operators applicable in the state.
+    pub fn applicable(&self, state: usize) -> Vec<usize> {
+        let mut res = Vec::new();
+        if let (Some(tree), true) = (&self.tree, state != GOAL) {
+            tree.applicable(&self.state(state), &mut res);
+        }
+        res.sort_unstable();
+        res
+    }
+
+    /// Apply the operator - `None` if its preconditions are not satisfied.
+    pub fn apply(&self, state: usize, op: usize) -> Option<usize> {
+        if state == GOAL {
+            return None;
+        }
+        self.successor(&self.state(state), &self.operators[op])
+            .map(|facts| self.state_of(facts))
+    }
+
+    /// Pairs of facts which can't hold together.
+    fn mutexes(&self) -> &Mutexes {
+        self.mutexes.get_or_init(|| {
+            let init: Vec<usize> = self.state(INIT).iter().collect();
+            Mutexes::new(self.facts.len(), &init, &self.operators)
+        })
+    }
+
+    /// Check if the facts & their complements are consistent.
+    fn consistent(&self, facts: &FactSet) -> bool {
+        self.complements
+            .iter()
+            .all(|(fact, complement)| facts.contains(*fact) != facts.contains(*complement))
+    }
+
+    /// Conditions of effects are evaluated before any effect gets applied; deletes are applied
+    /// before adds.
+    fn successor(&self, facts: &FactSet, op: &Operator) -> Option<FactSet> {
+        if !facts.contains_all(&op.pre) {
+            return None;
+        }
+        let triggered: Vec<&Conditional> = op
+            .conditional
+            .iter()
+            .filter(|c| facts.contains_all(&c.pre))
+            .collect();
+        let mut res = facts.clone();
+        for fact in op.del.iter().chain(triggered.iter().flat_map(|c| &c.del)) {
+            res.remove(*fact);
+        }
+        for fact in op.add.iter().chain(triggered.iter().flat_map(|c| &c.add)) {
+            res.insert(*fact);
+        }
+        for (fact, complement) in &self.complements {
+            if res.contains(*fact) {
+                res.remove(*complement);
+            } else {
+                res.insert(*complement);
+            }
+        }
+        Some(res)
+    }
+
+    ///
+    /// Cheapest operator leading from one state to the other - `None` if there is none.
+    ///
+    pub fn label(&self, from: usize, to: usize) -> Option<&Operator> {
+        let facts = self.state(from);
+        let to = self.state(to);
+        self.applicable(from)
+            .into_iter()
+            .map(|op| &self.operators[op])
+            .filter(|op| self.successor(&facts, op).as_ref() == Some(&to))
+            .min_by(|a, b| a.cost.total_cmp(&b.cost))
+    }
+
+    ///
+    /// Names of the actions taken along the path of states (incl. the state it starts from) -
+    /// steps into the artificial goal state are skipped. `None` if a step is not possible.
+    ///
+    pub fn actions(&self, path: &[usize]) -> Option<Vec<&str>> {
+        path.windows(2)
+            .filter(|step| step[1] != GOAL)
+            .map(|step| self.label(step[0], step[1]).map(|op| op.name.as_str()))
+            .collect()
+    }
+}
+
+impl planner::ProblemSpace for Task {
+    type State = usize;
+    type Iter = vec::IntoIter<(usize, f64)>;
+
+    /// Estimate of the selected heuristic - as forward searches estimate the distance to the goal
+    /// & backward searches the distance from the start, the minimum of both directions is used.
+    fn heuristic(&self, state: &Self::State, other: &Self::State) -> f64 {
+        if state == other || self.heuristic == heuristics::Heuristic::Blind {
+            return 0.0;
+        }
+        f64::min(self.estimate(*state, *other), self.estimate(*other, *state))
+    }
+
+    fn succ(&self, state: &Self::State) -> Self::Iter {
+        let mut res = Vec::new();
+        if *state == GOAL {
+            return res.into_iter();
+        }
+        let facts = self.state(*state);
+        for op in self.applicable(*state) {
+            let op = &self.operators[op];
+            if let Some(next) = self.successor(&facts, op) {
+                res.push((self.state_of(next), op.cost));
+            }
+        }
+        if facts.contains_all(&self.goal) {
+            res.push((GOAL, 0.0));
+        }
+        res.into_iter()
+    }
+
+    fn pred(&self, state: &Self::State) -> Self::Iter {
+        let mut res = Vec::new();
+        let n_facts = self.facts.len();
+        if *state == GOAL {
+            // all completions of the goal.
+            let free: Vec<usize> = (0..n_facts).filter(|f| !self.goal.contains(f)).collect();
+            let goal = FactSet::from_facts(n_facts, &self.goal);
+            for facts in self.mutexes().completions(&goal, &free) {
+                if self.consistent(&facts) {
+                    res.push((self.state_of(facts), 0.0));
+                }
+            }
+            return res.into_iter();
+        }
+        // regression - predecessors hold the preconditions & differ in the facts the operator
+        // touches.
+        let facts = self.state(*state);
+        for op in &self.operators {
+            if !facts.contains_all(&op.add) {
+                continue;
+            }
+            let mut touched: Vec<usize> = op.add.iter().chain(&op.del).copied().collect();
+            for cond in &op.conditional {
+                touched.extend(cond.add.iter().chain(&cond.del));
+            }
+            touched.sort_unstable();
+            touched.dedup();
+            if op
+                .pre
+                .iter()
+                .any(|f| !touched.contains(f) && !facts.contains(*f))
+            {
+                continue;
+            }
+            let mut candidate = facts.clone();
+            for fact in &touched {
+                candidate.remove(*fact);
+            }
+            for fact in &op.pre {
+                candidate.insert(*fact);
+            }
+            let free: Vec<usize> = touched
+                .iter()
+                .filter(|f| !op.pre.contains(f))
+                .copied()
+                .collect();
+            for candidate in self.mutexes().completions(&candidate, &free) {
+                if self.consistent(&candidate)
+                    && self.successor(&candidate, op).as_ref() == Some(&facts)
+                {
+                    res.push((self.state_of(candidate), op.cost));
+                }
+            }
+        }
+        res.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cooperative;
+    use crate::pddl;
+    use crate::planner::ProblemSpace;
+    use crate::strips;
+
+    const DOMAIN: &str = "
+        (define (domain lamp)
+          (:requirements :strips :negative-preconditions :conditional-effects)
+          (:predicates (on) (seen))
+          (:action toggle
+            :parameters ()
+            :effect (and (when (on) (not (on))) (when (not (on)) (on))))
+          (:action look
+            :parameters ()
+            :precondition (on)
+            :effect (seen)))";
+
+    const PROBLEM: &str = "
+        (define (problem look-in-the-dark)
+          (:domain lamp)
+          (:init)
+          (:goal (and (seen) (not (on)))))";
+
+    /// Operator with unit costs.
+    fn operator(name: &str, pre: Vec<usize>, add: Vec<usize>, del: Vec<usize>) -> strips::Operator {
+        strips::Operator {
+            name: String::from(name),
+            pre,
+            add,
+            del,
+            conditional: vec![],
+            cost: 1.0,
+        }
+    }
+
+    /// Rooms in a row - the robot moves between neighbouring rooms & picks up the ball.
+    fn task() -> strips::Task {
+        let facts = vec!["(at a)", "(at b)", "(at c)", "(ball c)", "(holding)"];
+        let operators = vec![
+            operator("(move a b)", vec![0], vec![1], vec![0]),
+            operator("(move b a)", vec![1], vec![0], vec![1]),
+            operator("(move b c)", vec![1], vec![2], vec![1]),
+            operator("(move c b)", vec![2], vec![1], vec![2]),
+            operator("(pick c)", vec![3, 2], vec![4], vec![3]),
+        ];
+        strips::Task::new(
+            facts.into_iter().map(String::from).collect(),
+            operators,
+            &[0, 3],
+            vec![4, 0],
+        )
+    }
+
+    fn lamp() -> strips::Task {
+        let domain = pddl::Domain::parse(DOMAIN).unwrap();
+        let problem = pddl::Problem::parse(PROBLEM).unwrap();
+        strips::Task::from_pddl(&pddl::Task::ground(&domain, &problem).unwrap())
+    }
+
+    /// Follow the cheapest successors to the goal.
+    fn plan(task: &strips::Task) -> Vec<usize> {
+        let mut dist = cooperative::TrueDistance::new(task.goal_state());
+        let mut path = vec![task.initial_state()];
+        while *path.last().unwrap() != task.goal_state() {
+            let (next, _) = task
+                .succ(path.last().unwrap())
+                .min_by(|a, b| {
+                    (a.1 + dist.distance(task, &a.0)).total_cmp(&(b.1 + dist.distance(task, &b.0)))
+                })
+                .unwrap();
+            path.push(next);
+        }
+        path
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_fact_set_for_success() {
+        let mut facts = strips::FactSet::new(100);
+        facts.insert(70);
+        facts.remove(70);
+        facts.contains(1);
+        facts.contains_all(&[1, 2]);
+        facts.iter().count();
+    }
+
+    #[test]
+    fn test_task_for_success() {
+        let task = task();
+        let init = task.initial_state();
+        task.succ(&init);
+        task.pred(&init);
+        task.heuristic(&init, &task.goal_state());
+        task.applicable(init);
+        task.apply(init, 0);
+        task.is_goal(init);
+        task.actions(&[init]);
+        lamp();
+    }
+
+    // Test for failure.
+
+    #[test]
+    #[should_panic]
+    fn test_task_for_failure() {
+        strips::Task::new(
+            vec![String::from("(on)")],
+            vec![operator("(flip)", vec![], vec![1], vec![])],
+            &[],
+            vec![0],
+        );
+    }
+
+    #[test]
+    fn test_actions_for_failure() {
+        let task = task();
+        let init = task.initial_state();
+        assert_eq!(task.apply(init, 2), None);
+        assert_eq!(task.apply(task.goal_state(), 0), None);
+
+        // no single operator leads from a to c.
+        let b = task.apply(init, 0).unwrap();
+        let c = task.apply(b, 2).unwrap();
+        assert!(task.label(init, c).is_none());
+        assert_eq!(task.actions(&[init, c]), None);
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_fact_set_for_sanity() {
+        let mut facts = strips::FactSet::from_facts(130, &[3, 64, 129]);
+        assert!(facts.contains(64));
+        assert!(!facts.contains(63));
+        assert!(facts.contains_all(&[3, 129]));
+        facts.remove(3);
+        assert_eq!(facts.iter().collect::<Vec<_>>(), vec![64, 129]);
+        assert_eq!(facts, strips::FactSet::from_facts(130, &[129, 64]));
+    }
+
+    #[test]
+    fn test_mutexes_for_sanity() {
+        let task = task();
+        let mutexes = strips::Mutexes::new(5, &[0, 3], task.operators());
+        assert!(mutexes.compatible(&strips::FactSet::from_facts(5, &[2, 4])));
+        assert!(!mutexes.compatible(&strips::FactSet::from_facts(5, &[0, 1])));
+        // picking up the ball removes it from the room.
+        assert!(!mutexes.compatible(&strips::FactSet::from_facts(5, &[3, 4])));
+
+        // in room c - with the ball in the room, in hand or neither.
+        let completions = mutexes.completions(&strips::FactSet::from_facts(5, &[2]), &[0, 1, 3, 4]);
+        assert_eq!(
+            completions,
+            vec![
+                strips::FactSet::from_facts(5, &[2, 3]),
+                strips::FactSet::from_facts(5, &[2, 4]),
+                strips::FactSet::from_facts(5, &[2]),
+            ]
+        );
+        assert!(mutexes
+            .completions(&strips::FactSet::from_facts(5, &[0, 2]), &[3])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_match_tree_for_sanity() {
+        let task = task();
+        let mut states = vec![task.initial_state()];
+        let mut i = 0;
+        while i < states.len() {
+            // the match tree agrees with checking all operators.
+            let facts = task.state(states[i]);
+            let expected: Vec<usize> = (0..task.operators().len())
+                .filter(|op| facts.contains_all(&task.operators()[*op].pre))
+                .collect();
+            assert_eq!(task.applicable(states[i]), expected);
+            for (next, _) in task.succ(&states[i]) {
+                if next != task.goal_state() && !states.contains(&next) {
+                    states.push(next);
+                }
+            }
+            i += 1;
+        }
+        assert_eq!(states.len(), 6);
+        assert!(task.applicable(task.goal_state()).is_empty());
+    }
+
+    #[test]
+    fn test_task_for_sanity() {
+        let task = task();
+        let init = task.initial_state();
+        assert_eq!(task.goal(), &[0, 4]);
+        assert_eq!(task.operators()[4].pre, vec![2, 3]);
+
+        // deletes before adds.
+        let b = task.apply(init, 0).unwrap();
+        assert_eq!(task.state(b), strips::FactSet::from_facts(5, &[1, 3]));
+        assert_eq!(task.apply(b, 1), Some(init));
+        assert_eq!(task.succ(&b).count(), 2);
+
+        // regression.
+        assert!(task.pred(&b).any(|s| s == (init, 1.0)));
+        assert!(task.pred(&b).all(|(s, _)| task.succ(&s).any(|n| n.0 == b)));
+        assert!(task.pred(&task.goal_state()).all(|(s, _)| task.is_goal(s)));
+
+        // cheapest plan & its actions.
+        let path = plan(&task);
+        assert_eq!(path.len(), 7);
+        assert!(task.is_goal(path[5]));
+        assert_eq!(
+            task.actions(&path),
+            Some(vec![
+                "(move a b)",
+                "(move b c)",
+                "(pick c)",
+                "(move c b)",
+                "(move b a)"
+            ])
+        );
+
+        // more than 64 facts - incl. an operator touching all of them.
+        let n_facts = 70;
+        let mut operators: Vec<strips::Operator> = (0..n_facts - 1)
+            .map(|i| operator("(step)", vec![i], vec![i + 1], vec![i]))
+            .collect();
+        operators.push(operator("(reset)", vec![], vec![0], (0..n_facts).collect()));
+        let facts = (0..n_facts).map(|i| format!("(at {})", i)).collect();
+        let task = strips::Task::new(facts, operators, &[0], vec![n_facts - 1]);
+        assert_eq!(task.pred(&task.goal_state()).count(), 1);
+        // resetting from any of the facts - or from none, which pairs of facts can't rule out.
+        assert_eq!(task.pred(&task.initial_state()).count(), n_facts + 1);
+        let mut dist = cooperative::TrueDistance::new(task.goal_state());
+        assert_eq!(dist.distance(&task, &task.initial_state()), 69.0);
+    }
+
+    #[test]
+    fn test_from_pddl_for_sanity() {
+        let task = lamp();
+        assert_eq!(task.facts(), &["(on)", "(seen)", "(not (on))"]);
+
+        // the conditional effects stay conditional - & keep the complement up to date.
+        let toggle = task
+            .operators()
+            .iter()
+            .find(|op| op.name == "(toggle)")
+            .unwrap();
+        assert!(toggle.pre.is_empty() && toggle.add.is_empty());
+        assert_eq!(
+            toggle.conditional,
+            vec![
+                strips::Conditional {
+                    pre: vec![0],
+                    add: vec![2],
+                    del: vec![0],
+                },
+                strips::Conditional {
+                    pre: vec![2],
+                    add: vec![0],
+                    del: vec![2],
+                },
+            ]
+        );
+
+        // the complement holds initially.
+        assert_eq!(
+            task.state(task.initial_state()),
+            strips::FactSet::from_facts(3, &[2])
+        );
+
+        // regression through the conditional effects - to consistent states only.
+        let op = task
+            .operators()
+            .iter()
+            .position(|op| op.name == "(toggle)")
+            .unwrap();
+        let on = task.apply(task.initial_state(), op).unwrap();
+        assert_eq!(
+            task.pred(&on).collect::<Vec<_>>(),
+            vec![(task.initial_state(), 1.0)]
+        );
+        assert_eq!(task.goal(), &[1, 2]);
+        assert_eq!(
+            task.actions(&plan(&task)),
+            Some(vec!["(toggle)", "(look)", "(toggle)"])
+        );
+
+        // a conditional effect per object - still a single operator.
+        let domain = pddl::Domain::parse(
+            "(define (domain lamps)
+               (:requirements :strips :conditional-effects)
+               (:predicates (on ?l))
+               (:action toggle-all
+                 :parameters ()
+                 :effect (forall (?l) (and (when (on ?l) (not (on ?l)))
+                                           (when (not (on ?l)) (on ?l))))))",
+        )
+        .unwrap();
+        let lamps: Vec<String> = (0..20).map(|i| format!("l{}", i)).collect();
+        let problem = pddl::Problem::parse(&format!(
+            "(define (problem all-on) (:domain lamps) (:objects {}) (:init) (:goal (on l0)))",
+            lamps.join(" ")
+        ))
+        .unwrap();
+        let task = strips::Task::from_pddl(&pddl::Task::ground(&domain, &problem).unwrap());
+        assert_eq!(task.operators().len(), 1);
+        assert_eq!(task.operators()[0].conditional.len(), 40);
+        let on = task.apply(task.initial_state(), 0).unwrap();
+        assert_eq!(task.state(on).len(), 20);
+        assert!(task.is_goal(on));
+    }
+}
@@ -0,0 +1,612 @@
+use std::cmp;
+#[cfg(feature = "multi_agent")]
+use std::collections;
+#[cfg(feature = "multi_agent")]
+use std::error;
+#[cfg(feature = "multi_agent")]
+use std::fmt;
+#[cfg(feature = "multi_agent")]
+use std::thread;
+#[cfg(feature = "multi_agent")]
+use std::time;
+
+#[cfg(feature = "multi_agent")]
+use rusty_agent::agent as messaging;
+
+///
+/// A task which needs to be performed by a coalition of agents.
+///
+pub struct Task {
+    /// Amount of each capability needed to perform the task.
+    pub requirements: Vec<f64>,
+    /// Value gained by performing the task.
+    pub value: f64,
+}
+
+///
+/// An agent which can join a coalition.
+///
+pub struct Agent {
+    /// Amount of each capability the agent brings into a coalition.
+    pub capabilities: Vec<f64>,
+    /// Cost of the agent joining a coalition.
+    pub cost: f64,
+}
+
+///
+/// Result of the task allocation.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Allocation {
+    /// Tasks and the coalitions (sorted agent ids) assigned to them - in the order they were formed.
+    pub assignments: Vec<(usize, Vec<usize>)>,
+    /// Tasks which could not be assigned to any coalition.
+    pub unassigned: Vec<usize>,
+    /// Sum of the benefits of all assignments.
+    pub value: f64,
+}
+
+///
+/// Configuration of the distributed task allocation.
+///
+#[cfg(feature = "multi_agent")]
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Time (in ms) between checking for new messages.
+    pub poll_interval: u64,
+    /// Time (in ms) after which the agent gives up waiting for the proposals of the others.
+    pub timeout: u64,
+}
+
+#[cfg(feature = "multi_agent")]
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            poll_interval: 10,
+            timeout: 5000,
+        }
+    }
+}
+
+///
+/// Reasons the distributed task allocation can fail.
+///
+#[cfg(feature = "multi_agent")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Error {
+    /// Not all agents proposed in time.
+    Timeout,
+}
+
+#[cfg(feature = "multi_agent")]
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Timeout => write!(f, "agents did not propose in time"),
+        }
+    }
+}
+
+#[cfg(feature = "multi_agent")]
+impl error::Error for Error {}
+
+/// A coalition proposed to perform a task.
+#[derive(Clone, Debug, PartialEq)]
+struct Proposal {
+    task: usize,
+    members: Vec<usize>,
+    benefit: f64,
+}
+
+/// Higher benefits first - smaller coalitions, lower task & agent ids break ties.
+fn better(a: &Proposal, b: &Proposal) -> bool {
+    match a.benefit.partial_cmp(&b.benefit) {
+        Some(cmp::Ordering::Greater) => true,
+        Some(cmp::Ordering::Less) => false,
+        _ => (a.members.len(), a.task, &a.members) < (b.members.len(), b.task, &b.members),
+    }
+}
+
+/// Check if a coalition can perform a task - its combined capabilities meet the requirements.
+fn capable(task: &Task, agents: &[Agent], members: &[usize]) -> bool {
+    task.requirements.iter().enumerate().all(|(i, req)| {
+        let sum: f64 = members
+            .iter()
+            .map(|m| agents[*m].capabilities.get(i).copied().unwrap_or(0.0))
+            .sum();
+        sum >= *req
+    })
+}
+
+/// Best coalition (of at most max_size available agents) & task pair with a non-negative
+/// benefit. If a leader is given only coalitions in which it is the member with the lowest id are
+/// considered - this way the agents split up the work.
+fn best_proposal(
+    tasks: &[Task],
+    agents: &[Agent],
+    open: &[usize],
+    available: &[usize],
+    max_size: usize,
+    leader: Option<usize>,
+) -> Option<Proposal> {
+    fn extend(
+        candidates: &[usize],
+        members: &mut Vec<usize>,
+        max_size: usize,
+        visit: &mut dyn FnMut(&[usize]),
+    ) {
+        if !members.is_empty() {
+            visit(members);
+        }
+        if members.len() == max_size {
+            return;
+        }
+        for (i, candidate) in candidates.iter().enumerate() {
+            members.push(*candidate);
+            extend(&candidates[i + 1..], members, max_size, visit);
+            members.pop();
+        }
+    }
+
+    let mut res: Option<Proposal> = None;
+    let mut visit = |members: &[usize]| {
+        let cost: f64 = members.iter().map(|m| agents[*m].cost).sum();
+        for task in open {
+            let benefit = tasks[*task].value - cost;
+            if benefit < 0.0 || !capable(&tasks[*task], agents, members) {
+                continue;
+            }
+            let proposal = Proposal {
+                task: *task,
+                members: members.to_vec(),
+                benefit,
+            };
+            if res.as_ref().is_none_or(|r| better(&proposal, r)) {
+                res = Some(proposal);
+            }
+        }
+    };
+    let mut candidates: Vec<usize> = available.to_vec();
+    candidates.sort_unstable();
+    match leader {
+        Some(l) if !candidates.contains(&l) => return None,
+        Some(l) => {
+            // the leader always is the first member.
+            let others: Vec<usize> = candidates.into_iter().filter(|c| *c > l).collect();
+            extend(&others, &mut vec![l], max_size, &mut visit);
+        }
+        None => extend(&candidates, &mut Vec::new(), max_size, &mut visit),
+    }
+    res
+}
+
+/// Commit to a proposal - the task is done & the members are no longer available.
+fn commit(
+    proposal: Proposal,
+    open: &mut Vec<usize>,
+    available: &mut Vec<usize>,
+    res: &mut Allocation,
+) {
+    open.retain(|t| *t != proposal.task);
+    available.retain(|a| !proposal.members.contains(a));
+    res.value += proposal.benefit;
+    res.assignments.push((proposal.task, proposal.members));
+}
+
+///
+/// Allocate tasks to coalitions of agents using the greedy algorithm - in each round the
+/// coalition & task pair with the highest benefit (task value minus the costs of the members) is
+/// formed, until no more coalition can perform one of the remaining tasks.
+///
+/// # Example
+/// ```
+/// use rusty_planner::task_allocation;
+///
+/// let tasks = vec![
+///     task_allocation::Task { requirements: vec![2.0, 1.0], value: 10.0 },
+///     task_allocation::Task { requirements: vec![0.0, 1.0], value: 4.0 },
+/// ];
+/// let agents = vec![
+///     task_allocation::Agent { capabilities: vec![1.0, 0.0], cost: 1.0 },
+///     task_allocation::Agent { capabilities: vec![1.0, 1.0], cost: 1.0 },
+///     task_allocation::Agent { capabilities: vec![0.0, 1.0], cost: 1.0 },
+/// ];
+///
+/// let res = task_allocation::solve(&tasks, &agents, 2);
+/// assert_eq!(res.assignments, vec![(0, vec![0, 1]), (1, vec![2])]);
+/// assert_eq!(res.value, 11.0);
+/// ```
+///
+pub fn solve(tasks: &[Task], agents: &[Agent], max_size: usize) -> Allocation {
+    let mut open: Vec<usize> = (0..tasks.len()).collect();
+    let mut available: Vec<usize> = (0..agents.len()).collect();
+    let mut res = Allocation::default();
+    while let Some(proposal) = best_proposal(tasks, agents, &open, &available, max_size, None) {
+        commit(proposal, &mut open, &mut available, &mut res);
+    }
+    res.unassigned = open;
+    res
+}
+
+/// Messages exchanged between the agents - the proposal of the sender for a round, if any.
+#[cfg(feature = "multi_agent")]
+#[derive(Clone, Debug, PartialEq)]
+enum Message {
+    Propose(usize, usize, Proposal),
+    Pass(usize, usize),
+}
+
+#[cfg(feature = "multi_agent")]
+impl Message {
+    fn new(round: usize, sender: usize, proposal: Option<Proposal>) -> Message {
+        match proposal {
+            Some(proposal) => Message::Propose(round, sender, proposal),
+            None => Message::Pass(round, sender),
+        }
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            Message::Propose(round, sender, p) => {
+                let members: Vec<String> = p.members.iter().map(|m| m.to_string()).collect();
+                format!(
+                    "propose;{};{};{};{};{}",
+                    round,
+                    sender,
+                    p.task,
+                    p.benefit,
+                    members.join(",")
+                )
+            }
+            Message::Pass(round, sender) => format!("pass;{};{}", round, sender),
+        }
+    }
+
+    /// Decode a message - None if it is not part of the protocol.
+    fn decode(msg: &str) -> Option<Message> {
+        let (kind, rest) = msg.split_once(';')?;
+        let data: Vec<&str> = rest.split(';').collect();
+        match (kind, data.len()) {
+            ("propose", 5) => {
+                let members: Result<Vec<usize>, _> =
+                    data[4].split(',').map(|m| m.parse::<usize>()).collect();
+                Some(Message::Propose(
+                    data[0].parse().ok()?,
+                    data[1].parse().ok()?,
+                    Proposal {
+                        task: data[2].parse().ok()?,
+                        benefit: data[3].parse().ok()?,
+                        members: members.ok()?,
+                    },
+                ))
+            }
+            ("pass", 2) => Some(Message::Pass(data[0].parse().ok()?, data[1].parse().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// Round, sender & proposal.
+    fn unpack(self) -> (usize, usize, Option<Proposal>) {
+        match self {
+            Message::Propose(round, sender, proposal) => (round, sender, Some(proposal)),
+            Message::Pass(round, sender) => (round, sender, None),
+        }
+    }
+}
+
+///
+/// Distributed version of the greedy task allocation - each agent (identified by its id) runs
+/// this routine. Agents only evaluate the coalitions they lead, broadcast their best proposal and
+/// all agree on the best one proposed in each round. All agents return the same allocation - or
+/// time out if some agent does not propose in time.
+///
+#[cfg(feature = "multi_agent")]
+pub fn solve_distributed<A: messaging::Agent>(
+    agent: &A,
+    id: usize,
+    tasks: &[Task],
+    agents: &[Agent],
+    max_size: usize,
+    config: &Config,
+) -> Result<Allocation, Error> {
+    let deadline = time::Instant::now() + time::Duration::from_millis(config.timeout);
+    let mut open: Vec<usize> = (0..tasks.len()).collect();
+    let mut available: Vec<usize> = (0..agents.len()).collect();
+    let mut res = Allocation::default();
+    let mut inbox: collections::HashMap<usize, collections::HashMap<usize, Option<Proposal>>> =
+        collections::HashMap::new();
+
+    for round in 0.. {
+        if open.is_empty() {
+            break;
+        }
+        let own = best_proposal(tasks, agents, &open, &available, max_size, Some(id));
+        agent.broadcast(&Message::new(round, id, own.clone()).encode());
+        inbox.entry(round).or_default().insert(id, own);
+
+        // wait for the proposals of all other agents.
+        while inbox[&round].len() < agents.len() {
+            if time::Instant::now() >= deadline {
+                return Err(Error::Timeout);
+            }
+            let revd = agent.retrieve();
+            if revd.is_empty() {
+                thread::sleep(time::Duration::from_millis(config.poll_interval));
+            }
+            for msg in revd {
+                if let Some((r, sender, proposal)) = Message::decode(&msg).map(Message::unpack) {
+                    inbox.entry(r).or_default().insert(sender, proposal);
+                }
+            }
+        }
+
+        let mut best: Option<Proposal> = None;
+        for proposal in inbox.remove(&round).unwrap().into_values().flatten() {
+            if best.as_ref().is_none_or(|b| better(&proposal, b)) {
+                best = Some(proposal);
+            }
+        }
+        match best {
+            Some(proposal) => commit(proposal, &mut open, &mut available, &mut res),
+            None => break,
+        }
+    }
+    res.unassigned = open;
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "multi_agent")]
+    use std::thread;
+
+    #[cfg(feature = "multi_agent")]
+    use crate::bus;
+    use crate::task_allocation;
+
+    fn agent(capabilities: Vec<f64>, cost: f64) -> task_allocation::Agent {
+        task_allocation::Agent { capabilities, cost }
+    }
+
+    fn task(requirements: Vec<f64>, value: f64) -> task_allocation::Task {
+        task_allocation::Task {
+            requirements,
+            value,
+        }
+    }
+
+    /// Robots with arms (1st capability) & sensors (2nd) - tasks need different combinations.
+    fn warehouse() -> (Vec<task_allocation::Task>, Vec<task_allocation::Agent>) {
+        let tasks = vec![
+            task(vec![2.0, 0.0], 5.0),
+            task(vec![1.0, 1.0], 6.0),
+            task(vec![0.0, 2.0], 3.0),
+            task(vec![3.0, 3.0], 20.0),
+        ];
+        let agents = vec![
+            agent(vec![1.0, 0.0], 1.0),
+            agent(vec![1.0, 1.0], 2.0),
+            agent(vec![0.0, 1.0], 1.0),
+            agent(vec![2.0, 0.0], 2.0),
+            agent(vec![0.0, 1.0], 0.5),
+        ];
+        (tasks, agents)
+    }
+
+    #[cfg(feature = "multi_agent")]
+    fn run_distributed(max_size: usize) -> Vec<task_allocation::Allocation> {
+        let n_agents = warehouse().1.len();
+        let mut handles = Vec::new();
+        for agent in bus::connect(n_agents, bus::Config::default()) {
+            let id = agent.id();
+            handles.push(thread::spawn(move || {
+                let (tasks, agents) = warehouse();
+                let config = task_allocation::Config::default();
+                task_allocation::solve_distributed(&agent, id, &tasks, &agents, max_size, &config)
+                    .unwrap()
+            }));
+        }
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_capable_for_success() {
+        let (tasks, agents) = warehouse();
+        task_allocation::capable(&tasks[0], &agents, &[0, 1]);
+    }
+
+    #[test]
+    fn test_best_proposal_for_success() {
+        let (tasks, agents) = warehouse();
+        task_allocation::best_proposal(&tasks, &agents, &[0, 1], &[0, 1, 2], 2, None);
+    }
+
+    #[test]
+    fn test_solve_for_success() {
+        let (tasks, agents) = warehouse();
+        task_allocation::solve(&tasks, &agents, 3);
+    }
+
+    #[cfg(feature = "multi_agent")]
+    #[test]
+    fn test_solve_distributed_for_success() {
+        run_distributed(2);
+    }
+
+    // Test for failure.
+
+    #[test]
+    fn test_solve_for_failure() {
+        // no agent can perform the task.
+        let res = task_allocation::solve(&[task(vec![1.0], 5.0)], &[agent(vec![0.5], 1.0)], 3);
+        assert_eq!(res.assignments.len(), 0);
+        assert_eq!(res.unassigned, vec![0]);
+
+        // not worth it.
+        let res = task_allocation::solve(&[task(vec![1.0], 0.5)], &[agent(vec![1.0], 1.0)], 3);
+        assert_eq!(res.unassigned, vec![0]);
+        assert_eq!(res.value, 0.0);
+    }
+
+    #[cfg(feature = "multi_agent")]
+    #[test]
+    fn test_decode_for_failure() {
+        let decode = task_allocation::Message::decode;
+        assert_eq!(decode("foo"), None);
+        assert_eq!(decode("pass;1"), None);
+        assert_eq!(decode("propose;1;2;3"), None);
+        assert_eq!(decode("propose;1;2;a;1.0;1"), None);
+        assert_eq!(decode("1;2;3;1.0;1"), None);
+    }
+
+    #[cfg(feature = "multi_agent")]
+    #[test]
+    fn test_solve_distributed_for_failure() {
+        // the other agent never proposes.
+        let (tasks, agents) = warehouse();
+        let agent = bus::connect(1, bus::Config::default()).pop().unwrap();
+        let config = task_allocation::Config {
+            poll_interval: 5,
+            timeout: 50,
+        };
+        let res = task_allocation::solve_distributed(&agent, 0, &tasks, &agents[..2], 2, &config);
+        assert_eq!(res, Err(task_allocation::Error::Timeout));
+        assert_eq!(
+            task_allocation::Error::Timeout.to_string(),
+            "agents did not propose in time"
+        );
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_better_for_sanity() {
+        let proposal = |task, members: Vec<usize>, benefit| task_allocation::Proposal {
+            task,
+            members,
+            benefit,
+        };
+        assert!(task_allocation::better(
+            &proposal(1, vec![0, 1], 2.0),
+            &proposal(0, vec![0], 1.0)
+        ));
+        assert!(task_allocation::better(
+            &proposal(1, vec![2], 1.0),
+            &proposal(0, vec![0, 1], 1.0)
+        ));
+        assert!(task_allocation::better(
+            &proposal(0, vec![2], 1.0),
+            &proposal(1, vec![0], 1.0)
+        ));
+        assert!(!task_allocation::better(
+            &proposal(0, vec![0], 1.0),
+            &proposal(0, vec![0], 1.0)
+        ));
+    }
+
+    #[test]
+    fn test_capable_for_sanity() {
+        let (tasks, agents) = warehouse();
+        assert!(task_allocation::capable(&tasks[0], &agents, &[3]));
+        assert!(task_allocation::capable(&tasks[0], &agents, &[0, 1]));
+        assert!(!task_allocation::capable(&tasks[0], &agents, &[0, 2]));
+
+        // missing capabilities count as 0.
+        assert!(!task_allocation::capable(
+            &task(vec![0.0, 0.0, 1.0], 1.0),
+            &agents,
+            &[0, 1, 2, 3, 4]
+        ));
+        assert!(task_allocation::capable(&task(vec![], 1.0), &agents, &[0]));
+    }
+
+    #[test]
+    fn test_best_proposal_for_sanity() {
+        let (tasks, agents) = warehouse();
+        let all = [0, 1, 2, 3, 4];
+
+        // big task done by the cheapest capable coalition.
+        let res =
+            task_allocation::best_proposal(&tasks, &agents, &[0, 1, 2, 3], &all, 5, None).unwrap();
+        assert_eq!(res.task, 3);
+        assert_eq!(res.members, vec![1, 2, 3, 4]);
+        assert_eq!(res.benefit, 14.5);
+
+        // coalitions are limited in size.
+        let res =
+            task_allocation::best_proposal(&tasks, &agents, &[0, 1, 2, 3], &all, 2, None).unwrap();
+        assert_eq!(res.task, 1);
+        assert_eq!(res.members, vec![0, 4]);
+
+        // only coalitions led by the agent.
+        let res = task_allocation::best_proposal(&tasks, &agents, &[0, 1, 2, 3], &all, 5, Some(1))
+            .unwrap();
+        assert_eq!(res.members, vec![1, 2, 3, 4]);
+        let res =
+            task_allocation::best_proposal(&tasks, &agents, &[0, 1, 2], &all, 5, Some(3)).unwrap();
+        assert_eq!(res.members, vec![3, 4]);
+        assert_eq!(
+            task_allocation::best_proposal(&tasks, &agents, &[2], &all, 5, Some(4)),
+            None
+        );
+        assert_eq!(
+            task_allocation::best_proposal(&tasks, &agents, &[0], &[1, 2], 5, Some(0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_solve_for_sanity() {
+        let (tasks, agents) = warehouse();
+        let res = task_allocation::solve(&tasks, &agents, 5);
+        assert_eq!(res.assignments, vec![(3, vec![1, 2, 3, 4])]);
+        assert_eq!(res.unassigned, vec![0, 1, 2]);
+        assert_eq!(res.value, 14.5);
+
+        // small coalitions only.
+        let res = task_allocation::solve(&tasks, &agents, 2);
+        assert_eq!(res.assignments.len(), 3);
+        assert_eq!(res.unassigned, vec![3]);
+        let mut used: Vec<usize> = res.assignments.iter().flat_map(|a| a.1.clone()).collect();
+        used.sort_unstable();
+        used.dedup();
+        assert_eq!(
+            used.len(),
+            res.assignments.iter().map(|a| a.1.len()).sum::<usize>()
+        );
+    }
+
+    #[cfg(feature = "multi_agent")]
+    #[test]
+    fn test_decode_for_sanity() {
+        let proposal = Some(task_allocation::Proposal {
+            task: 2,
+            members: vec![0, 3],
+            benefit: 1.25,
+        });
+        let msg = task_allocation::Message::new(4, 3, proposal);
+        assert_eq!(msg.encode(), "propose;4;3;2;1.25;0,3");
+        assert_eq!(task_allocation::Message::decode(&msg.encode()), Some(msg));
+        let msg = task_allocation::Message::new(0, 1, None);
+        assert_eq!(msg.encode(), "pass;0;1");
+        assert_eq!(
+            task_allocation::Message::decode(&msg.encode()).map(task_allocation::Message::unpack),
+            Some((0, 1, None))
+        );
+    }
+
+    #[cfg(feature = "multi_agent")]
+    #[test]
+    fn test_solve_distributed_for_sanity() {
+        // all agents agree with the centralized version.
+        for max_size in [1, 2, 5] {
+            let (tasks, agents) = warehouse();
+            let expected = task_allocation::solve(&tasks, &agents, max_size);
+            for res in run_distributed(max_size) {
+                assert_eq!(res, expected);
+            }
+        }
+    }
+}
@@ -1,4 +1,11 @@
 use std::cmp;
+use std::collections;
+use std::hash;
+#[cfg(test)]
+use std::vec;
+
+#[cfg(test)]
+use crate::planner;
 
 /// rhs & g data for A* related searches...
 pub struct StateData {
@@ -47,6 +54,110 @@ impl<S> Ord for HeapEntry<S> {
     }
 }
 
+/// Small xorshift based pseudo random number generator - deterministic for a given seed.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // state must never be 0.
+        let state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        Rng {
+            state: if state == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                state
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Returns a number in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a number in [0, n).
+    pub fn gen_range(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Maps values to dense ids - for problem spaces whose states are too big to be `Copy`.
+pub struct Interner<T> {
+    items: Vec<T>,
+    ids: collections::HashMap<T, usize>,
+}
+
+impl<T: Clone + Eq + hash::Hash> Interner<T> {
+    pub fn new() -> Interner<T> {
+        Interner {
+            items: Vec::new(),
+            ids: collections::HashMap::new(),
+        }
+    }
+
+    /// Returns the id of the value - the same value always gets the same id.
+    pub fn intern(&mut self, item: T) -> usize {
+        if let Some(id) = self.ids.get(&item) {
+            return *id;
+        }
+        let id = self.items.len();
+        self.ids.insert(item.clone(), id);
+        self.items.push(item);
+        id
+    }
+
+    pub fn get(&self, id: usize) -> &T {
+        &self.items[id]
+    }
+}
+
+/// 4-connected grid with obstacles - shared by the tests of the pathfinding algorithms.
+#[cfg(test)]
+pub struct Grid {
+    pub width: i32,
+    pub height: i32,
+    pub blocked: Vec<(i32, i32)>,
+}
+
+#[cfg(test)]
+impl planner::ProblemSpace for Grid {
+    type State = (i32, i32);
+    type Iter = vec::IntoIter<(Self::State, f64)>;
+
+    fn heuristic(&self, s: &Self::State, g: &Self::State) -> f64 {
+        ((s.0 - g.0).abs() + (s.1 - g.1).abs()) as f64
+    }
+
+    fn succ(&self, s: &Self::State) -> Self::Iter {
+        let mut res = Vec::new();
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let n = (s.0 + dx, s.1 + dy);
+            if n.0 >= 0
+                && n.0 < self.width
+                && n.1 >= 0
+                && n.1 < self.height
+                && !self.blocked.contains(&n)
+            {
+                res.push((n, 1.0));
+            }
+        }
+        res.into_iter()
+    }
+
+    fn pred(&self, s: &Self::State) -> Self::Iter {
+        self.succ(s)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections;
@@ -76,4 +187,32 @@ mod tests {
         assert_eq!(open.pop().unwrap().keys, (1.0, 2.0));
         assert_eq!(open.pop().unwrap().keys, (2.0, 1.0));
     }
+
+    #[test]
+    fn test_interner_for_sanity() {
+        let mut interner = util::Interner::new();
+        assert_eq!(interner.intern(vec![1u64, 2]), 0);
+        assert_eq!(interner.intern(vec![3u64]), 1);
+        assert_eq!(interner.intern(vec![1u64, 2]), 0);
+        assert_eq!(interner.get(1), &vec![3u64]);
+    }
+
+    #[test]
+    fn test_rng_for_sanity() {
+        let mut rng_0 = util::Rng::new(42);
+        let mut rng_1 = util::Rng::new(42);
+        // same seed --> same sequence.
+        for _ in 0..10 {
+            assert_eq!(rng_0.next_u64(), rng_1.next_u64());
+        }
+        // values are within range.
+        for _ in 0..100 {
+            let val = rng_0.next_f64();
+            assert!((0.0..1.0).contains(&val));
+            assert!(rng_0.gen_range(3) < 3);
+        }
+        // state of 0 does not get stuck.
+        let mut rng_2 = util::Rng::new(0x9E37_79B9_7F4A_7C15);
+        assert_ne!(rng_2.next_u64(), rng_2.next_u64());
+    }
 }
@@ -0,0 +1,486 @@
+use std::collections;
+use std::error;
+use std::fmt;
+
+use crate::pddl;
+use crate::planner;
+
+///
+/// Reasons a plan is invalid.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error<S> {
+    /// The state at the given step is not a successor of the state before.
+    IllegalStep { step: usize, from: S, to: S },
+    /// The plan ends in the given state instead of the goal.
+    GoalNotReached(S),
+    /// The action at the given step can't be applied - with the reason why.
+    Inapplicable {
+        step: usize,
+        action: String,
+        reason: String,
+    },
+    /// Goal conditions which don't hold at the end of the plan.
+    GoalNotSatisfied(Vec<String>),
+    /// The action at the given step is not an action of the domain - or refers to unknown objects.
+    UnknownAction { step: usize, action: String },
+    /// The domain & problem can't be grounded.
+    Malformed(pddl::Error),
+}
+
+impl<S: fmt::Debug> fmt::Display for Error<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::IllegalStep { step, from, to } => write!(
+                f,
+                "step {}: {:?} is not a successor of {:?}",
+                step, to, from
+            ),
+            Error::GoalNotReached(state) => write!(f, "plan ends in {:?} - not the goal", state),
+            Error::Inapplicable {
+                step,
+                action,
+                reason,
+            } => write!(
+                f,
+                "step {}: {} is not applicable - {}",
+                step, action, reason
+            ),
+            Error::GoalNotSatisfied(conditions) => {
+                write!(f, "goal not satisfied: {}", conditions.join(", "))
+            }
+            Error::UnknownAction { step, action } => {
+                write!(
+                    f,
+                    "step {}: {} is not an action of the domain",
+                    step, action
+                )
+            }
+            Error::Malformed(err) => write!(f, "can't ground the task - {}", err),
+        }
+    }
+}
+
+impl<S: fmt::Debug> error::Error for Error<S> {}
+
+///
+/// Validate a plan as returned by the solvers - every state needs to be a successor of the one
+/// before & the last one the goal. The plan does not include the start state; a leading start
+/// state is a step which stays in it. Returns the recomputed cost of the plan - for repeated
+/// transitions the cheapest one counts.
+///
+pub fn validate<PS: planner::ProblemSpace>(
+    ps: &PS,
+    start: PS::State,
+    goal: PS::State,
+    plan: &[PS::State],
+) -> Result<f64, Error<PS::State>> {
+    let mut cost = 0.0;
+    let mut curr = start;
+    for (step, next) in plan.iter().enumerate() {
+        let transition = ps
+            .succ(&curr)
+            .filter(|(s, _)| s == next)
+            .map(|(_, c)| c)
+            .fold(f64::INFINITY, f64::min);
+        if transition.is_infinite() {
+            return Err(Error::IllegalStep {
+                step,
+                from: curr,
+                to: *next,
+            });
+        }
+        cost += transition;
+        curr = *next;
+    }
+    if curr != goal {
+        return Err(Error::GoalNotReached(curr));
+    }
+    Ok(cost)
+}
+
+///
+/// Validate a PDDL plan for the problem - one ground action per line, e.g.
+/// `(drive van depot shop)`. Comments starting with `;`, step prefixes such as `0:` & durations
+/// such as `[1]` are ignored. Returns the cost of the plan.
+///
+pub fn validate_pddl(
+    domain: &pddl::Domain,
+    problem: &pddl::Problem,
+    plan: &str,
+) -> Result<f64, Error<usize>> {
+    let task = pddl::Task::ground(domain, problem).map_err(Error::Malformed)?;
+    let mut state = task.initial_state();
+    let mut cost = 0.0;
+    for (step, action) in actions(plan).into_iter().enumerate() {
+        let op = match task.operators.iter().position(|op| op.name == action) {
+            Some(op) => op,
+            None => return Err(pruned(domain, problem, &task, step, action)),
+        };
+        let facts = task.facts_of(state);
+        let operator = &task.operators[op];
+        let violated = unsatisfied(&task, &facts, &operator.pre, &operator.pre_neg);
+        if !violated.is_empty() {
+            return Err(Error::Inapplicable {
+                step,
+                action,
+                reason: format!("precondition not satisfied: {}", violated.join(", ")),
+            });
+        }
+        state = task.apply(state, op).unwrap();
+        cost += operator.cost;
+    }
+    let violated = unsatisfied(&task, &task.facts_of(state), &task.goal, &task.goal_neg);
+    if !violated.is_empty() {
+        return Err(Error::GoalNotSatisfied(violated));
+    }
+    Ok(cost)
+}
+
+/// Ground actions of the plan - normalized to match the operator names.
+fn actions(plan: &str) -> Vec<String> {
+    plan.lines()
+        .map(|line| line.split(';').next().unwrap().trim().to_lowercase())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let start = line.find('(').unwrap_or(0);
+            let end = line.rfind(')').map_or(line.len(), |i| i + 1);
+            let inner = line[start..end]
+                .trim_start_matches('(')
+                .trim_end_matches(')');
+            format!(
+                "({})",
+                inner.split_whitespace().collect::<Vec<_>>().join(" ")
+            )
+        })
+        .collect()
+}
+
+/// Why the ground action is not an operator of the task - it's unknown, or got pruned while
+/// grounding as some precondition can never hold.
+fn pruned(
+    domain: &pddl::Domain,
+    problem: &pddl::Problem,
+    task: &pddl::Task,
+    step: usize,
+    action: String,
+) -> Error<usize> {
+    let inapplicable = |action: String, reason: String| Error::Inapplicable {
+        step,
+        action,
+        reason,
+    };
+    let mut names = action[1..action.len() - 1]
+        .split_whitespace()
+        .map(String::from);
+    let name = names.next().unwrap_or_default();
+    let args: Vec<String> = names.collect();
+    let schema = match domain.actions.iter().find(|a| a.name == name) {
+        Some(schema) if schema.parameters.len() == args.len() => schema,
+        _ => return Error::UnknownAction { step, action },
+    };
+    let objects = domain.constants.iter().chain(&problem.objects);
+    for (arg, (_, ty)) in args.iter().zip(&schema.parameters) {
+        match objects.clone().find(|o| o.0 == *arg) {
+            None => return Error::UnknownAction { step, action },
+            Some(object) if !domain.is_a(&object.1, ty) => {
+                return inapplicable(action, format!("{} is not of type {}", arg, ty))
+            }
+            Some(_) => {}
+        }
+    }
+
+    // static literals hold as given in the initial state; unreachable facts never hold.
+    let fluents: collections::HashSet<&str> = domain
+        .actions
+        .iter()
+        .flat_map(|a| a.effects.iter().map(|e| e.literal.atom.predicate.as_str()))
+        .collect();
+    let bind = |atom: &pddl::Atom| pddl::Atom {
+        predicate: atom.predicate.clone(),
+        args: atom
+            .args
+            .iter()
+            .map(
+                |arg| match schema.parameters.iter().position(|p| p.0 == *arg) {
+                    Some(i) => args[i].clone(),
+                    None => arg.clone(),
+                },
+            )
+            .collect(),
+    };
+    let mut statics = Vec::new();
+    let mut unreachable = Vec::new();
+    for literal in &schema.precondition {
+        let atom = bind(&literal.atom);
+        if fluents.contains(atom.predicate.as_str()) {
+            if literal.positive && !task.facts.contains(&atom.to_string()) {
+                unreachable.push(atom.to_string());
+            }
+            continue;
+        }
+        let holds = if atom.predicate == "=" {
+            atom.args[0] == atom.args[1]
+        } else {
+            problem.init.contains(&atom)
+        };
+        match (holds, literal.positive) {
+            (false, true) => statics.push(atom.to_string()),
+            (true, false) => statics.push(format!("(not {})", atom)),
+            _ => {}
+        }
+    }
+    if !statics.is_empty() {
+        inapplicable(
+            action,
+            format!("static precondition not satisfied: {}", statics.join(", ")),
+        )
+    } else if !unreachable.is_empty() {
+        inapplicable(
+            action,
+            format!("precondition never holds: {}", unreachable.join(", ")),
+        )
+    } else {
+        inapplicable(action, String::from("pruned while grounding"))
+    }
+}
+
+/// Conditions not holding given the facts - negated ones as `(not <fact>)`.
+fn unsatisfied(task: &pddl::Task, facts: &[&str], pos: &[usize], neg: &[usize]) -> Vec<String> {
+    let pos = pos
+        .iter()
+        .map(|f| task.facts[*f].as_str())
+        .filter(|f| !facts.contains(f))
+        .map(String::from);
+    let neg = neg
+        .iter()
+        .map(|f| task.facts[*f].as_str())
+        .filter(|f| facts.contains(f))
+        .map(|f| format!("(not {})", f));
+    pos.chain(neg).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec;
+
+    use crate::pddl;
+    use crate::planner;
+    use crate::validator;
+
+    const DOMAIN: &str = "
+        (define (domain door)
+          (:requirements :strips :negative-preconditions)
+          (:predicates (open) (inside))
+          (:action open-door :parameters () :precondition (not (open)) :effect (open))
+          (:action enter :parameters () :precondition (open) :effect (inside))
+          (:action close-door :parameters () :precondition (open) :effect (not (open))))";
+
+    const PROBLEM: &str = "
+        (define (problem get-in)
+          (:domain door)
+          (:init)
+          (:goal (and (inside) (not (open)))))";
+
+    const ROOMS: &str = "
+        (define (domain rooms)
+          (:requirements :strips :typing)
+          (:types room)
+          (:predicates (at ?r - room) (door ?a ?b - room) (lit ?r - room))
+          (:action go
+            :parameters (?a ?b - room)
+            :precondition (and (at ?a) (door ?a ?b) (lit ?b))
+            :effect (and (at ?b) (not (at ?a)))))";
+
+    const TOUR: &str = "
+        (define (problem tour)
+          (:domain rooms)
+          (:objects hall kitchen cellar attic - room lamp)
+          (:init (at hall) (door hall kitchen) (door attic hall) (lit hall) (lit kitchen))
+          (:goal (at kitchen)))";
+
+    /// States on a line - moving forward costs 0.5, backward 1.5 & staying 2.0.
+    struct Line {}
+
+    impl planner::ProblemSpace for Line {
+        type State = i32;
+        type Iter = vec::IntoIter<(Self::State, f64)>;
+
+        fn heuristic(&self, _: &Self::State, _: &Self::State) -> f64 {
+            0.0
+        }
+
+        fn succ(&self, state: &Self::State) -> Self::Iter {
+            vec![(state + 1, 0.5), (state - 1, 1.5), (*state, 2.0)].into_iter()
+        }
+
+        fn pred(&self, state: &Self::State) -> Self::Iter {
+            vec![(state - 1, 0.5), (state + 1, 1.5), (*state, 2.0)].into_iter()
+        }
+    }
+
+    fn door() -> (pddl::Domain, pddl::Problem) {
+        (
+            pddl::Domain::parse(DOMAIN).unwrap(),
+            pddl::Problem::parse(PROBLEM).unwrap(),
+        )
+    }
+
+    // Test for success.
+
+    #[test]
+    fn test_validate_for_success() {
+        validator::validate(&Line {}, 0, 2, &[1, 2]).unwrap();
+    }
+
+    #[test]
+    fn test_validate_pddl_for_success() {
+        let (domain, problem) = door();
+        validator::validate_pddl(&domain, &problem, "(open-door)\n(enter)\n(close-door)").unwrap();
+    }
+
+    // Test for failure.
+
+    #[test]
+    fn test_validate_for_failure() {
+        assert_eq!(
+            validator::validate(&Line {}, 0, 2, &[1, 3]),
+            Err(validator::Error::IllegalStep {
+                step: 1,
+                from: 1,
+                to: 3
+            })
+        );
+        assert_eq!(
+            validator::validate(&Line {}, 0, 2, &[1]),
+            Err(validator::Error::GoalNotReached(1))
+        );
+        assert_eq!(
+            validator::validate(&Line {}, 0, 2, &[]),
+            Err(validator::Error::GoalNotReached(0))
+        );
+    }
+
+    #[test]
+    fn test_validate_pddl_for_failure() {
+        let (domain, problem) = door();
+        let validate = |plan: &str| validator::validate_pddl(&domain, &problem, plan);
+        assert_eq!(
+            validate("(enter)"),
+            Err(validator::Error::Inapplicable {
+                step: 0,
+                action: String::from("(enter)"),
+                reason: String::from("precondition not satisfied: (open)")
+            })
+        );
+        assert_eq!(
+            validate("(open-door)\n(open-door)"),
+            Err(validator::Error::Inapplicable {
+                step: 1,
+                action: String::from("(open-door)"),
+                reason: String::from("precondition not satisfied: (not (open))")
+            })
+        );
+        assert_eq!(
+            validate("(open-door)\n(enter)"),
+            Err(validator::Error::GoalNotSatisfied(vec![String::from(
+                "(not (open))"
+            )]))
+        );
+        assert_eq!(
+            validate(""),
+            Err(validator::Error::GoalNotSatisfied(vec![String::from(
+                "(inside)"
+            )]))
+        );
+        assert_eq!(
+            validate("(fly)"),
+            Err(validator::Error::UnknownAction {
+                step: 0,
+                action: String::from("(fly)")
+            })
+        );
+
+        // domain & problem don't match.
+        let problem = pddl::Problem::parse(TOUR).unwrap();
+        let res = validator::validate_pddl(&domain, &problem, "");
+        assert!(matches!(res, Err(validator::Error::Malformed(_))));
+    }
+
+    // Test for sanity.
+
+    #[test]
+    fn test_validate_for_sanity() {
+        // recomputed cost - a leading start state is a step staying in it.
+        assert_eq!(validator::validate(&Line {}, 0, 2, &[1, 2]), Ok(1.0));
+        assert_eq!(validator::validate(&Line {}, 0, 2, &[0, 1, 2]), Ok(3.0));
+        assert_eq!(validator::validate(&Line {}, 0, 0, &[0]), Ok(2.0));
+        assert_eq!(validator::validate(&Line {}, 0, 0, &[-1, 0]), Ok(2.0));
+        assert_eq!(validator::validate(&Line {}, 0, 0, &[]), Ok(0.0));
+
+        // readable diagnostics.
+        let err = validator::validate(&Line {}, 0, 2, &[1, 3]).unwrap_err();
+        assert_eq!(err.to_string(), "step 1: 3 is not a successor of 1");
+        let err = validator::validate(&Line {}, 0, 2, &[1]).unwrap_err();
+        assert_eq!(err.to_string(), "plan ends in 1 - not the goal");
+    }
+
+    #[test]
+    fn test_validate_pddl_for_sanity() {
+        let (domain, problem) = door();
+        let task = pddl::Task::ground(&domain, &problem).unwrap();
+        let plan = "
+            ; plan found by hand.
+            0: (OPEN-DOOR) [1]
+            1: ( enter )
+            2: (close-door) ; done.
+            ; cost = 3 (unit cost)";
+        assert_eq!(validator::validate_pddl(&domain, &problem, plan), Ok(3.0));
+
+        // the states of the plan are valid for the task's problem space too.
+        let init = task.initial_state();
+        let s_1 = task.apply(init, 0).unwrap();
+        let s_2 = task.apply(s_1, 1).unwrap();
+        let s_3 = task.apply(s_2, 2).unwrap();
+        let plan = [s_1, s_2, s_3, task.goal_state()];
+        assert_eq!(
+            validator::validate(&task, init, task.goal_state(), &plan),
+            Ok(3.0)
+        );
+        let err = validator::validate(&task, init, task.goal_state(), &plan[..3]).unwrap_err();
+        assert_eq!(err, validator::Error::GoalNotReached(s_3));
+
+        // actions pruned while grounding - named by the failing literals of their schema.
+        let domain = pddl::Domain::parse(ROOMS).unwrap();
+        let problem = pddl::Problem::parse(TOUR).unwrap();
+        let validate = |plan: &str| validator::validate_pddl(&domain, &problem, plan);
+        assert_eq!(validate("(go hall kitchen)"), Ok(1.0));
+        let reason = |plan: &str| match validate(plan) {
+            Err(validator::Error::Inapplicable { reason, .. }) => reason,
+            res => panic!("unexpected result {:?}", res),
+        };
+        assert_eq!(
+            reason("(go hall cellar)"),
+            "static precondition not satisfied: (door hall cellar), (lit cellar)"
+        );
+        assert_eq!(
+            reason("(go attic hall)"),
+            "precondition never holds: (at attic)"
+        );
+        assert_eq!(reason("(go hall lamp)"), "lamp is not of type room");
+
+        // unknown actions - or unknown objects.
+        for plan in ["(go hall)", "(go hall garage)", "(walk hall kitchen)"] {
+            let err = validate(plan).unwrap_err();
+            assert!(matches!(
+                err,
+                validator::Error::UnknownAction { step: 0, .. }
+            ));
+        }
+        let err = validate("(go hall)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "step 0: (go hall) is not an action of the domain"
+        );
+    }
+}
@@ -565,3 +565,235 @@ fn test_multi_agent_example() {
         res2
     );
 }
+
+#[cfg(feature = "multi_agent")]
+#[test]
+fn test_multi_agent_optimality() {
+    use rusty_planner::planner::ProblemSpace;
+
+    /// Agent 0 can deliver directly but expensively, or hand over to agent 1 at location 2.
+    const ACTIONS: [(usize, i32, i32, f64); 6] = [
+        (0, 0, 9, 5.0),
+        (0, 0, 2, 1.0),
+        (1, 2, 3, 0.5),
+        (1, 3, 4, 0.5),
+        (1, 4, 5, 0.5),
+        (1, 5, 9, 0.5),
+    ];
+
+    /// Problem space of a single agent - or of all agents if no id is given.
+    struct Delivery {
+        agent_id: Option<usize>,
+    }
+
+    impl rusty_planner::planner::ProblemSpace for Delivery {
+        type State = i32;
+        type Iter = vec::IntoIter<(i32, f64)>;
+
+        fn heuristic(&self, _: &Self::State, _: &Self::State) -> f64 {
+            0.0
+        }
+
+        fn succ(&self, state: &Self::State) -> Self::Iter {
+            ACTIONS
+                .iter()
+                .filter(|a| self.agent_id.is_none_or(|id| id == a.0) && a.1 == *state)
+                .map(|a| (a.2, a.3))
+                .collect::<Vec<_>>()
+                .into_iter()
+        }
+
+        fn pred(&self, state: &Self::State) -> Self::Iter {
+            ACTIONS
+                .iter()
+                .filter(|a| self.agent_id.is_none_or(|id| id == a.0) && a.2 == *state)
+                .map(|a| (a.1, a.3))
+                .collect::<Vec<_>>()
+                .into_iter()
+        }
+    }
+
+    impl rusty_planner::planner::SharedStates for Delivery {
+        fn is_public(&self, state: &Self::State) -> bool {
+            *state == 2
+        }
+
+        fn serialize(&self, msg_type: u8, state: &Self::State, para: Vec<f64>) -> String {
+            let mut string_list: Vec<String> = vec![msg_type.to_string(), state.to_string()];
+            for item in para {
+                string_list.push(item.to_string());
+            }
+            string_list.join(";")
+        }
+
+        fn deserialize(&self, msg: String) -> (u8, Self::State, Vec<f64>) {
+            let data = msg.split(';').collect::<Vec<&str>>();
+            let para = data[2..].iter().map(|x| x.parse().unwrap()).collect();
+            (data[0].parse().unwrap(), data[1].parse().unwrap(), para)
+        }
+    }
+
+    let config = rusty_planner::mad_astar::Config {
+        poll_interval: 5,
+        timeout: Some(10000),
+        ..Default::default()
+    };
+    let problems = vec![
+        Delivery { agent_id: Some(0) },
+        Delivery { agent_id: Some(1) },
+    ];
+    let plan = rusty_planner::mad_astar::solve_in_process(
+        problems,
+        0,
+        9,
+        &config,
+        rusty_planner::bus::Config::default(),
+    )
+    .unwrap();
+
+    // Agent 0 finds the direct delivery first - but the handover is cheaper.
+    let centralised = Delivery { agent_id: None };
+    let mut cost = 0.0;
+    for step in &plan {
+        // each agent is capable of the step it is responsible for.
+        let ps = Delivery {
+            agent_id: Some(step.agent),
+        };
+        cost += ps.succ(&step.from).find(|s| s.0 == step.to).unwrap().1;
+    }
+    let mut dist = rusty_planner::cooperative::TrueDistance::new(9);
+    let states: Vec<i32> = plan.iter().map(|step| step.from).collect();
+    assert_eq!(states, vec![0, 2, 3, 4, 5]);
+    assert_eq!(cost, dist.distance(&centralised, &0));
+}
+
+#[cfg(feature = "multi_agent")]
+#[test]
+fn test_multi_agent_picker() {
+    use rusty_planner::mad_astar::Step;
+
+    /// Picker example - see example_4.
+    struct Picker {
+        pick_id: i32,
+    }
+
+    impl rusty_planner::planner::ProblemSpace for Picker {
+        type State = (i32, i32);
+        type Iter = vec::IntoIter<(Self::State, f64)>;
+
+        fn heuristic(&self, _: &Self::State, _: &Self::State) -> f64 {
+            0.0
+        }
+
+        fn succ(&self, state: &Self::State) -> Self::Iter {
+            match (*state, self.pick_id) {
+                ((0, 0), 0) => vec![((0, 1), 0.5), ((1, 1), 0.7)].into_iter(),
+                ((0, 1), 0) => vec![((0, 0), 0.5), ((0, 2), 0.5), ((1, 1), 0.5)].into_iter(),
+                ((0, 2), 0) => vec![((0, 1), 0.5), ((1, 1), 0.7)].into_iter(),
+                ((1, 1), 0) => vec![((0, 0), 0.7), ((0, 1), 0.5), ((0, 2), 0.7)].into_iter(),
+                ((1, 1), 1) => vec![((2, 1), 0.5)].into_iter(),
+                ((2, 0), 1) => vec![((2, 1), 0.5)].into_iter(),
+                ((2, 1), 1) => vec![((2, 0), 0.5), ((2, 2), 0.5), ((1, 1), 0.5)].into_iter(),
+                ((2, 2), 1) => vec![((2, 1), 0.5)].into_iter(),
+                _ => vec![].into_iter(),
+            }
+        }
+
+        fn pred(&self, state: &Self::State) -> Self::Iter {
+            self.succ(state)
+        }
+    }
+
+    impl rusty_planner::planner::SharedStates for Picker {
+        fn is_public(&self, state: &Self::State) -> bool {
+            matches!(state, (1, 1))
+        }
+
+        fn serialize(&self, msg_type: u8, state: &Self::State, para: Vec<f64>) -> String {
+            let mut string_list: Vec<String> = vec![
+                msg_type.to_string(),
+                state.0.to_string(),
+                state.1.to_string(),
+            ];
+            for item in para {
+                string_list.push(item.to_string());
+            }
+            string_list.join(";")
+        }
+
+        fn deserialize(&self, msg: String) -> (u8, Self::State, Vec<f64>) {
+            let data = msg.split(';').collect::<Vec<&str>>();
+            let state = (data[1].parse().unwrap(), data[2].parse().unwrap());
+            let para = data[3..].iter().map(|x| x.parse().unwrap()).collect();
+            (data[0].parse().unwrap(), state, para)
+        }
+    }
+
+    let config = rusty_planner::mad_astar::Config {
+        poll_interval: 5,
+        timeout: Some(10000),
+        ..Default::default()
+    };
+    let expected = vec![
+        Step {
+            agent: 0,
+            from: (0, 0),
+            to: (1, 1),
+        },
+        Step {
+            agent: 1,
+            from: (1, 1),
+            to: (2, 1),
+        },
+        Step {
+            agent: 1,
+            from: (2, 1),
+            to: (2, 2),
+        },
+    ];
+
+    // package moves from picker 0 to picker 1...
+    let problems = vec![Picker { pick_id: 0 }, Picker { pick_id: 1 }];
+    let plan = rusty_planner::mad_astar::solve_in_process(
+        problems,
+        (0, 0),
+        (2, 2),
+        &config,
+        rusty_planner::bus::Config::default(),
+    );
+    assert_eq!(plan, Ok(expected.clone()));
+
+    // ...also on a lossless but slow network - every picker knows the full plan.
+    let bus_config = rusty_planner::bus::Config {
+        latency: (0, 10),
+        seed: 1,
+        ..Default::default()
+    };
+    let mut handles = Vec::new();
+    for endpoint in rusty_planner::bus::connect(2, bus_config) {
+        let config = rusty_planner::mad_astar::Config {
+            ring: Some((endpoint.id(), 2)),
+            ..config.clone()
+        };
+        handles.push(thread::spawn(move || {
+            let ps = Picker {
+                pick_id: endpoint.id() as i32,
+            };
+            rusty_planner::mad_astar::solve_joint(&endpoint, &ps, (0, 0), (2, 2), &config)
+        }));
+    }
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), Ok(expected.clone()));
+    }
+
+    // goal which picker 1 can not reach.
+    let problems = vec![Picker { pick_id: 0 }, Picker { pick_id: 1 }];
+    let plan = rusty_planner::mad_astar::solve_in_process(
+        problems,
+        (0, 0),
+        (3, 3),
+        &config,
+        rusty_planner::bus::Config::default(),
+    );
+    assert_eq!(plan, Err(rusty_planner::mad_astar::Error::NoPlan));
+}